@@ -0,0 +1,307 @@
+use crate::{
+    adopt, artifact,
+    cli::{self, ActivateCommand, CommitCommand, Config, InstallCommand},
+    events, failure,
+    hooks::{self, Hook},
+    journal::Journal,
+    lint, list, local, lock, manifest,
+    metrics::Report,
+    otlp, remote, snapshot, state, state_db, update,
+};
+use anyhow::{anyhow, Result};
+use semver::Version;
+use std::path::PathBuf;
+
+/// Installs a release without switching to it, so the download and preparation happen outside the maintenance window.
+pub async fn install(config: &mut Config, command: &InstallCommand) -> Result<()> {
+    let config = &*prepare(config)?;
+    let http = remote::client(config)?;
+    adopt::ensure_layout(config).await?;
+    let _lock = lock::acquire(config).await?;
+
+    let journal = Journal::default();
+    let started_at = std::time::Instant::now();
+    let mut report = Report::default();
+    let result = update::rollback_on_abort(
+        config,
+        &journal,
+        install_release(config, &http, command, &journal, &mut report),
+    )
+    .await;
+
+    finish(config, &http, "install", report, started_at, result).await
+}
+
+/// Switches to an installed release, `install`ed earlier or kept from an earlier update.
+pub async fn activate(config: &mut Config, command: &ActivateCommand) -> Result<()> {
+    let config = &*prepare(config)?;
+    let http = remote::client(config)?;
+
+    let version = match &command.version {
+        Some(version) => version.clone(),
+        None => list::installed(config)
+            .await?
+            .into_iter()
+            .map(|(version, _)| version)
+            .next()
+            .ok_or_else(|| anyhow!("no release is installed"))?,
+    };
+
+    switch(config, &http, "activate", &version).await
+}
+
+/// Switches to the release staged by `--stage-only`. The token makes sure it is the release the caller staged,
+/// and not one staged by a later run for a newer version.
+pub async fn commit(config: &mut Config, command: &CommitCommand) -> Result<()> {
+    let config = &*prepare(config)?;
+    let http = remote::client(config)?;
+
+    let state = state::State::load(config).await?;
+    let version = match &state.staged {
+        Some(staged) if staged.token == command.token => Version::parse(&staged.version)?,
+        Some(staged) => {
+            return Err(anyhow!(
+                "token doesn't match the one printed when release {} was staged - check that the token is of this host",
+                staged.version
+            ))
+        }
+        None => {
+            return Err(anyhow!(
+                "no release is staged - stage one with `teamspeak-updater --stage-only update`"
+            ))
+        }
+    };
+
+    switch(config, &http, "commit", &version).await?;
+
+    let mut state = state::State::load(config).await?;
+    if state
+        .staged
+        .take_if(|staged| staged.version == version.to_string())
+        .is_some()
+    {
+        state.save(config).await?;
+    }
+
+    Ok(())
+}
+
+/// Switches back to the release the symlink pointed to before the last switch, e.g. when the new version misbehaves.
+pub async fn deactivate(config: &mut Config) -> Result<()> {
+    let config = &*prepare(config)?;
+    let http = remote::client(config)?;
+
+    let previous_path = previous_release(config).await?.ok_or_else(|| {
+        anyhow!(
+            "no earlier release to return to - no old symlink next to {} points to one",
+            config.symlink_path.to_string_lossy()
+        )
+    })?;
+    let version = match manifest::read(&previous_path).await? {
+        Some(manifest) => Version::parse(&manifest.version)?,
+        None => previous_path
+            .file_name()
+            .and_then(|name| local::release_version(config, &name.to_string_lossy()))
+            .ok_or_else(|| {
+                anyhow!(
+                    "no version in the name of release directory {}",
+                    previous_path.to_string_lossy()
+                )
+            })?,
+    };
+    println!(
+        "↩️ Returning to release {} ({})",
+        version,
+        previous_path.to_string_lossy()
+    );
+
+    switch(config, &http, "deactivate", &version).await
+}
+
+fn prepare(config: &mut Config) -> Result<&mut Config> {
+    cli::print_header();
+    lint::validate(config)?;
+
+    if local::in_place_dir(config).is_some() {
+        return Err(anyhow!(
+            "in-place installs keep a single release - use `update`, which installs and activates it at once"
+        ));
+    }
+
+    config.print_summary();
+    lint::check(config)?;
+
+    Ok(config)
+}
+
+async fn install_release(
+    config: &Config,
+    http: &reqwest::Client,
+    command: &InstallCommand,
+    journal: &Journal,
+    report: &mut Report,
+) -> Result<()> {
+    let installed_version = local::installed_version(config).await?;
+    let version = match &command.version {
+        Some(version) => version.clone(),
+        None => remote::latest_version(config, http).await?,
+    };
+    report.installed_version = installed_version.clone();
+    report.published_version = Some(version.clone());
+
+    let mut state = state::State::load(config).await?;
+    if installed_version.as_ref() == Some(&version) {
+        println!("✅ TeamSpeak {} is installed and active already", version);
+        return Ok(());
+    }
+    if update::is_staged(config, &state, &version).await? {
+        println!(
+            "✅ TeamSpeak {} is installed already - switch to it with `teamspeak-updater activate {}`",
+            version, version
+        );
+        return Ok(());
+    }
+
+    println!("📥 Installing TeamSpeak {}", version);
+    hooks::run(config, Hook::PreDownload, report, None).await?;
+    let download = update::download_release(
+        config,
+        http,
+        journal,
+        &mut state,
+        installed_version.as_ref(),
+        &version,
+    )
+    .await?;
+    report.download_bytes = download.size;
+    update::stage_release(
+        config,
+        report,
+        installed_version.as_ref(),
+        &version,
+        &download,
+    )
+    .await?;
+    artifact::record(config, http, &download, &version).await;
+
+    println!();
+    println!(
+        "✅ TeamSpeak {} installed into {} - switch to it with `teamspeak-updater activate {}` ✅",
+        version,
+        local::release_path(config, &version)?.to_string_lossy(),
+        version
+    );
+
+    Ok(())
+}
+
+async fn switch(
+    config: &Config,
+    http: &reqwest::Client,
+    name: &str,
+    version: &Version,
+) -> Result<()> {
+    let journal = Journal::default();
+    let started_at = std::time::Instant::now();
+    let mut report = Report::default();
+    let result = update::rollback_on_abort(
+        config,
+        &journal,
+        switch_release(config, version, &journal, &mut report),
+    )
+    .await;
+
+    finish(config, http, name, report, started_at, result).await
+}
+
+async fn switch_release(
+    config: &Config,
+    version: &Version,
+    journal: &Journal,
+    report: &mut Report,
+) -> Result<()> {
+    let installed_version = local::installed_version(config).await?;
+    report.installed_version = installed_version.clone();
+    report.published_version = Some(version.clone());
+    if installed_version.as_ref() == Some(version) {
+        println!("✅ TeamSpeak {} is active already", version);
+        return Ok(());
+    }
+
+    let mut state = state::State::load(config).await?;
+    let release_path = local::release_path(config, version)?;
+    let incomplete = state
+        .incomplete
+        .as_ref()
+        .is_some_and(|incomplete| incomplete.path == release_path);
+    if incomplete || !release_path.is_dir() {
+        return Err(anyhow!(
+            "release {} is not installed - install it with `teamspeak-updater install --version {}`",
+            version,
+            version
+        ));
+    }
+
+    let snapshot = events::phase(
+        config,
+        "snapshot",
+        snapshot::before_update(config, installed_version.as_ref()),
+    )
+    .await?;
+    snapshot::print_before_update(config, snapshot.as_deref());
+    update::activate_staged(config, journal, report, installed_version.as_ref(), version).await?;
+
+    if state
+        .pending
+        .as_ref()
+        .is_some_and(|pending| pending.version == version.to_string())
+    {
+        state.pending = None;
+        state.save(config).await?;
+    }
+
+    println!();
+    println!("✅ TeamSpeak {} is active ✅", version);
+    report.previous_version = report.installed_version.replace(version.clone());
+
+    Ok(())
+}
+
+/// Notifies about failures and exports the trace of the run, like updates do.
+async fn finish(
+    config: &Config,
+    http: &reqwest::Client,
+    name: &str,
+    mut report: Report,
+    started_at: std::time::Instant,
+    result: Result<()>,
+) -> Result<()> {
+    if let Err(e) = &result {
+        failure::notify(config, http, &report, e).await;
+    }
+    report.success = result.is_ok();
+    report.duration = started_at.elapsed();
+    let phases = events::summarize(config, report.duration);
+    let outcome = result.as_ref().map(|_| "succeeded");
+    otlp::export(config, http, name, &report, &phases, outcome).await;
+    if let Err(e) = state_db::record(config, name, &report, outcome).await {
+        println!("⚠️ Failed to record the run in the state database: {}", e);
+    }
+
+    result
+}
+
+/// Newest release an old symlink saved by an earlier switch points to, other than the active one.
+async fn previous_release(config: &Config) -> Result<Option<PathBuf>> {
+    let active_release = local::canonical_link_target(config).await.ok();
+
+    for backup in local::symlink_backups(config).await? {
+        if let Ok(release) = tokio::fs::canonicalize(&backup).await {
+            if release.is_dir() && Some(&release) != active_release.as_ref() {
+                return Ok(Some(release));
+            }
+        }
+    }
+
+    Ok(None)
+}
@@ -0,0 +1,176 @@
+use crate::{
+    cli::{self, AdoptCommand, Config},
+    lint, local, privileged,
+};
+use anyhow::{anyhow, Result};
+use semver::Version;
+use std::path::Path;
+
+const CHANGELOG_FILE_NAMES: [&str; 2] = ["CHANGELOG", "doc/CHANGELOG"];
+
+pub async fn run(config: &mut Config, command: &AdoptCommand) -> Result<()> {
+    cli::print_header();
+    lint::validate(config)?;
+    let config = &*config;
+
+    let server_dir = command.server_dir.as_ref().unwrap_or(&config.symlink_path);
+    adopt(config, server_dir, command.version.as_ref()).await?;
+
+    println!();
+    println!("✅ Installation adopted, future updates will use the releases layout.");
+
+    Ok(())
+}
+
+/// Makes sure the symlink path is a symlink into the releases directory before an update touches anything.
+///
+/// A real directory in its place gets adopted with `--force-adopt`, anything else is left for the user to sort out.
+pub async fn ensure_layout(config: &Config) -> Result<()> {
+    use std::io::ErrorKind;
+    use tokio::fs;
+
+    let Config {
+        symlink_path,
+        releases_path,
+        force_adopt,
+        ..
+    } = config;
+
+    if local::in_place_dir(config).is_some() {
+        return Ok(());
+    }
+
+    let symlink_metadata = match fs::symlink_metadata(symlink_path).await {
+        Ok(symlink_metadata) => symlink_metadata,
+        // Nothing installed yet, the update creates the symlink.
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e.into()),
+    };
+
+    if symlink_metadata.is_dir() {
+        if !force_adopt {
+            return Err(anyhow!(
+                "{} is a directory, not a symlink into the releases directory - move it into the releases layout with `teamspeak-updater adopt` or pass --force-adopt",
+                symlink_path.to_string_lossy()
+            ));
+        }
+
+        println!(
+            "⚠️ {} is a directory, adopting it into the releases layout",
+            symlink_path.to_string_lossy()
+        );
+        return adopt(config, symlink_path, config.assume_version.as_ref()).await;
+    }
+    if !symlink_metadata.is_symlink() {
+        return Err(anyhow!(
+            "{} is a file, not a symlink into the releases directory - move it away first",
+            symlink_path.to_string_lossy()
+        ));
+    }
+
+    // The target may be missing under `--repair`, so its directory is what gets compared.
+    let target = local::link_target(config).await?;
+    let target_dir = target.parent().map(|target_dir| target_dir.canonicalize());
+    let releases_path = releases_path.canonicalize()?;
+    match target_dir {
+        Some(Ok(target_dir)) if target_dir == releases_path => Ok(()),
+        _ => Err(anyhow!(
+            "symlink {} points to {}, which is not inside the releases directory {} - point it at a release there or fix --releases-path",
+            symlink_path.to_string_lossy(),
+            target.to_string_lossy(),
+            releases_path.to_string_lossy()
+        )),
+    }
+}
+
+/// Moves the installation in `server_dir` into the releases directory and points the symlink at it.
+async fn adopt(config: &Config, server_dir: &Path, version: Option<&Version>) -> Result<()> {
+    use tokio::fs;
+
+    let Config { symlink_path, .. } = config;
+
+    let symlink_metadata = fs::symlink_metadata(server_dir).await?;
+    if symlink_metadata.is_symlink() {
+        return Err(anyhow!(
+            "{} is already a symlink - nothing to adopt",
+            server_dir.to_string_lossy()
+        ));
+    }
+    if !symlink_metadata.is_dir() {
+        return Err(anyhow!(
+            "{} is not a directory",
+            server_dir.to_string_lossy()
+        ));
+    }
+
+    let version = match version {
+        Some(version) => version.clone(),
+        None => detect_version(server_dir).await?,
+    };
+    println!(
+        "🏠 Determined version of {}: {}",
+        server_dir.to_string_lossy(),
+        version
+    );
+
+    let release_path = local::release_path(config, &version)?;
+    if fs::symlink_metadata(&release_path).await.is_ok() {
+        return Err(anyhow!(
+            "release directory {} already exists",
+            release_path.to_string_lossy()
+        ));
+    }
+
+    if server_dir != symlink_path && fs::symlink_metadata(symlink_path).await.is_ok() {
+        return Err(anyhow!(
+            "symlink path {} is already taken - move it away before adopting",
+            symlink_path.to_string_lossy()
+        ));
+    }
+
+    println!(
+        "📦 Moving {} to {}",
+        server_dir.to_string_lossy(),
+        release_path.to_string_lossy()
+    );
+    privileged::rename(config, server_dir, &release_path).await?;
+
+    println!(
+        "🧠 Creating symlink {} -> {}",
+        symlink_path.to_string_lossy(),
+        release_path.to_string_lossy()
+    );
+    privileged::symlink_dir(
+        config,
+        &local::link_source(config, &release_path)?,
+        symlink_path,
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Reads the version from the first `Server Release x.y.z` line of the bundled changelog.
+async fn detect_version(server_dir: &Path) -> Result<Version> {
+    for file_name in CHANGELOG_FILE_NAMES {
+        let changelog = match tokio::fs::read(server_dir.join(file_name)).await {
+            Ok(changelog) => changelog,
+            Err(_) => continue,
+        };
+
+        let version = String::from_utf8_lossy(&changelog)
+            .lines()
+            .filter_map(|line| line.split_once("Server Release"))
+            .filter_map(|(_, rest)| rest.split_whitespace().next())
+            .find_map(|version| Version::parse(version).ok());
+
+        if let Some(version) = version {
+            return Ok(version);
+        }
+    }
+
+    Err(anyhow!(
+        "could not determine version of {} from its changelog - pass it with --version",
+        server_dir.to_string_lossy()
+    ))
+}
@@ -0,0 +1,199 @@
+//! Tiny HTTP API of the daemon, for dashboards which shouldn't have to shell out to `status`.
+
+use crate::{cli::Config, status};
+use anyhow::Result;
+use futures::{stream::FuturesUnordered, StreamExt};
+use serde_json::json;
+use std::{net::SocketAddr, time::Duration};
+use subtle::ConstantTimeEq;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    sync::Notify,
+};
+
+/// Requests are small, anything larger is not meant for this API.
+const MAX_REQUEST_BYTES: usize = 8 * 1024;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+/// Connections handled at once. Further ones wait in the listen backlog.
+const MAX_CONNECTIONS: usize = 64;
+
+struct Response {
+    status: &'static str,
+    body: serde_json::Value,
+}
+
+fn respond(status: &'static str, body: serde_json::Value) -> Response {
+    Response { status, body }
+}
+
+/// Serves `GET /status` and `POST /update`, which wakes up the daemon through `trigger`. Connections are handled
+/// concurrently, so a slow client doesn't hold up the others.
+pub async fn serve(
+    config: &Config,
+    listen: SocketAddr,
+    token: Option<&str>,
+    trigger: &Notify,
+) -> Result<()> {
+    let listener = TcpListener::bind(listen).await?;
+    println!("📡 Serving the API on http://{}", listener.local_addr()?);
+
+    let mut connections = FuturesUnordered::new();
+    loop {
+        tokio::select! {
+            accepted = listener.accept(), if connections.len() < MAX_CONNECTIONS => {
+                let (stream, _) = accepted?;
+                connections.push(tokio::time::timeout(
+                    REQUEST_TIMEOUT,
+                    handle(config, stream, token, trigger),
+                ));
+            }
+            Some(handled) = connections.next() => match handled {
+                Ok(Ok(())) => {}
+                Ok(Err(e)) => println!("⚠️ API request failed: {:#}", e),
+                Err(_) => println!("⚠️ API request timed out"),
+            },
+        }
+    }
+}
+
+/// Compares the bearer token in constant time, so response times don't give away how much of it matched.
+fn is_authorized(authorization: Option<&str>, token: &str) -> bool {
+    authorization
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .is_some_and(|provided| provided.as_bytes().ct_eq(token.as_bytes()).into())
+}
+
+async fn handle(
+    config: &Config,
+    mut stream: TcpStream,
+    token: Option<&str>,
+    trigger: &Notify,
+) -> Result<()> {
+    let mut request = vec![];
+    let mut buffer = [0; 1024];
+    while !request.windows(4).any(|window| window == b"\r\n\r\n") {
+        let read = stream.read(&mut buffer).await?;
+        if read == 0 || request.len() + read > MAX_REQUEST_BYTES {
+            return Ok(());
+        }
+        request.extend_from_slice(&buffer[..read]);
+    }
+
+    let request = String::from_utf8_lossy(&request);
+    let mut lines = request.lines();
+    let mut request_line = lines.next().unwrap_or_default().split_whitespace();
+    let (method, path) = (
+        request_line.next().unwrap_or_default(),
+        request_line.next().unwrap_or_default(),
+    );
+    let authorization = lines
+        .take_while(|line| !line.is_empty())
+        .filter_map(|line| line.split_once(':'))
+        .find(|(name, _)| name.trim().eq_ignore_ascii_case("authorization"))
+        .map(|(_, value)| value.trim());
+
+    let response = match (method, path) {
+        ("GET", "/status") => match status::collect(config).await {
+            Ok(status) => respond("200 OK", serde_json::to_value(status)?),
+            Err(e) => respond(
+                "500 Internal Server Error",
+                json!({ "error": format!("{:#}", e) }),
+            ),
+        },
+        ("POST", "/update") => match token {
+            None => respond(
+                "403 Forbidden",
+                json!({ "error": "updates can't be triggered without --api-token" }),
+            ),
+            Some(token) if is_authorized(authorization, token) => {
+                println!("📨 Update triggered through the API");
+                trigger.notify_one();
+                respond("202 Accepted", json!({ "triggered": true }))
+            }
+            Some(_) => respond(
+                "401 Unauthorized",
+                json!({ "error": "missing or wrong bearer token" }),
+            ),
+        },
+        (_, "/status" | "/update") => respond(
+            "405 Method Not Allowed",
+            json!({ "error": "method not allowed" }),
+        ),
+        _ => respond("404 Not Found", json!({ "error": "not found" })),
+    };
+
+    let body = serde_json::to_vec_pretty(&response.body)?;
+    let head = format!(
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        response.status,
+        body.len()
+    );
+    stream.write_all(head.as_bytes()).await?;
+    stream.write_all(&body).await?;
+    stream.shutdown().await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use argh::FromArgs;
+
+    #[test]
+    fn matching_token_is_authorized() {
+        assert!(is_authorized(Some("Bearer s3cret"), "s3cret"));
+    }
+
+    #[test]
+    fn wrong_or_missing_token_is_refused() {
+        assert!(!is_authorized(Some("Bearer s3crex"), "s3cret"));
+        assert!(!is_authorized(Some("Bearer s3cret-and-more"), "s3cret"));
+        assert!(!is_authorized(Some("Bearer s3c"), "s3cret"));
+        assert!(!is_authorized(Some("s3cret"), "s3cret"));
+        assert!(!is_authorized(None, "s3cret"));
+    }
+
+    async fn get(address: SocketAddr) -> Result<String> {
+        let mut stream = TcpStream::connect(address).await?;
+        stream.write_all(b"GET /missing HTTP/1.1\r\n\r\n").await?;
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await?;
+        Ok(response)
+    }
+
+    #[tokio::test]
+    async fn connections_beyond_the_limit_wait_for_a_free_slot() {
+        let config = Config::from_args(&["teamspeak-updater"], &[]).unwrap();
+        let trigger = Notify::new();
+        let address = std::net::TcpListener::bind("127.0.0.1:0")
+            .and_then(|listener| listener.local_addr())
+            .unwrap();
+
+        let test = async {
+            // Wait for the listener, then take up every slot with connections which don't send a request.
+            while TcpStream::connect(address).await.is_err() {
+                tokio::task::yield_now().await;
+            }
+            let mut idle = vec![];
+            for _ in 0..MAX_CONNECTIONS {
+                idle.push(TcpStream::connect(address).await.unwrap());
+            }
+
+            let waiting = get(address);
+            tokio::pin!(waiting);
+            let blocked = tokio::time::timeout(Duration::from_millis(500), &mut waiting).await;
+            assert!(blocked.is_err(), "a connection beyond the limit was served");
+
+            drop(idle.pop());
+            let response = waiting.await.unwrap();
+            assert!(response.starts_with("HTTP/1.1 404 Not Found"));
+        };
+
+        tokio::select! {
+            result = serve(&config, address, None, &trigger) => panic!("the API stopped: {:?}", result),
+            () = test => {}
+        }
+    }
+}
@@ -0,0 +1,107 @@
+use crate::{
+    cli::Config,
+    events, local, manifest,
+    remote::{self, Download},
+};
+use anyhow::Result;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+#[derive(Serialize)]
+struct ArtifactRecord {
+    version: String,
+    target: String,
+    archive_url: String,
+    digest: String,
+    size: u64,
+    release_path: PathBuf,
+    tree_hash: String,
+    signature_status: String,
+}
+
+/// Emits the artifact record as the `artifact` phase. The release is installed by then, so failing to write the
+/// record only warns instead of failing the update.
+pub async fn record(
+    config: &Config,
+    http: &reqwest::Client,
+    download: &Download,
+    published_version: &semver::Version,
+) {
+    let emitted = events::phase(
+        config,
+        "artifact",
+        emit(config, http, download, published_version),
+    )
+    .await;
+    if let Err(e) = emitted {
+        println!("⚠️ Failed to write the artifact record: {:#}", e);
+    }
+}
+
+async fn emit(
+    config: &Config,
+    http: &reqwest::Client,
+    download: &Download,
+    published_version: &semver::Version,
+) -> Result<()> {
+    let Config {
+        artifact_record,
+        target_tuple,
+        ..
+    } = config;
+
+    let artifact_record = match artifact_record {
+        Some(artifact_record) => artifact_record,
+        None => return Ok(()),
+    };
+
+    let release_path = local::release_path(config, published_version)?;
+    let tree_hash = {
+        let release_path = release_path.clone();
+        tokio::task::spawn_blocking(move || tree_hash(&release_path)).await??
+    };
+
+    let record = ArtifactRecord {
+        version: published_version.to_string(),
+        target: target_tuple.to_string(),
+        archive_url: download.url.to_string(),
+        digest: format!("sha256:{}", download.sha256),
+        size: download.size,
+        release_path,
+        tree_hash: format!("sha256:{}", tree_hash),
+        signature_status: match download.verified {
+            Some(algorithm) => format!("checksum:{}", algorithm),
+            None => "unverified".to_owned(),
+        },
+    };
+
+    if artifact_record.starts_with("http://") || artifact_record.starts_with("https://") {
+        remote::ensure_online(config, "posting the artifact record")?;
+        http.post(artifact_record)
+            .json(&record)
+            .send()
+            .await?
+            .error_for_status()?;
+    } else {
+        tokio::fs::write(artifact_record, serde_json::to_vec_pretty(&record)?).await?;
+    }
+
+    println!("🧾 Artifact record written to {}", artifact_record);
+
+    Ok(())
+}
+
+/// Hashes relative paths and contents of all files within the directory, in a stable order.
+fn tree_hash(root: &Path) -> Result<String> {
+    use sha2::{Digest, Sha256};
+
+    let mut hasher = Sha256::new();
+    for (relative, digest) in manifest::file_digests(root)? {
+        hasher.update(relative.as_bytes());
+        hasher.update([0]);
+        hasher.update(digest.as_bytes());
+        hasher.update([b'\n']);
+    }
+
+    Ok(hex::encode(hasher.finalize()))
+}
@@ -0,0 +1,154 @@
+use crate::{
+    cli::Config,
+    local,
+    manifest::{self, Manifest},
+    preserve,
+};
+use anyhow::Result;
+use semver::Version;
+
+/// Files shown in the output, hooks get the full list.
+const PRINTED_CHANGES: usize = 20;
+
+/// File of a release that was added, removed or changed, with its sizes before and after.
+pub struct Change {
+    pub path: String,
+    pub old_size: Option<u64>,
+    pub new_size: Option<u64>,
+}
+
+/// Difference between shipped files of two releases, taken from their manifests.
+pub struct Changes {
+    pub from: String,
+    pub added: Vec<Change>,
+    pub removed: Vec<Change>,
+    pub changed: Vec<Change>,
+}
+
+impl Changes {
+    pub fn summary(&self) -> String {
+        format!(
+            "{} added, {} removed, {} changed since {}",
+            self.added.len(),
+            self.removed.len(),
+            self.changed.len(),
+            self.from
+        )
+    }
+
+    /// One line per file, prefixed with `+`, `-` or `~`. Sizes are left out where manifests of older releases lack them.
+    pub fn lines(&self) -> Vec<String> {
+        let line = |prefix: char, change: &Change| match (change.old_size, change.new_size) {
+            (Some(old_size), Some(new_size)) if old_size != new_size => format!(
+                "{} {} ({} -> {} bytes)",
+                prefix, change.path, old_size, new_size
+            ),
+            (_, Some(size)) | (Some(size), None) => {
+                format!("{} {} ({} bytes)", prefix, change.path, size)
+            }
+            (None, None) => format!("{} {}", prefix, change.path),
+        };
+
+        self.added
+            .iter()
+            .map(|change| line('+', change))
+            .chain(self.removed.iter().map(|change| line('-', change)))
+            .chain(self.changed.iter().map(|change| line('~', change)))
+            .collect()
+    }
+}
+
+/// Reads the manifest of the installed release before the update replaces it, which in-place installs do.
+pub async fn installed_manifest(
+    config: &Config,
+    installed_version: Option<&Version>,
+) -> Option<Manifest> {
+    let release_path = local::release_path(config, installed_version?).ok()?;
+    manifest::read(&release_path).await.ok().flatten()
+}
+
+/// Compares the files of the new release with the old manifest and prints a summary.
+pub async fn report(
+    config: &Config,
+    old_manifest: Option<&Manifest>,
+    published_version: &Version,
+) -> Result<Option<Changes>> {
+    let old_manifest = match old_manifest {
+        Some(old_manifest) => old_manifest,
+        None => return Ok(None),
+    };
+    let new_manifest =
+        match manifest::read(&local::release_path(config, published_version)?).await? {
+            Some(new_manifest) => new_manifest,
+            None => return Ok(None),
+        };
+
+    let changes = between(config, old_manifest, &new_manifest);
+    if let Some(changes) = &changes {
+        print(changes, Some(PRINTED_CHANGES));
+    }
+
+    Ok(changes)
+}
+
+/// Difference between the files recorded in two manifests, `None` if either of them lacks file hashes.
+///
+/// Preserved paths are left out, as they are state carried over rather than files shipped by TeamSpeak.
+pub fn between(
+    config: &Config,
+    old_manifest: &Manifest,
+    new_manifest: &Manifest,
+) -> Option<Changes> {
+    let (old_files, new_files) = match (&old_manifest.files, &new_manifest.files) {
+        (Some(old_files), Some(new_files)) => (old_files, new_files),
+        _ => return None,
+    };
+
+    let mut changes = Changes {
+        from: old_manifest.version.clone(),
+        added: vec![],
+        removed: vec![],
+        changed: vec![],
+    };
+    for (path, digest) in new_files {
+        if preserve::is_preserved(config, path) {
+            continue;
+        }
+
+        let change = Change {
+            path: path.clone(),
+            old_size: old_manifest.size(path),
+            new_size: new_manifest.size(path),
+        };
+        match old_files.get(path) {
+            None => changes.added.push(change),
+            Some(old_digest) if old_digest != digest => changes.changed.push(change),
+            Some(_) => {}
+        }
+    }
+    for path in old_files.keys() {
+        if !new_files.contains_key(path) && !preserve::is_preserved(config, path) {
+            changes.removed.push(Change {
+                path: path.clone(),
+                old_size: old_manifest.size(path),
+                new_size: None,
+            });
+        }
+    }
+
+    Some(changes)
+}
+
+/// Prints the summary and up to `limit` changed files.
+pub fn print(changes: &Changes, limit: Option<usize>) {
+    println!("📋 Files: {}", changes.summary());
+
+    let lines = changes.lines();
+    let limit = limit.unwrap_or(lines.len());
+    for line in lines.iter().take(limit) {
+        println!("   {}", line);
+    }
+    if lines.len() > limit {
+        println!("   … and {} more", lines.len() - limit);
+    }
+}
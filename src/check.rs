@@ -0,0 +1,41 @@
+use crate::{cli::Config, remote, update};
+use anyhow::Result;
+use std::process::exit;
+
+// Nagios plugin exit codes.
+const OK_EXIT_CODE: i32 = 0;
+const WARNING_EXIT_CODE: i32 = 1;
+const UNKNOWN_EXIT_CODE: i32 = 2;
+
+/// Compares installed and published versions, printing a single parsable line for monitoring systems.
+pub async fn run(config: &Config) -> Result<()> {
+    let http = remote::client(config)?;
+
+    let (line, exit_code) = match update::fetch_versions(config, &http).await {
+        Ok((Some(installed), published)) if installed >= published => {
+            (format!("UP_TO_DATE {}", installed), OK_EXIT_CODE)
+        }
+        Ok((installed, published)) => {
+            let mut line = format!(
+                "UPDATE_AVAILABLE {} -> {}",
+                installed.map_or_else(|| String::from("none"), |version| version.to_string()),
+                published
+            );
+            // The date is informational, the line stays useful without it.
+            if let Ok(Some(published_at)) = remote::published_at(config, &http, &published).await {
+                line.push_str(&format!(
+                    " published {}",
+                    published_at.format("%Y-%m-%dT%H:%M:%SZ")
+                ));
+            }
+            (line, WARNING_EXIT_CODE)
+        }
+        Err(e) => (
+            format!("UNKNOWN {}", format!("{:#}", e).replace('\n', " ")),
+            UNKNOWN_EXIT_CODE,
+        ),
+    };
+
+    println!("{}", line);
+    exit(exit_code)
+}
@@ -0,0 +1,255 @@
+use crate::{cli::Config, remote};
+use anyhow::{anyhow, Result};
+use semver::Version;
+use sha2::Digest;
+use std::{fmt::Display, str::FromStr};
+use thiserror::Error;
+
+/// Algorithm of a checksum file published next to the archives.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Algorithm {
+    Sha256,
+    Sha1,
+    Md5,
+}
+
+#[derive(Debug, Error)]
+pub enum AlgorithmError {
+    #[error("checksum algorithm not recognized: {0} (expected `sha256`, `sha1` or `md5`)")]
+    NotRecognized(String),
+}
+
+impl FromStr for Algorithm {
+    type Err = AlgorithmError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "sha256" => Ok(Self::Sha256),
+            "sha1" => Ok(Self::Sha1),
+            "md5" => Ok(Self::Md5),
+            _ => Err(AlgorithmError::NotRecognized(s.to_owned())),
+        }
+    }
+}
+
+impl Display for Algorithm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Sha256 => "sha256",
+            Self::Sha1 => "sha1",
+            Self::Md5 => "md5",
+        })
+    }
+}
+
+impl Algorithm {
+    /// Collisions can be crafted for these, so a match proves the download complete, but not untampered.
+    fn is_weak(self) -> bool {
+        matches!(self, Self::Sha1 | Self::Md5)
+    }
+
+    fn default_template(self) -> &'static str {
+        match self {
+            Self::Sha256 => "{file}.sha256",
+            Self::Sha1 => "{file}.sha1",
+            Self::Md5 => "{file}.md5",
+        }
+    }
+}
+
+/// Name of the checksum file of an algorithm, given as `algorithm=template`.
+#[derive(Clone)]
+pub struct Template {
+    algorithm: Algorithm,
+    template: String,
+}
+
+#[derive(Debug, Error)]
+pub enum TemplateError {
+    #[error("checksum template not recognized: {0} (expected `algorithm=template`, e.g. `sha1={{file}}.sha1`)")]
+    NotRecognized(String),
+    #[error(transparent)]
+    Algorithm(#[from] AlgorithmError),
+}
+
+impl FromStr for Template {
+    type Err = TemplateError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (algorithm, template) = s
+            .split_once('=')
+            .filter(|(_, template)| !template.is_empty())
+            .ok_or_else(|| TemplateError::NotRecognized(s.to_owned()))?;
+
+        Ok(Self {
+            algorithm: algorithm.trim().parse()?,
+            template: template.trim().to_owned(),
+        })
+    }
+}
+
+impl Display for Template {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}={}", self.algorithm, self.template)
+    }
+}
+
+/// Verifies a downloaded archive against the checksum file of the first `--checksum-algo` the mirror publishes one for.
+/// Nothing is verified when no algorithm is configured. Returns the algorithm the archive was verified with.
+pub async fn verify(
+    config: &Config,
+    http: &reqwest::Client,
+    download: &remote::Download,
+    version: &Version,
+) -> Result<Option<Algorithm>> {
+    let Config {
+        checksum_algo,
+        checksum_template,
+        ..
+    } = config;
+    if checksum_algo.is_empty() {
+        return Ok(None);
+    }
+
+    let file_name = download
+        .url
+        .path_segments()
+        .and_then(|mut segments| segments.next_back())
+        .map(|name| percent_encoding::percent_decode_str(name).decode_utf8_lossy())
+        .unwrap_or_default();
+
+    for algorithm in checksum_algo {
+        // The last template given for an algorithm wins.
+        let template = checksum_template
+            .iter()
+            .rev()
+            .find(|template| template.algorithm == *algorithm)
+            .map_or(algorithm.default_template(), |template| {
+                template.template.as_str()
+            });
+        let checksum_url = download.url.join(
+            &template
+                .replace("{file}", &file_name)
+                .replace("{version}", &version.to_string()),
+        )?;
+        remote::ensure_allowed(config, &checksum_url)?;
+
+        let request = remote::authorize(config, http.get(checksum_url.clone()));
+        let response = remote::send_to_mirror(config, request).await?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            continue;
+        }
+        let contents = response.error_for_status()?.text().await?;
+
+        let expected = find_checksum(&contents, &file_name)
+            .ok_or_else(|| anyhow!("{} has no checksum for {}", checksum_url, file_name))?;
+        let actual = match algorithm {
+            Algorithm::Sha256 => download.sha256.clone(),
+            Algorithm::Sha1 => digest::<sha1::Sha1>(&download.archive).await?,
+            Algorithm::Md5 => digest::<md5::Md5>(&download.archive).await?,
+        };
+        if !expected.eq_ignore_ascii_case(&actual) {
+            return Err(anyhow!(
+                "checksum mismatch for {}: {} expects {}, got {}",
+                file_name,
+                checksum_url,
+                expected,
+                actual
+            ));
+        }
+
+        println!("🔏 Checksum matches {}", checksum_url);
+        if algorithm.is_weak() {
+            println!(
+                "⚠️ {} is verified with {} only - it shows the download is complete, but not that it wasn't tampered with. Publish SHA256 checksums on the mirror if you can.",
+                file_name, algorithm
+            );
+        }
+        return Ok(Some(*algorithm));
+    }
+
+    let algorithms = checksum_algo
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>();
+    Err(anyhow!(
+        "mirror publishes no checksum file for {} ({} tried)",
+        file_name,
+        algorithms.join(", ")
+    ))
+}
+
+/// Checksum of `file_name` in the contents of a file in `sha256sum` format (also used by `sha1sum` and `md5sum`),
+/// or a file with the bare checksum.
+pub fn find_checksum(contents: &str, file_name: &str) -> Option<String> {
+    let entries = contents
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let checksum = fields.next()?;
+            let name = fields.next().map(|name| name.trim_start_matches('*'));
+            Some((checksum, name))
+        })
+        .collect::<Vec<_>>();
+
+    entries
+        .iter()
+        .find(|(_, name)| *name == Some(file_name))
+        .or_else(|| entries.iter().find(|(_, name)| name.is_none()))
+        .map(|(checksum, _)| checksum.to_string())
+}
+
+/// Hashes the archive from its start, for algorithms not computed while downloading.
+async fn digest<D: Digest>(archive: &tokio::fs::File) -> Result<String> {
+    use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+    let mut archive = archive.try_clone().await?;
+    archive.seek(std::io::SeekFrom::Start(0)).await?;
+
+    let mut hasher = D::new();
+    let mut buffer = vec![0; 1 << 16];
+    loop {
+        let read = archive.read(&mut buffer).await?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+
+    Ok(hex::encode(hasher.finalize()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ARCHIVE: &str = "teamspeak3-server_linux_amd64-3.13.7.tar.bz2";
+
+    #[test]
+    fn finds_the_line_of_the_archive() {
+        let contents = "1111  teamspeak3-server_win64-3.13.7.zip\n\
+                        2222  teamspeak3-server_linux_amd64-3.13.7.tar.bz2\n";
+
+        assert_eq!(find_checksum(contents, ARCHIVE).as_deref(), Some("2222"));
+    }
+
+    #[test]
+    fn accepts_binary_mode_markers() {
+        let contents = "3333 *teamspeak3-server_linux_amd64-3.13.7.tar.bz2\n";
+
+        assert_eq!(find_checksum(contents, ARCHIVE).as_deref(), Some("3333"));
+    }
+
+    #[test]
+    fn accepts_a_bare_checksum() {
+        assert_eq!(find_checksum("4444\n", ARCHIVE).as_deref(), Some("4444"));
+    }
+
+    #[test]
+    fn other_files_do_not_match() {
+        let contents = "1111  teamspeak3-server_win64-3.13.7.zip\n\n";
+
+        assert_eq!(find_checksum(contents, ARCHIVE), None);
+        assert_eq!(find_checksum("", ARCHIVE), None);
+    }
+}
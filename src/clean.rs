@@ -0,0 +1,275 @@
+use crate::{
+    cli::{self, CleanCommand, Config},
+    lint, local, lock, privileged, remote, state,
+};
+use anyhow::Result;
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+/// Work files and incomplete releases younger than this may belong to an update running right now.
+const STALE_AFTER: Duration = Duration::from_secs(60 * 60);
+
+/// Something `clean` removes, with the space it frees.
+struct Leftover {
+    path: PathBuf,
+    kind: Kind,
+    size: u64,
+    description: &'static str,
+}
+
+enum Kind {
+    File,
+    Dir,
+    Symlink,
+}
+
+/// Removes what updates leave behind: the listing cache, stale downloads and staging directories, the directory
+/// of an incomplete release and old symlinks saved by earlier swaps.
+pub async fn run(config: &mut Config, command: &CleanCommand) -> Result<()> {
+    let CleanCommand {
+        dry_run,
+        keep_backups,
+    } = command;
+
+    cli::print_header();
+    lint::validate(config)?;
+    let config = &*config;
+
+    // Listing what would be removed doesn't need to keep updaters waiting.
+    let _lock = if *dry_run {
+        None
+    } else {
+        Some(lock::acquire(config).await?)
+    };
+
+    let mut state = state::State::load(config).await?;
+    let mut leftovers = vec![];
+    leftovers.extend(listing_cache(config).await);
+    leftovers.extend(work_files(config).await?);
+    leftovers.extend(in_place_staging(config).await?);
+    let incomplete = incomplete_release(config, &state).await?;
+    let removes_incomplete = incomplete.is_some();
+    leftovers.extend(incomplete);
+    leftovers.extend(old_backups(config, *keep_backups).await?);
+
+    if leftovers.is_empty() {
+        println!("✅ Nothing to clean up");
+        return Ok(());
+    }
+
+    let mut reclaimed = 0;
+    for leftover in &leftovers {
+        let Leftover {
+            path,
+            kind,
+            size,
+            description,
+        } = leftover;
+
+        // Symlinks take no space worth mentioning.
+        let size_note = match kind {
+            Kind::Symlink => String::new(),
+            Kind::File | Kind::Dir => format!(" ({})", format_size(*size)),
+        };
+        if *dry_run {
+            println!(
+                "🔍 Would remove {} {}{}",
+                description,
+                path.to_string_lossy(),
+                size_note
+            );
+        } else {
+            match kind {
+                Kind::File => tokio::fs::remove_file(path).await?,
+                Kind::Dir => privileged::remove_tree(config, path).await?,
+                Kind::Symlink => privileged::remove_symlink(config, path).await?,
+            }
+            println!(
+                "🗑️ Removed {} {}{}",
+                description,
+                path.to_string_lossy(),
+                size_note
+            );
+        }
+        reclaimed += size;
+    }
+
+    if *dry_run {
+        println!();
+        println!(
+            "✅ Would reclaim {} - run without --dry-run to remove it",
+            format_size(reclaimed)
+        );
+        return Ok(());
+    }
+
+    if removes_incomplete {
+        state.incomplete = None;
+        state.save(config).await?;
+    }
+    println!();
+    println!("✅ Reclaimed {}", format_size(reclaimed));
+
+    Ok(())
+}
+
+/// The cached listing of the mirror, which the next update fetches again.
+async fn listing_cache(config: &Config) -> Option<Leftover> {
+    let path = remote::listing_cache_path(config);
+    let metadata = tokio::fs::symlink_metadata(&path).await.ok()?;
+
+    Some(Leftover {
+        path,
+        kind: Kind::File,
+        size: metadata.len(),
+        description: "listing cache",
+    })
+}
+
+/// Downloads and extraction directories the updater left in the work directory after interrupted updates,
+/// and archives kept for `--ssh`. Files of others sharing the work directory are left alone.
+async fn work_files(config: &Config) -> Result<Vec<Leftover>> {
+    let work_dir = local::work_dir(config)?;
+    if !work_dir.is_dir() {
+        return Ok(vec![]);
+    }
+
+    let mut leftovers = vec![];
+    let mut read_dir = tokio::fs::read_dir(&work_dir).await?;
+    while let Some(entry) = read_dir.next_entry().await? {
+        if !entry
+            .file_name()
+            .to_string_lossy()
+            .starts_with(local::WORK_ENTRY_PREFIX)
+        {
+            continue;
+        }
+        let path = entry.path();
+        let metadata = entry.metadata().await?;
+        if !is_stale(&metadata) {
+            continue;
+        }
+
+        let leftover = if metadata.is_dir() {
+            Leftover {
+                size: size_of_dir(&path).await?,
+                path,
+                kind: Kind::Dir,
+                description: "work directory",
+            }
+        } else {
+            Leftover {
+                path,
+                kind: Kind::File,
+                size: metadata.len(),
+                description: "downloaded file",
+            }
+        };
+        leftovers.push(leftover);
+    }
+
+    Ok(leftovers)
+}
+
+/// Staging directory of an interrupted in-place update.
+async fn in_place_staging(config: &Config) -> Result<Option<Leftover>> {
+    let Some(path) = local::in_place_staging_dir(config) else {
+        return Ok(None);
+    };
+    match tokio::fs::symlink_metadata(&path).await {
+        Ok(metadata) if metadata.is_dir() && is_stale(&metadata) => Ok(Some(Leftover {
+            size: size_of_dir(&path).await?,
+            path,
+            kind: Kind::Dir,
+            description: "staging directory",
+        })),
+        _ => Ok(None),
+    }
+}
+
+/// Directory of a release whose extraction failed and which the updater couldn't remove then. A recent one may
+/// still be extracted by an updater which doesn't take the lock, e.g. an older version running from cron.
+async fn incomplete_release(config: &Config, state: &state::State) -> Result<Option<Leftover>> {
+    let Some(incomplete) = &state.incomplete else {
+        return Ok(None);
+    };
+    let age = state::unix_timestamp().saturating_sub(incomplete.since);
+    if local::in_place_dir(config).is_some()
+        || !incomplete.path.is_dir()
+        || age < STALE_AFTER.as_secs()
+    {
+        return Ok(None);
+    }
+    let active_release = local::canonical_link_target(config).await.ok();
+    if active_release == incomplete.path.canonicalize().ok() {
+        return Ok(None);
+    }
+
+    Ok(Some(Leftover {
+        path: incomplete.path.clone(),
+        kind: Kind::Dir,
+        size: size_of_dir(&incomplete.path).await?,
+        description: "incomplete release",
+    }))
+}
+
+/// Old symlinks which are broken, point to the active release, or are older than the `keep` newest ones
+/// `deactivate` can return to.
+async fn old_backups(config: &Config, keep: usize) -> Result<Vec<Leftover>> {
+    if local::in_place_dir(config).is_some() {
+        return Ok(vec![]);
+    }
+
+    let active_release = local::canonical_link_target(config).await.ok();
+    let mut kept = 0;
+    let mut leftovers = vec![];
+    for backup in local::symlink_backups(config).await? {
+        let release = tokio::fs::canonicalize(&backup)
+            .await
+            .ok()
+            .filter(|release| release.is_dir());
+        let description = match release {
+            None => "broken old symlink",
+            Some(release) if Some(&release) == active_release.as_ref() => {
+                "old symlink to the active release"
+            }
+            Some(_) if kept < keep => {
+                kept += 1;
+                continue;
+            }
+            Some(_) => "old symlink",
+        };
+
+        leftovers.push(Leftover {
+            path: backup,
+            kind: Kind::Symlink,
+            size: 0,
+            description,
+        });
+    }
+
+    Ok(leftovers)
+}
+
+fn is_stale(metadata: &std::fs::Metadata) -> bool {
+    metadata
+        .modified()
+        .ok()
+        .and_then(|modified| modified.elapsed().ok())
+        .is_some_and(|age| age >= STALE_AFTER)
+}
+
+async fn size_of_dir(path: &Path) -> Result<u64> {
+    let path = path.to_owned();
+    tokio::task::spawn_blocking(move || local::dir_size(&path)).await?
+}
+
+fn format_size(size: u64) -> String {
+    if size >= 1024 * 1024 {
+        format!("{:.1} MiB", size as f64 / 1024.0 / 1024.0)
+    } else {
+        format!("{} KiB", size.div_ceil(1024))
+    }
+}
@@ -1,43 +1,912 @@
-use crate::target;
-use argh::FromArgs;
+use crate::{
+    checksum, completions, events, layout, local, permissions, remote, schedule, target, throttle,
+};
+use argh::{ArgsInfo, FromArgs};
 use std::path::PathBuf;
 
 /// Check for update and install new TeamSpeak version, automatically.
-#[derive(FromArgs)]
+#[derive(FromArgs, ArgsInfo)]
 pub struct Config {
+    /// path to a TOML config file with option values. Command line options take precedence over it, environment variables (`TS_UPDATER_MIRROR_URL`, ...) are used last.
+    #[argh(option)]
+    pub config: Option<PathBuf>,
+    /// profile of the config file to apply on top of its top-level options, from its `[profile.<name>]` section.
+    #[argh(option)]
+    pub profile: Option<String>,
     /// path to TeamSpeak symlink which will be used for pinning the latest version.
     #[argh(option, default = "PathBuf::from(\"/opt/teamspeak\")")]
     pub symlink_path: PathBuf,
     /// path to releases directory where all downloaded TeamSpeak versions will be stored.
     #[argh(option, default = "PathBuf::from(\"/opt/teamspeak-releases/\")")]
     pub releases_path: PathBuf,
+    /// name of release directories inside the releases directory, with `{version}` and optionally `{date}` (day of the install) placeholders, e.g. `teamspeak-{version}`.
+    #[argh(option, default = "layout::ReleaseLayout::default()")]
+    pub release_dir_name: layout::ReleaseLayout,
+    /// how releases are installed: `releases` (versioned directories and a symlink) or `in-place` (files replaced inside --target-dir).
+    #[argh(option, default = "local::InstallMode::Releases")]
+    pub install_mode: local::InstallMode,
+    /// directory updated by the `in-place` install mode.
+    #[argh(option)]
+    pub target_dir: Option<PathBuf>,
+    /// server generation to manage: `ts3` or `ts6`. Selects the default mirror and archive file name template.
+    #[argh(option, default = "remote::Product::Ts3")]
+    pub product: remote::Product,
     /// operating system / architecture tuple used to recognize which TeamSpeak version should be installed.
     #[argh(option, default = "target::Tuple::deduce()")]
     pub target_tuple: target::Tuple,
-    /// mirror from where TeamSpeak version should be matched.
+    /// mirror from where TeamSpeak version should be matched. Defaults to the download server of the selected product.
+    #[argh(option, default = "String::from(remote::DEFAULT_TS3_MIRROR_URL)")]
+    pub mirror_url: String,
+    /// user name for HTTP basic authentication against the mirror. Credentials embedded in --mirror-url are used too.
+    #[argh(option)]
+    pub mirror_user: Option<String>,
+    /// password for HTTP basic authentication against the mirror. Prefer the config file or `TS_UPDATER_MIRROR_PASSWORD` over the command line.
+    #[argh(option)]
+    pub mirror_password: Option<String>,
+    /// bearer token sent to the mirror, taking precedence over basic authentication.
+    #[argh(option)]
+    pub mirror_token: Option<String>,
+    /// address of the raw ServerQuery interface of the server.
+    #[argh(option, default = "String::from(\"127.0.0.1:10011\")")]
+    pub query_address: String,
+    /// login name for ServerQuery.
+    #[argh(option, default = "String::from(\"serveradmin\")")]
+    pub query_user: String,
+    /// password for ServerQuery. Prefer the config file or `TS_UPDATER_QUERY_PASSWORD` over the command line.
+    #[argh(option)]
+    pub query_password: Option<String>,
+    /// archive file name on the mirror. Supports `{version}`, `{target}` and `{ext}` placeholders.
+    #[argh(option, default = "String::from(target::DEFAULT_FILENAME_TEMPLATE)")]
+    pub filename_template: String,
+    /// archive path relative to the mirror URL. Supports `{version}`, `{target}` and `{file}` placeholders. Not used for `ts6`, which archives are found among release assets.
+    #[argh(option, default = "String::from(\"{version}/{file}\")")]
+    pub url_template: String,
+    /// algorithm of the checksum file the downloaded archive is verified with: `sha256`, `sha1` or `md5`. Can be repeated, the first one the mirror has a file for is used. Archives are not verified against the mirror when not given.
+    #[argh(option)]
+    pub checksum_algo: Vec<checksum::Algorithm>,
+    /// checksum file of an algorithm relative to the archive URL, as `algorithm=template` (e.g. `sha1=SHA1SUMS`). Supports `{file}` and `{version}` placeholders. Defaults to `{file}.sha256`, `{file}.sha1` and `{file}.md5`. Can be repeated.
+    #[argh(option)]
+    pub checksum_template: Vec<checksum::Template>,
+    /// user agent sent with all HTTP requests. Defaults to `teamspeak-updater/<version>`.
+    #[argh(option)]
+    pub user_agent: Option<String>,
+    /// extra header sent with all HTTP requests, as `Name: value`. Can be repeated.
+    #[argh(option)]
+    pub header: Vec<remote::Header>,
+    /// host downloads may be fetched from, also after redirects, e.g. `mirror.example.com` or `*.cdn.example.com`. Can be repeated. All hosts are allowed when not given.
+    #[argh(option)]
+    pub allowed_hosts: Vec<remote::AllowedHost>,
+    /// longest total time to wait on a mirror answering 429 or 503 with `Retry-After` before giving up until the next run (e.g. `5m`).
     #[argh(
         option,
-        default = "String::from(\"https://files.teamspeak-services.com/releases/server/\")"
+        default = "humantime::Duration::from(std::time::Duration::from_secs(60))"
     )]
-    pub mirror_url: String,
+    pub max_retry_wait: humantime::Duration,
+    /// number of redirects followed for a single request. 0 refuses all redirects.
+    #[argh(option, default = "10")]
+    pub max_redirects: usize,
+    /// connect to HTTP servers over IPv4 only, e.g. when IPv6 to the mirror is broken.
+    #[argh(switch)]
+    pub ipv4_only: bool,
+    /// connect to HTTP servers over IPv6 only.
+    #[argh(switch)]
+    pub ipv6_only: bool,
+    /// local address HTTP connections are made from, to pick the interface downloads go through.
+    #[argh(option)]
+    pub bind_address: Option<std::net::IpAddr>,
+    /// address used for a host instead of asking DNS, curl-style as `host:port:address`. Can be repeated.
+    #[argh(option)]
+    pub resolve: Vec<remote::Resolve>,
+    /// number of parallel connections used to download the archive in ranges.
+    #[argh(option, default = "1")]
+    pub download_connections: u64,
+    /// number of files copied into a new release at the same time. Lower it when the updater runs out of file descriptors.
+    #[argh(option, default = "32")]
+    pub copy_concurrency: u64,
+    /// unpack tarballs while they download instead of after, to shorten updates of large releases. Needs a single download connection.
+    #[argh(switch)]
+    pub stream_extract: bool,
+    /// number of threads extracting entries of zip archives (Windows and macOS releases) in parallel.
+    #[argh(option, default = "1")]
+    pub extract_threads: u64,
+    /// maximum download speed in bytes per second, shared by all connections. Accepts `K`, `M` and `G` suffixes (e.g. `2M`).
+    #[argh(option)]
+    pub limit_rate: Option<throttle::Rate>,
+    /// path to licensekey.dat kept outside of release directories. It gets symlinked into every new release.
+    #[argh(option)]
+    pub license_path: Option<PathBuf>,
+    /// directory with files copied into every new release, overriding shipped ones. Files ending with `.tmpl` get `{version}`, `{target}`, `{release_dir}`, `{symlink_path}` and `{releases_path}` substituted and are installed without the suffix.
+    #[argh(option)]
+    pub overlay_dir: Option<PathBuf>,
+    /// file or directory, relative to the release, copied from the installed release into the new one (e.g. `ts3server.sqlitedb` or `files`). Can be repeated.
+    #[argh(option)]
+    pub preserve: Vec<PathBuf>,
+    /// directory keeping mutable server configuration (`ts3server.ini`, query IP allow and deny lists) outside of releases. Its files get symlinked into every new release; missing ones are copied from the running installation first.
+    #[argh(option)]
+    pub shared_config_dir: Option<PathBuf>,
+    /// directory with `pre-download.d`, `post-extract.d`, `pre-swap.d`, `post-swap.d` and `on-failure.d` subdirectories of executables run during updates, e.g. `/etc/teamspeak-updater/hooks`.
+    #[argh(option)]
+    pub hooks_dir: Option<PathBuf>,
+    /// shell command run when an update fails. It gets `TS_ERROR`, `TS_FAILED_PHASE` and `TS_LOG_TAIL` environment variables, like `on-failure` hooks.
+    #[argh(option)]
+    pub on_failure: Option<String>,
+    /// http(s) endpoint receiving a JSON notification with the failed phase, error chain and recent events when an update fails.
+    #[argh(option)]
+    pub failure_webhook: Option<String>,
+    /// http(s) endpoint of an OpenTelemetry collector receiving a trace of every run over OTLP/HTTP, e.g. `http://localhost:4318`. Defaults to `OTEL_EXPORTER_OTLP_TRACES_ENDPOINT` or `OTEL_EXPORTER_OTLP_ENDPOINT`.
+    #[argh(option)]
+    pub otlp_endpoint: Option<String>,
+    /// file path or http(s) endpoint receiving a JSON provenance record of the installed release.
+    #[argh(option)]
+    pub artifact_record: Option<String>,
+    /// user (and optionally group, as `user:group`) which should own the installed release directory. Unix only.
+    #[argh(option)]
+    pub owner: Option<String>,
+    /// command used to run privileged operations (symlink swap, chown), e.g. `sudo -n` or `doas`.
+    #[argh(option)]
+    pub escalate_with: Option<String>,
+    /// octal mode of directories created in new releases, e.g. `755`, regardless of the umask. Unix only.
+    #[argh(option)]
+    pub dir_mode: Option<permissions::Mode>,
+    /// octal mode of files created in new releases, e.g. `644`, regardless of the umask. Executables additionally keep the execute bit wherever they are readable. Unix only.
+    #[argh(option)]
+    pub file_mode: Option<permissions::Mode>,
+    /// SELinux context (e.g. `system_u:object_r:usr_t:s0`) applied to new releases with `chcon`, for hosts without a matching file context rule.
+    #[argh(option)]
+    pub selinux_context: Option<String>,
+    /// directory for downloaded archives and extraction before files are moved into the release. Defaults to `.work` inside the releases directory (the target directory in-place).
+    #[argh(option)]
+    pub work_dir: Option<PathBuf>,
+    /// path to a Prometheus textfile collector file which gets metrics of each run.
+    #[argh(option)]
+    pub metrics_textfile: Option<PathBuf>,
+    /// path to the updater state file. Defaults to `.ts-updater-state.json` inside releases directory.
+    #[argh(option)]
+    pub state_path: Option<PathBuf>,
+    /// path to an SQLite database every run gets recorded in, for `report`.
+    #[argh(option)]
+    pub state_db: Option<PathBuf>,
+    /// how long a version which archive is missing on the mirror gets skipped before retrying (e.g. `12h`, `2d`).
+    #[argh(
+        option,
+        default = "humantime::Duration::from(std::time::Duration::from_secs(24 * 60 * 60))"
+    )]
+    pub unavailable_ttl: humantime::Duration,
+    /// longest time a phase of an update may run before it is cancelled and the update rolled back, as `phase=duration` (e.g. `extract=10m`, or `*=30m` for every phase). Can be repeated.
+    #[argh(option)]
+    pub phase_timeout: Vec<events::PhaseTimeout>,
+    /// reuse the mirror listing cached by an earlier run while it is younger than this (e.g. `1h`), instead of fetching it again.
+    #[argh(option)]
+    pub max_listing_age: Option<humantime::Duration>,
+    /// install new versions only once they were published at least this long ago (e.g. `3d`), as dated by the mirror listing.
+    #[argh(option)]
+    pub min_release_age: Option<humantime::Duration>,
+    /// number of releases to stay behind the newest one, e.g. 1 installs the second-newest release.
+    #[argh(option, default = "0")]
+    pub lag: usize,
+    /// day (`2026-12-24`) or range of days (`2026-12-24..2026-12-26`) on which updates are not installed. Can be repeated.
+    #[argh(option)]
+    pub blackout: Vec<schedule::Blackout>,
+    /// install versions flagged as security fixes right away, outside of maintenance windows and regardless of `--min-release-age`.
+    #[argh(switch)]
+    pub security_fast_path: bool,
+    /// version to treat as a security fix under `--security-fast-path`, e.g. one named by an advisory. Can be repeated.
+    #[argh(option)]
+    pub security_release: Vec<semver::Version>,
+    /// directory holding snapshots of the server state. Defaults to `.snapshots` inside releases directory.
+    #[argh(option)]
+    pub snapshots_dir: Option<PathBuf>,
+    /// update remote hosts over SSH instead of this machine, as `user@host` or a comma-separated list of them. Paths and `--preserve` name locations on the hosts. Can be repeated.
+    #[argh(option)]
+    pub ssh: Vec<String>,
+    /// directory holding the cached mirror listing. Defaults to the releases directory.
+    #[argh(option)]
+    pub cache_dir: Option<PathBuf>,
+    /// stop the server with `ts3server_startscript.sh stop` of the installed release before the swap, and start the new release with its script afterwards, checking the pidfile each time.
+    #[argh(switch)]
+    pub startscript: bool,
+    /// label of the LaunchDaemon running the server on macOS (e.g. `com.teamspeak.server`), unloaded before the swap and loaded again afterwards.
+    #[argh(option)]
+    pub launchd_label: Option<String>,
+    /// rc.d service running the server on FreeBSD (e.g. `teamspeak`), stopped with `service <name> stop` before the swap and started again afterwards.
+    #[argh(option)]
+    pub rc_service: Option<String>,
+    /// name of the FreeBSD jail the server lives in. Service commands run inside it with `jexec`, and the symlink is made relative so it resolves inside the jail too.
+    #[argh(option)]
+    pub jail: Option<String>,
+    /// swap releases even when a TeamSpeak server is still running from the installed one.
+    #[argh(switch)]
+    pub force: bool,
+    /// when the symlink path is a real directory, move it into the releases directory and replace it with the symlink before updating, like `adopt` does.
+    #[argh(switch)]
+    pub force_adopt: bool,
+    /// treat a symlink pointing to a missing release directory as no version installed and install the latest one.
+    #[argh(switch)]
+    pub repair: bool,
+    /// version to treat as installed instead of detecting it from the symlink and release directory, e.g. when the layout is broken or for testing.
+    #[argh(option)]
+    pub assume_version: Option<semver::Version>,
+    /// skip checking with `ldd` that this host provides every shared library the server binary of a new release needs. Linux only.
+    #[argh(switch)]
+    pub skip_library_check: bool,
+    /// refuse to proceed when configuration warnings are found.
+    #[argh(switch)]
+    pub strict: bool,
+    /// fail when the name of the active release directory is not exactly a version, like `3.13.7-hotfix1` or `3.13.7 (copy)`, instead of taking the version it starts with.
+    #[argh(switch)]
+    pub strict_release_names: bool,
+    /// hard-link files of the new release which are identical to the installed release's, saving disk space for kept releases.
+    #[argh(switch)]
+    pub link_unchanged: bool,
+    /// install the update without switching to it and print a token; `commit --token <token>` switches later, e.g. on all hosts of a fleet at once.
+    #[argh(switch)]
+    pub stage_only: bool,
+    /// build new releases from the installed one and changed files of the mirror's delta directory (see `build-delta`), falling back to the full archive.
+    #[argh(switch)]
+    pub delta: bool,
+    /// snapshot the server state of the active release (see `snapshot`) before installing an update.
+    #[argh(switch)]
+    pub snapshot_before_update: bool,
+    /// include a ServerQuery `serversnapshotcreate` of every virtual server in snapshots, for databases kept outside of the release like MariaDB.
+    #[argh(switch)]
+    pub query_snapshot: bool,
+    /// include a `mysqldump` of the database in snapshots of servers using the MariaDB plugin, with credentials from its ini file.
+    #[argh(switch)]
+    pub mysqldump: bool,
+    /// restore SELinux contexts of new releases from the file context rules of the policy with `restorecon`.
+    #[argh(switch)]
+    pub selinux_relabel: bool,
+    /// fetch the mirror listing even if the cached one is younger than `--max-listing-age`.
+    #[argh(switch)]
+    pub refresh: bool,
+    /// never use the network: compare against the version cached by the last online check and fail right away where a download is needed.
+    #[argh(switch)]
+    pub offline: bool,
+    /// write the result of every run into the Windows Application event log, with source `TeamSpeakUpdater`.
+    #[argh(switch)]
+    pub event_log: bool,
+    /// write the result of every run to `syslog` or `journald`, with a priority matching its outcome and the versions and failed phase as fields. Unix only.
+    #[argh(option)]
+    pub system_log: Option<crate::system_log::SystemLog>,
+    /// write progress events as JSON lines to stderr.
+    #[argh(switch)]
+    pub json_log: bool,
+    /// print facts about the installation (installed and latest version, whether an update is available, number of releases and symlink target) for configuration management and exit, with no other output.
+    #[argh(switch)]
+    pub facts: bool,
+    /// format of `--facts`, `kv` for `key=value` lines or `json`.
+    #[argh(option, default = "crate::facts::Format::KeyValue")]
+    pub facts_format: crate::facts::Format,
+    #[argh(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(FromArgs, ArgsInfo)]
+#[argh(subcommand)]
+pub enum Command {
+    Update(UpdateCommand),
+    Completions(CompletionsCommand),
+    Adopt(AdoptCommand),
+    Daemon(DaemonCommand),
+    Check(CheckCommand),
+    Verify(VerifyCommand),
+    Doctor(DoctorCommand),
+    BuildDelta(BuildDeltaCommand),
+    Uninstall(UninstallCommand),
+    Status(StatusCommand),
+    RegisterTask(RegisterTaskCommand),
+    Download(DownloadCommand),
+    InstallFromFile(InstallFromFileCommand),
+    MirrorSync(MirrorSyncCommand),
+    Snapshot(SnapshotCommand),
+    Restore(RestoreCommand),
+    RestoreSnapshot(RestoreSnapshotCommand),
+    Inventory(InventoryCommand),
+    Hold(HoldCommand),
+    Unhold(UnholdCommand),
+    List(ListCommand),
+    Compare(CompareCommand),
+    GenerateLaunchd(GenerateLaunchdCommand),
+    BuildImage(BuildImageCommand),
+    Sidecar(SidecarCommand),
+    Fetch(FetchCommand),
+    Install(InstallCommand),
+    Activate(ActivateCommand),
+    Deactivate(DeactivateCommand),
+    Commit(CommitCommand),
+    Report(ReportCommand),
+    Versions(VersionsCommand),
+    Clean(CleanCommand),
+}
+
+/// Check for update and install new TeamSpeak version. This is the default command.
+#[derive(FromArgs, ArgsInfo)]
+#[argh(subcommand, name = "update")]
+pub struct UpdateCommand {}
+
+/// Move an existing TeamSpeak installation into the releases layout and create the symlink.
+#[derive(FromArgs, ArgsInfo)]
+#[argh(subcommand, name = "adopt")]
+pub struct AdoptCommand {
+    /// directory of the existing installation. Defaults to the symlink path.
+    #[argh(option)]
+    pub server_dir: Option<PathBuf>,
+    /// version of the existing installation, when it can't be detected from its changelog.
+    #[argh(option)]
+    pub version: Option<semver::Version>,
+}
+
+/// Keep running and check for updates periodically, installing them during maintenance windows.
+#[derive(FromArgs, ArgsInfo)]
+#[argh(subcommand, name = "daemon")]
+pub struct DaemonCommand {
+    /// how often the mirror gets checked for a new version (e.g. `30m`, `6h`).
+    #[argh(
+        option,
+        default = "humantime::Duration::from(std::time::Duration::from_secs(60 * 60))"
+    )]
+    pub interval: humantime::Duration,
+    /// window in which updates get installed, e.g. `Sun 03:00-05:00`, or `03:00-05:00` for every day. Can be repeated. Without it updates are installed right away.
+    #[argh(option)]
+    pub window: Vec<schedule::Window>,
+    /// address to serve the HTTP API on, e.g. `127.0.0.1:9555`. It offers `GET /status` and `POST /update`.
+    #[argh(option)]
+    pub listen: Option<std::net::SocketAddr>,
+    /// bearer token required by `POST /update`, which triggers an immediate check and installs an update outside of maintenance windows. Without it updates can't be triggered.
+    #[argh(option)]
+    pub api_token: Option<String>,
+    /// how often a failure or pending update that didn't change is notified again (e.g. `12h`). A different error or a newer version is notified right away.
+    #[argh(
+        option,
+        default = "humantime::Duration::from(std::time::Duration::from_secs(24 * 60 * 60))"
+    )]
+    pub reminder_interval: humantime::Duration,
+}
+
+/// Keep server files on a shared volume current for Kubernetes, as an initContainer with `--once` or as a sidecar. Requires the in-place install mode.
+#[derive(FromArgs, ArgsInfo)]
+#[argh(subcommand, name = "sidecar")]
+pub struct SidecarCommand {
+    /// check and update once, then exit, as an initContainer does.
+    #[argh(switch)]
+    pub once: bool,
+    /// how often the mirror gets checked when running as a sidecar (e.g. `30m`, `6h`).
+    #[argh(
+        option,
+        default = "humantime::Duration::from(std::time::Duration::from_secs(60 * 60))"
+    )]
+    pub interval: humantime::Duration,
+    /// file holding the installed version while the server files are complete, for readiness probes. Defaults to `.ts-updater-ready` inside the target directory.
+    #[argh(option)]
+    pub ready_file: Option<PathBuf>,
+}
+
+/// Compare installed and published versions, printing one line and exiting with a Nagios-compatible code.
+#[derive(FromArgs, ArgsInfo)]
+#[argh(subcommand, name = "check")]
+pub struct CheckCommand {}
+
+/// Re-hash a release directory and report files which differ from its manifest.
+#[derive(FromArgs, ArgsInfo)]
+#[argh(subcommand, name = "verify")]
+pub struct VerifyCommand {
+    /// version of the release to verify. Defaults to the installed one.
+    #[argh(positional)]
+    pub version: Option<semver::Version>,
+}
+
+/// Print a machine-readable inventory of a release: version, source, install time and hashes of its files.
+#[derive(FromArgs, ArgsInfo)]
+#[argh(subcommand, name = "inventory")]
+pub struct InventoryCommand {
+    /// version of the release. Defaults to the installed one.
+    #[argh(positional)]
+    pub version: Option<semver::Version>,
+    /// output format, `json` or `cyclonedx`.
+    #[argh(option, default = "crate::inventory::Format::Json")]
+    pub format: crate::inventory::Format,
+    /// file to write the inventory to instead of the standard output.
+    #[argh(option)]
+    pub out: Option<PathBuf>,
+}
+
+/// Freeze updates until `unhold`: new versions are still detected and reported, but not installed.
+#[derive(FromArgs, ArgsInfo)]
+#[argh(subcommand, name = "hold")]
+pub struct HoldCommand {
+    /// why updates are held, shown by `update` and `status`.
+    #[argh(option)]
+    pub reason: Option<String>,
+}
+
+/// Allow updates again after `hold`.
+#[derive(FromArgs, ArgsInfo)]
+#[argh(subcommand, name = "unhold")]
+pub struct UnholdCommand {}
+
+/// List installed releases, or versions published on the mirror with their release dates.
+#[derive(FromArgs, ArgsInfo)]
+#[argh(subcommand, name = "list")]
+pub struct ListCommand {
+    /// list versions published on the mirror instead of installed ones.
+    #[argh(switch)]
+    pub remote: bool,
+}
+
+/// Print versions published on the mirror, newest first, with their release dates when known. Meant for scripts.
+#[derive(FromArgs, ArgsInfo)]
+#[argh(subcommand, name = "versions")]
+pub struct VersionsCommand {
+    /// print only this many of the newest versions.
+    #[argh(option)]
+    pub limit: Option<usize>,
+    /// print the versions as a JSON array of objects with `version` and `published_at`.
+    #[argh(switch)]
+    pub json: bool,
+}
+
+/// Compare two versions: their files if both are installed, otherwise their release metadata from the mirror.
+#[derive(FromArgs, ArgsInfo)]
+#[argh(subcommand, name = "compare")]
+pub struct CompareCommand {
+    /// version to compare from.
+    #[argh(positional)]
+    pub from: semver::Version,
+    /// version to compare to.
+    #[argh(positional)]
+    pub to: semver::Version,
+}
+
+/// Diagnose the environment: installed release, directories, mirror, host and server service.
+#[derive(FromArgs, ArgsInfo)]
+#[argh(subcommand, name = "doctor")]
+pub struct DoctorCommand {
+    /// systemd unit running the TeamSpeak server, checked with `systemctl is-active`.
+    #[argh(option)]
+    pub service: Option<String>,
+}
+
+/// Summarize installed releases, the last check, pending updates and the daemon.
+#[derive(FromArgs, ArgsInfo)]
+#[argh(subcommand, name = "status")]
+pub struct StatusCommand {
+    /// print the status as JSON.
+    #[argh(switch)]
+    pub json: bool,
+}
+
+/// Remove an installed release which is not active, together with its records in the updater state.
+#[derive(FromArgs, ArgsInfo)]
+#[argh(subcommand, name = "uninstall")]
+pub struct UninstallCommand {
+    /// version of the release to remove.
+    #[argh(positional)]
+    pub version: semver::Version,
+}
+
+/// Remove the listing cache, downloads and staging directories left by interrupted updates, and old symlinks.
+#[derive(FromArgs, ArgsInfo)]
+#[argh(subcommand, name = "clean")]
+pub struct CleanCommand {
+    /// list what would be removed and the space it takes, without removing anything.
+    #[argh(switch)]
+    pub dry_run: bool,
+    /// number of old symlinks to other releases to keep for `deactivate`. Defaults to 1.
+    #[argh(option, default = "1")]
+    pub keep_backups: usize,
+}
+
+/// Write the delta directory of an extracted release for mirrors serving delta updates.
+#[derive(FromArgs, ArgsInfo)]
+#[argh(subcommand, name = "build-delta")]
+pub struct BuildDeltaCommand {
+    /// extracted release directory.
+    #[argh(positional)]
+    pub release_dir: PathBuf,
+    /// directory receiving the index and the files, to be published as `<archive URL>.delta/`.
+    #[argh(positional)]
+    pub output_dir: PathBuf,
+    /// version of the release. Defaults to the one recorded in its manifest.
+    #[argh(option)]
+    pub version: Option<semver::Version>,
+}
+
+/// Download and checksum an archive into a directory without touching the installation.
+#[derive(FromArgs, ArgsInfo)]
+#[argh(subcommand, name = "download")]
+pub struct DownloadCommand {
+    /// version to download. Defaults to the latest published one.
+    #[argh(option)]
+    pub version: Option<semver::Version>,
+    /// directory receiving the archive and its `.sha256` file.
+    #[argh(option)]
+    pub out: PathBuf,
+    /// expected SHA256 checksum of the archive, the download fails if it doesn't match.
+    #[argh(option)]
+    pub sha256: Option<String>,
+    /// target tuple to download the archive for, or `all` for every one. Can be repeated. Defaults to the configured target tuple.
+    #[argh(option)]
+    pub target_tuple: Vec<target::Selection>,
+    /// place archives at their path on the mirror (see `--url-template`), so the output directory can be served as a mirror.
+    #[argh(switch)]
+    pub mirror_layout: bool,
+}
+
+/// Archive the server state (database, files, license and config) of the active release into the snapshots directory.
+#[derive(FromArgs, ArgsInfo)]
+#[argh(subcommand, name = "snapshot")]
+pub struct SnapshotCommand {
+    /// list existing snapshots instead of taking one.
+    #[argh(switch)]
+    pub list: bool,
+}
+
+/// Restore server state from a snapshot into a release, replacing its database, files, license and config.
+#[derive(FromArgs, ArgsInfo)]
+#[argh(subcommand, name = "restore")]
+pub struct RestoreCommand {
+    /// snapshot ID, as printed by `snapshot --list`.
+    #[argh(positional)]
+    pub id: String,
+    /// version of the release to restore into. Defaults to the active one.
+    #[argh(option)]
+    pub version: Option<semver::Version>,
+}
+
+/// Deploy the ServerQuery snapshots of a snapshot (see `--query-snapshot`) back into the running server.
+#[derive(FromArgs, ArgsInfo)]
+#[argh(subcommand, name = "restore-snapshot")]
+pub struct RestoreSnapshotCommand {
+    /// snapshot ID, as printed by `snapshot --list`.
+    #[argh(positional)]
+    pub id: String,
+    /// deploy only the snapshot of this virtual server.
+    #[argh(option)]
+    pub server_id: Option<u64>,
+}
+
+/// Replicate the mirror (version directories, archives and checksum files) into a directory which can be served as `--mirror-url`.
+#[derive(FromArgs, ArgsInfo)]
+#[argh(subcommand, name = "mirror-sync")]
+pub struct MirrorSyncCommand {
+    /// directory receiving the mirror.
+    #[argh(option)]
+    pub dest: PathBuf,
+    /// sync only the given number of most recent versions.
+    #[argh(option)]
+    pub last: Option<usize>,
+    /// target tuple to sync archives for. Can be repeated. Defaults to all of them.
+    #[argh(option)]
+    pub target_tuple: Vec<target::Selection>,
+    /// remove version directories which are no longer synced, e.g. older than `--last`.
+    #[argh(switch)]
+    pub prune: bool,
+}
+
+/// Download and install a release next to the active one without switching to it, for `activate` or `update` to switch later.
+#[derive(FromArgs, ArgsInfo)]
+#[argh(subcommand, name = "install")]
+pub struct InstallCommand {
+    /// version to install. Defaults to the latest published one.
+    #[argh(option)]
+    pub version: Option<semver::Version>,
+}
+
+/// Switch the server to an installed release: stop it, point the symlink to the release and start it again.
+#[derive(FromArgs, ArgsInfo)]
+#[argh(subcommand, name = "activate")]
+pub struct ActivateCommand {
+    /// version to activate. Defaults to the newest installed release.
+    #[argh(positional)]
+    pub version: Option<semver::Version>,
+}
+
+/// Switch the server back to the release which was active before the last switch.
+#[derive(FromArgs, ArgsInfo)]
+#[argh(subcommand, name = "deactivate")]
+pub struct DeactivateCommand {}
+
+/// Switch to the release staged by `--stage-only`, given the token it printed.
+#[derive(FromArgs, ArgsInfo)]
+#[argh(subcommand, name = "commit")]
+pub struct CommitCommand {
+    /// token printed when the release was staged, making sure the staged release is the one expected.
+    #[argh(option)]
+    pub token: String,
+}
+
+/// Summarize runs recorded in the `--state-db` database: the last update and recent failures of every host.
+#[derive(FromArgs, ArgsInfo)]
+#[argh(subcommand, name = "report")]
+pub struct ReportCommand {
+    /// number of days runs, failures and updates are counted over.
+    #[argh(option, default = "30")]
+    pub days: u64,
+}
+
+/// Install the most recent published versions as releases next to the active one, without activating them.
+#[derive(FromArgs, ArgsInfo)]
+#[argh(subcommand, name = "fetch")]
+pub struct FetchCommand {
+    /// number of most recent published versions to install. Versions already installed are skipped.
+    #[argh(option)]
+    pub last: usize,
+    /// number of versions downloaded and installed at the same time.
+    #[argh(option, default = "3")]
+    pub concurrency: usize,
+}
+
+/// Install TeamSpeak from a local archive without contacting the mirror.
+#[derive(FromArgs, ArgsInfo)]
+#[argh(subcommand, name = "install-from-file")]
+pub struct InstallFromFileCommand {
+    /// archive to install.
+    #[argh(positional)]
+    pub archive: PathBuf,
+    /// version of the archive. Defaults to the one in its file name.
+    #[argh(option)]
+    pub version: Option<semver::Version>,
+    /// checksum file in `sha256sum` format to verify the archive with. Defaults to `<archive>.sha256` if it exists.
+    #[argh(option)]
+    pub sha256_file: Option<PathBuf>,
+}
+
+/// Create a daily Windows Scheduled Task running the updater with the options given before this subcommand.
+#[derive(FromArgs, ArgsInfo)]
+#[argh(subcommand, name = "register-task")]
+pub struct RegisterTaskCommand {
+    /// name of the scheduled task.
+    #[argh(option, default = "String::from(\"TeamSpeak Updater\")")]
+    pub task_name: String,
+    /// time of day the task runs at, as `HH:MM`.
+    #[argh(option, default = "String::from(\"04:00\")")]
+    pub time: String,
+    /// account the task runs as.
+    #[argh(option, default = "String::from(\"SYSTEM\")")]
+    pub user: String,
+}
+
+/// Print a macOS LaunchDaemon property list running the server through the symlink path, labelled with `--launchd-label`.
+#[derive(FromArgs, ArgsInfo)]
+#[argh(subcommand, name = "generate-launchd")]
+pub struct GenerateLaunchdCommand {
+    /// account the server runs as. Defaults to root.
+    #[argh(option)]
+    pub user: Option<String>,
+    /// start the server with `license_accepted=1`, accepting the TeamSpeak license agreement.
+    #[argh(switch)]
+    pub accept_license: bool,
+    /// file to write the property list to instead of the standard output.
+    #[argh(option)]
+    pub out: Option<PathBuf>,
+}
+
+/// Write a `docker build` context with a server release and a Dockerfile, and optionally build the image tagged with its version.
+#[derive(FromArgs, ArgsInfo)]
+#[argh(subcommand, name = "build-image")]
+pub struct BuildImageCommand {
+    /// version to put into the image. Defaults to the latest one on the mirror.
+    #[argh(positional)]
+    pub version: Option<semver::Version>,
+    /// directory of the build context, created if missing. Server files of another version in it get replaced.
+    #[argh(option)]
+    pub out: PathBuf,
+    /// name of the image, tagged with the version and `latest`.
+    #[argh(option, default = "String::from(\"teamspeak\")")]
+    pub name: String,
+    /// image the server image is based on.
+    #[argh(option, default = "String::from(\"debian:bookworm-slim\")")]
+    pub base_image: String,
+    /// build the image after writing the build context.
+    #[argh(switch)]
+    pub build: bool,
+    /// container tool building the image, e.g. `docker` or `podman`.
+    #[argh(option, default = "String::from(\"docker\")")]
+    pub builder: String,
+}
+
+/// Print shell completion script.
+#[derive(FromArgs, ArgsInfo)]
+#[argh(subcommand, name = "completions")]
+pub struct CompletionsCommand {
+    /// shell to generate completion script for: bash, zsh, fish or powershell.
+    #[argh(positional)]
+    pub shell: completions::Shell,
 }
 
 impl Config {
+    /// How often the daemon repeats notifications about a failure or pending update that didn't change. `None` outside
+    /// of daemon mode, where every run notifies.
+    pub fn reminder_interval(&self) -> Option<std::time::Duration> {
+        match &self.command {
+            Some(Command::Daemon(command)) => Some(command.reminder_interval.into()),
+            _ => None,
+        }
+    }
+
     pub fn print_summary(&self) {
         println!("🔧 Configuration Summary");
-        println!(
-            "Symlink of current TeamSpeak directory: {}",
-            self.symlink_path.to_string_lossy()
-        );
-        println!(
-            "Directory containing TeamSpeak releases: {}",
-            self.releases_path.to_string_lossy()
-        );
+        if let Some(config) = &self.config {
+            println!("Config file: {}", config.to_string_lossy());
+        }
+        if let Some(profile) = &self.profile {
+            println!("Config profile: {}", profile);
+        }
+        match local::in_place_dir(self) {
+            Some(target_dir) => println!(
+                "TeamSpeak directory updated in place: {}",
+                target_dir.to_string_lossy()
+            ),
+            None => {
+                println!(
+                    "Symlink of current TeamSpeak directory: {}",
+                    self.symlink_path.to_string_lossy()
+                );
+                println!(
+                    "Directory containing TeamSpeak releases: {}",
+                    self.releases_path.to_string_lossy()
+                );
+                if self.release_dir_name != layout::ReleaseLayout::default() {
+                    println!("Release directory name: {}", self.release_dir_name);
+                }
+            }
+        }
         println!(
             "Mirror URL used to check for TeamSpeak versions: {}",
             self.mirror_url
         );
+        if self.mirror_token.is_some() {
+            println!("Mirror authentication: bearer token");
+        } else if let Some(mirror_user) = &self.mirror_user {
+            println!("Mirror authentication: basic, as {}", mirror_user);
+        }
+        if !self.allowed_hosts.is_empty() {
+            let allowed_hosts = self
+                .allowed_hosts
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>();
+            println!("Allowed download hosts: {}", allowed_hosts.join(", "));
+        }
+        if let Some(bind_address) = &self.bind_address {
+            println!("HTTP connections from: {}", bind_address);
+        } else if self.ipv4_only {
+            println!("HTTP connections over: IPv4 only");
+        } else if self.ipv6_only {
+            println!("HTTP connections over: IPv6 only");
+        }
+        for resolve in &self.resolve {
+            println!("Static host address: {}", resolve);
+        }
+        if let Some(assume_version) = &self.assume_version {
+            println!(
+                "Installed version: assumed {}, not detected",
+                assume_version
+            );
+        }
+        println!("Server product: {}", self.product);
         println!("Package target tuple: {}", self.target_tuple,);
+        println!("Archive file name template: {}", self.filename_template);
+        println!("Archive URL template: {}", self.url_template);
+        if !self.checksum_algo.is_empty() {
+            let checksum_algo = self
+                .checksum_algo
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>();
+            println!("Checksum algorithms: {}", checksum_algo.join(", "));
+        }
+        for checksum_template in &self.checksum_template {
+            println!("Checksum file template: {}", checksum_template);
+        }
+        if let Some(limit_rate) = &self.limit_rate {
+            println!("Download rate limit: {}/s", limit_rate);
+        }
+        if let Some(owner) = &self.owner {
+            println!("Release owner: {}", owner);
+        }
+        for phase_timeout in &self.phase_timeout {
+            println!("Phase timeout: {}", phase_timeout);
+        }
+        if self.lag > 0 {
+            println!("Releases behind the latest: {}", self.lag);
+        }
+        if let Some(min_release_age) = &self.min_release_age {
+            println!("Minimum release age: {}", min_release_age);
+        }
+        for blackout in &self.blackout {
+            println!("Blackout dates: {}", blackout);
+        }
+        if self.security_fast_path {
+            println!("Security fixes bypass maintenance windows and the minimum release age");
+        }
+        for security_release in &self.security_release {
+            println!("Security release: {}", security_release);
+        }
+        if let Some(max_listing_age) = &self.max_listing_age {
+            println!("Maximum listing age: {}", max_listing_age);
+        }
+        if self.offline {
+            println!("Offline mode: no network access");
+        }
+        if let Some(dir_mode) = &self.dir_mode {
+            println!("Directory mode: {}", dir_mode);
+        }
+        if let Some(file_mode) = &self.file_mode {
+            println!("File mode: {}", file_mode);
+        }
+        if let Some(escalate_with) = &self.escalate_with {
+            println!("Privilege escalation command: {}", escalate_with);
+        }
+        if self.selinux_relabel {
+            println!("SELinux contexts: restored with restorecon");
+        }
+        if let Some(selinux_context) = &self.selinux_context {
+            println!("SELinux context: {}", selinux_context);
+        }
+        if self.query_snapshot {
+            println!(
+                "ServerQuery snapshots: {} as {}",
+                self.query_address, self.query_user
+            );
+        }
+        if self.mysqldump {
+            println!("MariaDB dumps: mysqldump");
+        }
+        if self.snapshot_before_update {
+            println!(
+                "Snapshots before updates: {}",
+                crate::snapshot::snapshots_dir(self).to_string_lossy()
+            );
+        }
+        if let Some(work_dir) = &self.work_dir {
+            println!("Work directory: {}", work_dir.to_string_lossy());
+        }
+        if let Some(license_path) = &self.license_path {
+            println!("License file: {}", license_path.to_string_lossy());
+        }
+        if let Some(overlay_dir) = &self.overlay_dir {
+            println!("Overlay directory: {}", overlay_dir.to_string_lossy());
+        }
+        if !self.ssh.is_empty() {
+            println!(
+                "Hosts updated over SSH: {}",
+                crate::fleet::hosts(self).join(", ")
+            );
+        }
+        if self.startscript {
+            println!("Server stopped and started with: ts3server_startscript.sh");
+        }
+        if let Some(launchd_label) = &self.launchd_label {
+            println!(
+                "Server stopped and started with: launchd job {}",
+                launchd_label
+            );
+        }
+        if let Some(rc_service) = &self.rc_service {
+            println!(
+                "Server stopped and started with: rc.d service {}",
+                rc_service
+            );
+        }
+        if let Some(jail) = &self.jail {
+            println!("Server jail: {}", jail);
+        }
+        if let Some(system_log) = &self.system_log {
+            println!("System log: {}", system_log);
+        }
+        if let Some(otlp_endpoint) = crate::otlp::traces_endpoint(self) {
+            println!("OTLP traces endpoint: {}", otlp_endpoint);
+        }
+        if let Some(hooks_dir) = &self.hooks_dir {
+            println!("Hooks directory: {}", hooks_dir.to_string_lossy());
+        }
+        if let Some(shared_config_dir) = &self.shared_config_dir {
+            println!(
+                "Shared config directory: {}",
+                shared_config_dir.to_string_lossy()
+            );
+        }
         println!();
     }
 }
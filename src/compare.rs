@@ -0,0 +1,116 @@
+use crate::{
+    changes,
+    cli::{self, CompareCommand, Config},
+    local,
+    manifest::{self, Manifest},
+    remote,
+};
+use anyhow::{anyhow, Result};
+use semver::Version;
+
+const DATE_FORMAT: &str = "%Y-%m-%d %H:%M";
+
+/// Compares two versions: their files if both are installed, otherwise what the mirror tells about them.
+pub async fn run(config: &Config, command: &CompareCommand) -> Result<()> {
+    let CompareCommand { from, to } = command;
+
+    cli::print_header();
+    println!("🔀 {} → {}: {}", from, to, kind(from, to));
+
+    let manifests = match (
+        installed_manifest(config, from).await?,
+        installed_manifest(config, to).await?,
+    ) {
+        (Some(from_manifest), Some(to_manifest)) => Some((from_manifest, to_manifest)),
+        _ => None,
+    };
+    if let Some((from_manifest, to_manifest)) = &manifests {
+        match changes::between(config, from_manifest, to_manifest) {
+            Some(changes) => {
+                changes::print(&changes, None);
+                return Ok(());
+            }
+            None => println!(
+                "⚠️ Manifests of these releases don't record file hashes, comparing release metadata"
+            ),
+        }
+    }
+
+    let http = remote::client(config)?;
+    let releases = remote::published_releases(config, &http).await?;
+    let published_at = |version: &Version| {
+        releases
+            .iter()
+            .find(|(published, _)| published == version)
+            .map(|(_, published_at)| *published_at)
+    };
+
+    let (from_published_at, to_published_at) = (published_at(from), published_at(to));
+    for (version, published_at) in [(from, from_published_at), (to, to_published_at)] {
+        match published_at {
+            Some(Some(published_at)) => println!(
+                "🌐 {:<16} published {}",
+                version,
+                published_at.format(DATE_FORMAT)
+            ),
+            Some(None) => println!("🌐 {:<16} published, date unknown", version),
+            None if manifests.is_some() => println!("🌐 {:<16} not on the mirror", version),
+            None => {
+                return Err(anyhow!(
+                    "version {} is neither installed nor published on {}",
+                    version,
+                    config.mirror_url
+                ))
+            }
+        }
+    }
+
+    if from_published_at.is_some() && to_published_at.is_some() {
+        let (older, newer) = if from < to { (from, to) } else { (to, from) };
+        let between = releases
+            .iter()
+            .filter(|(version, _)| version > older && version < newer)
+            .count();
+        println!("   {} releases in between", between);
+    }
+    if let (Some(Some(from_published_at)), Some(Some(to_published_at))) =
+        (from_published_at, to_published_at)
+    {
+        println!(
+            "   {} days apart",
+            (to_published_at - from_published_at).num_days().abs()
+        );
+    }
+
+    Ok(())
+}
+
+/// Manifest of `version` if it is installed.
+async fn installed_manifest(config: &Config, version: &Version) -> Result<Option<Manifest>> {
+    let release_path = local::release_path(config, version)?;
+    if !release_path.is_dir() {
+        return Ok(None);
+    }
+
+    manifest::read(&release_path).await
+}
+
+/// Which part of the version changes, and in which direction.
+fn kind(from: &Version, to: &Version) -> String {
+    if from == to {
+        return String::from("same version");
+    }
+
+    let part = if from.major != to.major {
+        "major"
+    } else if from.minor != to.minor {
+        "minor"
+    } else if from.patch != to.patch {
+        "patch"
+    } else {
+        "pre-release"
+    };
+    let direction = if from < to { "update" } else { "downgrade" };
+
+    format!("{} {}", part, direction)
+}
@@ -0,0 +1,400 @@
+use crate::cli::Config;
+use argh::{ArgsInfo, CommandInfoWithArgs, FlagInfo, FlagInfoKind};
+use std::{fmt::Write, str::FromStr};
+use thiserror::Error;
+
+const BINARY_NAME: &str = "teamspeak-updater";
+const SHELLS: [&str; 4] = ["bash", "zsh", "fish", "powershell"];
+
+#[derive(Clone, Copy)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+    Powershell,
+}
+
+#[derive(Debug, Error)]
+pub enum ShellError {
+    #[error("shell not supported: {0}")]
+    NotSupported(String),
+}
+
+impl FromStr for Shell {
+    type Err = ShellError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "bash" => Ok(Self::Bash),
+            "zsh" => Ok(Self::Zsh),
+            "fish" => Ok(Self::Fish),
+            "powershell" | "pwsh" => Ok(Self::Powershell),
+            _ => Err(ShellError::NotSupported(s.to_owned())),
+        }
+    }
+}
+
+/// Flattened view of a command: its name (empty for the top-level one), flags and possible positional values.
+struct Command {
+    name: &'static str,
+    description: &'static str,
+    flags: Vec<&'static FlagInfo<'static>>,
+    values: &'static [&'static str],
+}
+
+pub fn print(shell: Shell) {
+    let info = Config::get_args_info();
+    let commands = commands(&info);
+
+    let script = match shell {
+        Shell::Bash => bash(&commands),
+        Shell::Zsh => zsh(&commands),
+        Shell::Fish => fish(&commands),
+        Shell::Powershell => powershell(&commands),
+    };
+
+    print!("{}", script);
+}
+
+fn commands(info: &CommandInfoWithArgs) -> Vec<Command> {
+    let visible_flags = |info: &CommandInfoWithArgs| {
+        info.flags
+            .iter()
+            .filter(|flag| !flag.hidden && flag.long != "--help")
+            .collect()
+    };
+
+    let mut commands = vec![Command {
+        name: "",
+        description: info.description,
+        flags: visible_flags(info),
+        values: &[],
+    }];
+
+    commands.extend(info.commands.iter().map(|subcommand| Command {
+        name: subcommand.name,
+        description: subcommand.command.description,
+        flags: visible_flags(&subcommand.command),
+        values: positional_values(subcommand.name),
+    }));
+
+    commands
+}
+
+fn positional_values(command_name: &str) -> &'static [&'static str] {
+    match command_name {
+        "completions" => &SHELLS,
+        _ => &[],
+    }
+}
+
+fn takes_value(flag: &FlagInfo) -> bool {
+    matches!(flag.kind, FlagInfoKind::Option { .. })
+}
+
+fn completes_paths(flag: &FlagInfo) -> bool {
+    ["-path", "-dir", "-file", "-textfile"]
+        .iter()
+        .any(|suffix| flag.long.ends_with(suffix))
+}
+
+/// First sentence of the description, used as a short hint.
+fn summary(description: &str) -> String {
+    let sentence_end = description
+        .match_indices(". ")
+        .find(|(index, _)| {
+            description[index + 2..]
+                .chars()
+                .next()
+                .is_some_and(char::is_uppercase)
+        })
+        .map(|(index, _)| index)
+        .unwrap_or(description.len());
+
+    description[..sentence_end].trim_end_matches('.').to_owned()
+}
+
+fn bash(commands: &[Command]) -> String {
+    let (root, subcommands) = commands.split_first().expect("root command is present");
+    let function = format!("_{}", BINARY_NAME.replace('-', "_"));
+    let subcommand_names = subcommands
+        .iter()
+        .map(|command| command.name)
+        .collect::<Vec<_>>();
+    let value_flags = |paths: bool| {
+        commands
+            .iter()
+            .flat_map(|command| command.flags.iter())
+            .filter(|flag| takes_value(flag) && completes_paths(flag) == paths)
+            .map(|flag| flag.long)
+            .collect::<Vec<_>>()
+    };
+
+    let words = |command: &Command, extra: &[&str]| {
+        command
+            .flags
+            .iter()
+            .map(|flag| flag.long)
+            .chain(command.values.iter().copied())
+            .chain(extra.iter().copied())
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+
+    let mut out = String::new();
+    let _ = writeln!(out, "{}() {{", function);
+    let _ = writeln!(out, "    local cur prev subcommand word opts");
+    let _ = writeln!(out, "    cur=\"${{COMP_WORDS[COMP_CWORD]}}\"");
+    let _ = writeln!(out, "    prev=\"${{COMP_WORDS[COMP_CWORD-1]}}\"");
+    let _ = writeln!(out, "    subcommand=\"\"");
+    let _ = writeln!(
+        out,
+        "    for word in \"${{COMP_WORDS[@]:1:COMP_CWORD-1}}\"; do"
+    );
+    let _ = writeln!(out, "        case \"$word\" in");
+    let _ = writeln!(
+        out,
+        "            {}) subcommand=\"$word\" ;;",
+        subcommand_names.join("|")
+    );
+    let _ = writeln!(out, "        esac");
+    let _ = writeln!(out, "    done");
+    let _ = writeln!(out, "    case \"$prev\" in");
+    for (flags, completion) in [
+        (value_flags(true), "$(compgen -f -- \"$cur\")"),
+        (value_flags(false), ""),
+    ] {
+        if flags.is_empty() {
+            continue;
+        }
+        let _ = writeln!(out, "        {})", flags.join("|"));
+        let _ = writeln!(out, "            COMPREPLY=( {} )", completion);
+        let _ = writeln!(out, "            return ;;");
+    }
+    let _ = writeln!(out, "    esac");
+    let _ = writeln!(out, "    case \"$subcommand\" in");
+    for command in subcommands {
+        let _ = writeln!(
+            out,
+            "        {}) opts=\"{}\" ;;",
+            command.name,
+            words(command, &[])
+        );
+    }
+    let _ = writeln!(
+        out,
+        "        *) opts=\"{}\" ;;",
+        words(root, &subcommand_names)
+    );
+    let _ = writeln!(out, "    esac");
+    let _ = writeln!(out, "    COMPREPLY=( $(compgen -W \"$opts\" -- \"$cur\") )");
+    let _ = writeln!(out, "}}");
+    let _ = writeln!(out, "complete -F {} {}", function, BINARY_NAME);
+
+    out
+}
+
+fn zsh(commands: &[Command]) -> String {
+    let (root, subcommands) = commands.split_first().expect("root command is present");
+    let escape = |description: &str| {
+        summary(description)
+            .replace('\'', "'\\''")
+            .replace('[', "\\[")
+            .replace(']', "\\]")
+            .replace(':', "\\:")
+    };
+    let arguments = |command: &Command| {
+        let mut arguments = command
+            .flags
+            .iter()
+            .map(|flag| {
+                let name = flag.long.trim_start_matches('-');
+                if takes_value(flag) {
+                    format!(
+                        "'{}[{}]:{}:{}'",
+                        flag.long,
+                        escape(flag.description),
+                        name,
+                        if completes_paths(flag) { "_files" } else { " " }
+                    )
+                } else {
+                    format!("'{}[{}]'", flag.long, escape(flag.description))
+                }
+            })
+            .collect::<Vec<_>>();
+        if !command.values.is_empty() {
+            arguments.push(format!("'1: :({})'", command.values.join(" ")));
+        }
+        arguments
+    };
+
+    let mut out = String::new();
+    let _ = writeln!(out, "#compdef {}", BINARY_NAME);
+    let _ = writeln!(out);
+    let _ = writeln!(out, "_{}() {{", BINARY_NAME.replace('-', "_"));
+    let _ = writeln!(out, "    local line state");
+    let _ = writeln!(out, "    local -a subcommands");
+    let _ = writeln!(out, "    subcommands=(");
+    for command in subcommands {
+        let _ = writeln!(
+            out,
+            "        '{}:{}'",
+            command.name,
+            escape(command.description)
+        );
+    }
+    let _ = writeln!(out, "    )");
+    let _ = writeln!(out, "    _arguments -C \\");
+    for argument in arguments(root) {
+        let _ = writeln!(out, "        {} \\", argument);
+    }
+    let _ = writeln!(out, "        '1: :->subcommand' \\");
+    let _ = writeln!(out, "        '*:: :->arguments'");
+    let _ = writeln!(out, "    case $state in");
+    let _ = writeln!(out, "        subcommand)");
+    let _ = writeln!(out, "            _describe 'command' subcommands ;;");
+    let _ = writeln!(out, "        arguments)");
+    let _ = writeln!(out, "            case $line[1] in");
+    for command in subcommands {
+        let arguments = arguments(command);
+        if arguments.is_empty() {
+            continue;
+        }
+        let _ = writeln!(out, "                {})", command.name);
+        let _ = writeln!(
+            out,
+            "                    _arguments {} ;;",
+            arguments.join(" ")
+        );
+    }
+    let _ = writeln!(out, "            esac ;;");
+    let _ = writeln!(out, "    esac");
+    let _ = writeln!(out, "}}");
+    let _ = writeln!(out);
+    let _ = writeln!(out, "_{} \"$@\"", BINARY_NAME.replace('-', "_"));
+
+    out
+}
+
+fn fish(commands: &[Command]) -> String {
+    let (root, subcommands) = commands.split_first().expect("root command is present");
+    let escape = |description: &str| summary(description).replace('\'', "\\'");
+    let subcommand_names = subcommands
+        .iter()
+        .map(|command| command.name)
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    let mut out = String::new();
+    let mut complete = |condition: &str, command: &Command| {
+        for flag in &command.flags {
+            let _ = writeln!(
+                out,
+                "complete -c {} -n '{}' -l {}{} -d '{}'",
+                BINARY_NAME,
+                condition,
+                flag.long.trim_start_matches('-'),
+                match (takes_value(flag), completes_paths(flag)) {
+                    (true, true) => " -r -F",
+                    (true, false) => " -x",
+                    _ => "",
+                },
+                escape(flag.description)
+            );
+        }
+        if !command.values.is_empty() {
+            let _ = writeln!(
+                out,
+                "complete -c {} -n '{}' -f -a '{}'",
+                BINARY_NAME,
+                condition,
+                command.values.join(" ")
+            );
+        }
+    };
+
+    let root_condition = format!("not __fish_seen_subcommand_from {}", subcommand_names);
+    complete(&root_condition, root);
+    for command in subcommands {
+        complete(
+            &format!("__fish_seen_subcommand_from {}", command.name),
+            command,
+        );
+    }
+
+    for command in subcommands {
+        let _ = writeln!(
+            out,
+            "complete -c {} -n '{}' -f -a '{}' -d '{}'",
+            BINARY_NAME,
+            root_condition,
+            command.name,
+            escape(command.description)
+        );
+    }
+
+    out
+}
+
+fn powershell(commands: &[Command]) -> String {
+    let escape = |description: &str| summary(description).replace('\'', "''");
+    let (root, subcommands) = commands.split_first().expect("root command is present");
+
+    let candidates = |command: &Command, subcommands: &[Command]| {
+        command
+            .flags
+            .iter()
+            .map(|flag| (flag.long.to_owned(), escape(flag.description)))
+            .chain(
+                command
+                    .values
+                    .iter()
+                    .map(|value| (value.to_string(), value.to_string())),
+            )
+            .chain(
+                subcommands
+                    .iter()
+                    .map(|command| (command.name.to_owned(), escape(command.description))),
+            )
+            .map(|(text, tooltip)| format!("@('{}', '{}')", text, tooltip))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "Register-ArgumentCompleter -Native -CommandName '{}' -ScriptBlock {{",
+        BINARY_NAME
+    );
+    let _ = writeln!(
+        out,
+        "    param($wordToComplete, $commandAst, $cursorPosition)"
+    );
+    let _ = writeln!(
+        out,
+        "    $words = $commandAst.CommandElements | ForEach-Object {{ $_.ToString() }}"
+    );
+    let _ = writeln!(
+        out,
+        "    $candidates = @({})",
+        candidates(root, subcommands)
+    );
+    for command in subcommands {
+        let _ = writeln!(out, "    if ($words -contains '{}') {{", command.name);
+        let _ = writeln!(out, "        $candidates = @({})", candidates(command, &[]));
+        let _ = writeln!(out, "    }}");
+    }
+    let _ = writeln!(
+        out,
+        "    $candidates | Where-Object {{ $_[0] -like \"$wordToComplete*\" }} | ForEach-Object {{"
+    );
+    let _ = writeln!(
+        out,
+        "        [System.Management.Automation.CompletionResult]::new($_[0], $_[0], 'ParameterValue', $_[1])"
+    );
+    let _ = writeln!(out, "    }}");
+    let _ = writeln!(out, "}}");
+
+    out
+}
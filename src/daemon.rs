@@ -0,0 +1,132 @@
+use crate::{
+    api,
+    cli::{self, Command, Config, DaemonCommand},
+    lint, remote, schedule, state,
+    update::{self, Outcome},
+};
+use anyhow::Result;
+use chrono::Local;
+use futures::FutureExt;
+use tokio::sync::Notify;
+
+/// Runs the daemon of the `daemon` command held by `config`.
+pub async fn run(config: &mut Config) -> Result<()> {
+    cli::print_header();
+    lint::validate(config)?;
+
+    let config = &*config;
+    let Some(Command::Daemon(command)) = &config.command else {
+        unreachable!("the daemon runs for the daemon command only");
+    };
+    let DaemonCommand {
+        interval,
+        window,
+        listen,
+        api_token,
+        reminder_interval,
+    } = command;
+
+    let http = remote::client(config)?;
+
+    config.print_summary();
+    println!("Check interval: {}", interval);
+    println!("Reminder interval: {}", reminder_interval);
+    for window in window {
+        println!("Maintenance window: {}", window);
+    }
+    println!();
+    lint::check(config)?;
+
+    let trigger = Notify::new();
+    // Listening from the start catches signals sent while a check records its results, too.
+    let shutdown = update::shutdown_requested().fuse();
+    let checks = async {
+        tokio::pin!(shutdown);
+        let started_at = state::unix_timestamp();
+        let mut triggered = false;
+        loop {
+            let now = Local::now();
+            // Updates triggered through the API are installed right away.
+            let in_window =
+                triggered || window.is_empty() || window.iter().any(|window| window.contains(now));
+
+            let sleep_for = check(config, &http, window, interval, in_window).await;
+
+            if let Err(e) = heartbeat(config, started_at, sleep_for).await {
+                println!("⚠️ Failed to record the daemon heartbeat: {}", e);
+            }
+            println!(
+                "💤 Next check in {}",
+                humantime::format_duration(std::time::Duration::from_secs(sleep_for.as_secs()))
+            );
+            println!();
+
+            triggered = tokio::select! {
+                _ = tokio::time::sleep(sleep_for) => false,
+                _ = trigger.notified() => true,
+                Ok(()) = &mut shutdown => {
+                    println!("🛑 Stopping the daemon");
+                    return Ok(());
+                }
+            };
+        }
+    };
+
+    match listen {
+        Some(listen) => {
+            tokio::select! {
+                result = api::serve(config, *listen, api_token.as_deref(), &trigger) => result,
+                result = checks => result,
+            }
+        }
+        None => checks.await,
+    }
+}
+
+/// Runs one check, returning how long to sleep until the next one.
+async fn check(
+    config: &Config,
+    http: &reqwest::Client,
+    window: &[schedule::Window],
+    interval: &humantime::Duration,
+    in_window: bool,
+) -> std::time::Duration {
+    let now = Local::now();
+    let mut sleep_for: std::time::Duration = (*interval).into();
+
+    match update::run_once(config, http, in_window).await {
+        Ok(Outcome::Pending) => {
+            // Wake up for the window even if it starts before the next regular check.
+            if let Some(until_window) = window
+                .iter()
+                .filter_map(|window| window.next_start(now))
+                .min()
+                .and_then(|start| (start - Local::now()).to_std().ok())
+            {
+                sleep_for = sleep_for.min(until_window);
+            }
+        }
+        Ok(_) => {}
+        Err(e) => {
+            println!("🚨 Update failed: {:#}", e);
+            // The mirror told when to come back, whether that's before the next regular check or after it.
+            if let Some(retry_at) = remote::maintenance_retry_at(&e) {
+                sleep_for = std::time::Duration::from_secs(
+                    retry_at.saturating_sub(state::unix_timestamp()).max(1),
+                );
+            }
+        }
+    }
+
+    sleep_for
+}
+
+async fn heartbeat(config: &Config, started_at: u64, sleep_for: std::time::Duration) -> Result<()> {
+    let mut state = state::State::load(config).await?;
+    state.daemon = Some(state::Daemon {
+        pid: std::process::id(),
+        started_at,
+        next_check_at: state::unix_timestamp() + sleep_for.as_secs(),
+    });
+    state.save(config).await
+}
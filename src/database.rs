@@ -0,0 +1,196 @@
+use crate::{cli::Config, local, target::Tuple};
+use anyhow::{anyhow, Result};
+use semver::Version;
+use std::{collections::HashMap, path::Path};
+
+const SERVER_INI_FILE_NAME: &str = "ts3server.ini";
+const DEFAULT_PLUGIN: &str = "ts3db_sqlite3";
+const MARIADB_PLUGINS: &[&str] = &["ts3db_mariadb", "ts3db_mysql"];
+
+/// Database files of the SQLite plugin, relative to the release directory.
+pub const SQLITE_FILE_NAMES: &[&str] = &[
+    "ts3server.sqlitedb",
+    "ts3server.sqlitedb-wal",
+    "ts3server.sqlitedb-shm",
+];
+
+/// Database the server keeps its state in, as configured in `ts3server.ini`.
+pub enum Backend {
+    Sqlite,
+    MariaDb {
+        plugin: String,
+        /// Plugin's ini file, relative to the release directory.
+        ini_file: String,
+        settings: MariaDb,
+    },
+}
+
+/// Connection settings of the `[config]` section of the MariaDB plugin's ini file.
+pub struct MariaDb {
+    host: Option<String>,
+    port: Option<String>,
+    socket: Option<String>,
+    username: Option<String>,
+    password: Option<String>,
+    database: String,
+}
+
+impl Backend {
+    /// Reads the database plugin from `ts3server.ini` of the release. Servers without one use SQLite.
+    pub fn detect(release_path: &Path) -> Result<Self> {
+        let server_ini = match read_ini(&release_path.join(SERVER_INI_FILE_NAME))? {
+            Some(server_ini) => server_ini,
+            None => return Ok(Self::Sqlite),
+        };
+
+        let plugin = server_ini
+            .get("dbplugin")
+            .map(String::as_str)
+            .filter(|plugin| !plugin.is_empty())
+            .unwrap_or(DEFAULT_PLUGIN);
+        if !MARIADB_PLUGINS.contains(&plugin) {
+            return Ok(Self::Sqlite);
+        }
+
+        let ini_file = server_ini
+            .get("dbpluginparameter")
+            .cloned()
+            .unwrap_or_else(|| String::from("ts3db_mariadb.ini"));
+        let plugin_ini_path = release_path.join(&ini_file);
+        let plugin_ini = read_ini(&plugin_ini_path)?.ok_or_else(|| {
+            anyhow!(
+                "{} configures {}, but {} does not exist",
+                SERVER_INI_FILE_NAME,
+                plugin,
+                plugin_ini_path.to_string_lossy()
+            )
+        })?;
+
+        let value = |key: &str| {
+            plugin_ini
+                .get(key)
+                .filter(|value| !value.is_empty())
+                .cloned()
+        };
+        let settings = MariaDb {
+            host: value("host"),
+            port: value("port"),
+            socket: value("socket"),
+            username: value("username"),
+            password: value("password"),
+            database: value("database").ok_or_else(|| {
+                anyhow!(
+                    "{} does not name the database",
+                    plugin_ini_path.to_string_lossy()
+                )
+            })?,
+        };
+
+        Ok(Self::MariaDb {
+            plugin: plugin.to_owned(),
+            ini_file,
+            settings,
+        })
+    }
+
+    pub fn is_sqlite(&self) -> bool {
+        matches!(self, Self::Sqlite)
+    }
+}
+
+/// Key-value pairs of an ini file, ignoring sections. `None` if the file doesn't exist.
+fn read_ini(path: &Path) -> Result<Option<HashMap<String, String>>> {
+    let contents = match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(anyhow!("{}: {}", path.to_string_lossy(), e)),
+    };
+
+    Ok(Some(
+        contents
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.starts_with(['[', ';', '#']))
+            .filter_map(|line| line.split_once('='))
+            .map(|(key, value)| (key.trim().to_owned(), value.trim().to_owned()))
+            .collect(),
+    ))
+}
+
+fn plugin_file_name(tuple: Tuple, plugin: &str) -> String {
+    match tuple {
+        Tuple::WindowsX86 | Tuple::WindowsX8664 => format!("{}.dll", plugin),
+        Tuple::Mac => format!("lib{}.dylib", plugin),
+        _ => format!("lib{}.so", plugin),
+    }
+}
+
+/// Makes sure the new release ships the database plugin the server is configured with, before it gets activated.
+pub async fn check_plugin(config: &Config, published_version: &Version) -> Result<()> {
+    let staged_path = local::staged_release_path(config, published_version)?;
+    let plugin = match Backend::detect(&staged_path)? {
+        Backend::MariaDb { plugin, .. } => plugin,
+        Backend::Sqlite => return Ok(()),
+    };
+
+    let plugin_file_name = plugin_file_name(config.target_tuple, &plugin);
+    if !staged_path.join(&plugin_file_name).is_file() {
+        return Err(anyhow!(
+            "release {} does not ship {}, which the server is configured to use",
+            published_version,
+            plugin_file_name
+        ));
+    }
+    println!("🗄️ Database plugin {} is present", plugin_file_name);
+
+    Ok(())
+}
+
+/// Writes a `mysqldump` of the server's database into `path`, passing the password in the environment.
+pub async fn dump(settings: &MariaDb, path: &Path) -> Result<()> {
+    let MariaDb {
+        host,
+        port,
+        socket,
+        username,
+        password,
+        database,
+    } = settings;
+
+    let mut command = tokio::process::Command::new("mysqldump");
+    command.args(["--single-transaction", "--routines", "--triggers"]);
+    for (flag, value) in [
+        ("--host", host),
+        ("--port", port),
+        ("--socket", socket),
+        ("--user", username),
+    ] {
+        if let Some(value) = value {
+            command.arg(format!("{}={}", flag, value));
+        }
+    }
+    // Without a password in the ini, mysqldump falls back to `MYSQL_PWD` and option files on its own.
+    if let Some(password) = password {
+        command.env("MYSQL_PWD", password);
+    }
+
+    // `output()` would capture the dump in memory instead of writing it into the file.
+    let output = command
+        .arg(database)
+        .stdout(std::fs::File::create(path)?)
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .map_err(|e| anyhow!("failed to run mysqldump: {}", e))?
+        .wait_with_output()
+        .await?;
+
+    if !output.status.success() {
+        return Err(anyhow!(
+            "mysqldump failed with {}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    Ok(())
+}
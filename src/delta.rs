@@ -0,0 +1,264 @@
+use crate::{
+    cli::{self, BuildDeltaCommand, Config},
+    journal::Journal,
+    license::LICENSE_FILE_NAME,
+    local, manifest,
+    remote::{self, Download, Product},
+};
+use anyhow::{anyhow, Result};
+use semver::Version;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, path::Path};
+
+/// Name of the index inside the delta directory published next to an archive, `<archive URL>.delta/`.
+const INDEX_FILE_NAME: &str = "index.json";
+
+/// Files of a release, each stored in the delta directory under its SHA256.
+#[derive(Serialize, Deserialize)]
+struct DeltaIndex {
+    version: String,
+    files: BTreeMap<String, DeltaFile>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct DeltaFile {
+    sha256: String,
+    size: u64,
+    /// Unix permission bits.
+    #[serde(default)]
+    mode: Option<u32>,
+}
+
+/// Writes the delta directory of an extracted release, to be uploaded next to its archive on a mirror.
+pub async fn build(command: &BuildDeltaCommand) -> Result<()> {
+    let BuildDeltaCommand {
+        release_dir,
+        output_dir,
+        version,
+    } = command;
+
+    cli::print_header();
+
+    let version = match version {
+        Some(version) => version.to_string(),
+        None => manifest::read(release_dir)
+            .await?
+            .map(|manifest| manifest.version)
+            .ok_or_else(|| {
+                anyhow!(
+                    "{} has no release manifest - pass --version",
+                    release_dir.to_string_lossy()
+                )
+            })?,
+    };
+
+    let (release_dir, output_dir) = (release_dir.clone(), output_dir.clone());
+    let files = tokio::task::spawn_blocking(move || -> Result<_> {
+        use std::fs;
+
+        fs::create_dir_all(&output_dir)?;
+        let mut files = BTreeMap::new();
+
+        for (relative, sha256) in manifest::file_digests(&release_dir)? {
+            let path = release_dir.join(&relative);
+            let metadata = fs::symlink_metadata(&path)?;
+            // Links to the license or shared configuration belong to the installation, not to the release.
+            if !metadata.is_file() || relative == LICENSE_FILE_NAME {
+                continue;
+            }
+
+            let object_path = output_dir.join(&sha256);
+            if !object_path.exists() {
+                fs::copy(&path, &object_path)?;
+            }
+
+            files.insert(
+                relative,
+                DeltaFile {
+                    sha256,
+                    size: metadata.len(),
+                    mode: mode(&metadata),
+                },
+            );
+        }
+
+        let index = DeltaIndex { version, files };
+        fs::write(
+            output_dir.join(INDEX_FILE_NAME),
+            serde_json::to_vec_pretty(&index)?,
+        )?;
+
+        Ok(index.files.len())
+    })
+    .await??;
+
+    println!(
+        "✅ Delta index of {} file(s) written to {}",
+        files,
+        command.output_dir.to_string_lossy()
+    );
+    println!(
+        "   ↳ upload the directory next to the release archive as `<archive file name>.delta/`"
+    );
+
+    Ok(())
+}
+
+/// Assembles the new release from files of the installed one and changed files of the mirror's delta directory.
+///
+/// Returns `None` when the full archive has to be downloaded instead, e.g. because the mirror has no delta directory.
+pub async fn assemble(
+    config: &Config,
+    http: &reqwest::Client,
+    installed_version: Option<&Version>,
+    published_version: &Version,
+    journal: &Journal,
+) -> Result<Option<Download>> {
+    let installed_version = match installed_version {
+        Some(installed_version) if config.product == Product::Ts3 => installed_version,
+        _ => return Ok(None),
+    };
+    remote::ensure_online(config, "downloading the delta")?;
+
+    let mut delta_url = remote::remote_archive_path(config, published_version)?;
+    delta_url.set_path(&format!("{}.delta/", delta_url.path()));
+    let index_url = delta_url.join(INDEX_FILE_NAME)?;
+    remote::ensure_allowed(config, &index_url)?;
+
+    let request = remote::authorize(config, http.get(index_url.clone()));
+    let response = remote::send_to_mirror(config, request).await?;
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        println!(
+            "🌐 Mirror has no delta index for {}, downloading the full archive",
+            published_version
+        );
+        return Ok(None);
+    }
+    let index_body = response.error_for_status()?.bytes().await?;
+    let index: DeltaIndex = serde_json::from_slice(&index_body)?;
+    if index.version != published_version.to_string() {
+        return Err(anyhow!(
+            "delta index {} describes version {} instead of {}",
+            index_url,
+            index.version,
+            published_version
+        ));
+    }
+
+    let previous_path = local::release_path(config, installed_version)?;
+    // With `--assume-version` the release may not exist, and then there is nothing to assemble from.
+    if !previous_path.is_dir() {
+        return Ok(None);
+    }
+    let staged_path = local::staged_release_path(config, published_version)?;
+    if local::in_place_dir(config).is_some()
+        && tokio::fs::symlink_metadata(&staged_path).await.is_ok()
+    {
+        tokio::fs::remove_dir_all(&staged_path).await?;
+    }
+    tokio::fs::create_dir(&staged_path).await?;
+    journal.created_release(&staged_path);
+
+    println!(
+        "🧩 Assembling {} from {}...",
+        published_version,
+        previous_path.to_string_lossy()
+    );
+    let previous_files = {
+        let previous_path = previous_path.clone();
+        tokio::task::spawn_blocking(move || manifest::file_digests(&previous_path))
+            .await??
+            .into_iter()
+            .collect::<BTreeMap<_, _>>()
+    };
+
+    let (mut reused, mut downloaded, mut downloaded_bytes) = (0, 0, 0);
+    for (relative, file) in &index.files {
+        let path = staged_path.join(relative);
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+
+        if previous_files.get(relative) == Some(&file.sha256) {
+            tokio::fs::copy(previous_path.join(relative), &path).await?;
+            reused += 1;
+        } else {
+            let request = remote::authorize(config, http.get(delta_url.join(&file.sha256)?));
+            let contents = remote::send_to_mirror(config, request)
+                .await?
+                .error_for_status()?
+                .bytes()
+                .await?;
+            verify(relative, &contents, &file.sha256)?;
+            tokio::fs::write(&path, &contents).await?;
+            downloaded += 1;
+            downloaded_bytes += contents.len() as u64;
+        }
+
+        set_mode(&path, file.mode).await?;
+    }
+
+    println!(
+        "✅ Reused {} file(s), downloaded {} changed file(s) ({} KiB)",
+        reused,
+        downloaded,
+        downloaded_bytes / 1024
+    );
+
+    let index_file = tempfile::tempfile_in(local::work_dir(config)?)?;
+    std::io::Write::write_all(&mut &index_file, &index_body)?;
+
+    Ok(Some(Download {
+        archive: tokio::fs::File::from_std(index_file),
+        url: index_url,
+        sha256: sha256(&index_body),
+        size: downloaded_bytes,
+        verified: None,
+    }))
+}
+
+fn verify(relative: &str, contents: &[u8], expected: &str) -> Result<()> {
+    let actual = sha256(contents);
+    if actual != expected {
+        return Err(anyhow!(
+            "{} from the delta directory has SHA256 {} instead of {}",
+            relative,
+            actual,
+            expected
+        ));
+    }
+
+    Ok(())
+}
+
+fn sha256(contents: &[u8]) -> String {
+    use sha2::{Digest, Sha256};
+
+    hex::encode(Sha256::digest(contents))
+}
+
+#[cfg(unix)]
+fn mode(metadata: &std::fs::Metadata) -> Option<u32> {
+    use std::os::unix::fs::PermissionsExt;
+    Some(metadata.permissions().mode() & 0o7777)
+}
+
+#[cfg(not(unix))]
+fn mode(_metadata: &std::fs::Metadata) -> Option<u32> {
+    None
+}
+
+#[cfg(unix)]
+async fn set_mode(path: &Path, mode: Option<u32>) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    if let Some(mode) = mode {
+        tokio::fs::set_permissions(path, std::fs::Permissions::from_mode(mode)).await?;
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+async fn set_mode(_path: &Path, _mode: Option<u32>) -> Result<()> {
+    Ok(())
+}
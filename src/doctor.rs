@@ -0,0 +1,228 @@
+use crate::{
+    cli::{self, Config, DoctorCommand},
+    local, remote, target,
+};
+use anyhow::{anyhow, Result};
+use std::path::Path;
+
+/// Releases need room for the downloaded archive and the extracted server.
+const MIN_FREE_BYTES: u64 = 512 * 1024 * 1024;
+
+enum Status {
+    Pass(String),
+    Fail {
+        problem: String,
+        remediation: &'static str,
+    },
+    Skip(&'static str),
+}
+
+fn fail(problem: impl Into<String>, remediation: &'static str) -> Status {
+    Status::Fail {
+        problem: problem.into(),
+        remediation,
+    }
+}
+
+pub async fn run(config: &Config, command: &DoctorCommand) -> Result<()> {
+    cli::print_header();
+    println!("🩺 Environment diagnostics");
+
+    let http = remote::client(config)?;
+    let install_dir = local::in_place_dir(config).unwrap_or(&config.releases_path);
+
+    let checks = [
+        ("installed release", installed_release(config).await),
+        ("install directory writable", writable(install_dir)),
+        ("mirror reachable", mirror(config, &http).await),
+        ("target tuple", target_tuple(config)),
+        ("disk space", disk_space(install_dir)),
+        ("runtime libraries", runtime_libraries(config)),
+        ("server service", service(command).await),
+    ];
+
+    let mut failures = 0;
+    for (name, status) in checks {
+        match status {
+            Status::Pass(detail) => println!("✅ {}: {}", name, detail),
+            Status::Skip(reason) => println!("⏭️ {}: {}", name, reason),
+            Status::Fail {
+                problem,
+                remediation,
+            } => {
+                failures += 1;
+                println!("❌ {}: {}", name, problem);
+                println!("   ↳ {}", remediation);
+            }
+        }
+    }
+    println!();
+
+    if failures > 0 {
+        return Err(anyhow!("{} diagnostic check(s) failed", failures));
+    }
+
+    println!("✅ Environment looks healthy.");
+    Ok(())
+}
+
+async fn installed_release(config: &Config) -> Status {
+    let Config { symlink_path, .. } = config;
+
+    if local::in_place_dir(config).is_none() {
+        match tokio::fs::symlink_metadata(symlink_path).await {
+            Ok(metadata) if !metadata.is_symlink() => {
+                return fail(
+                    format!("{} is not a symlink", symlink_path.to_string_lossy()),
+                    "run `teamspeak-updater adopt` to move the installation into the releases layout",
+                )
+            }
+            Err(e) => {
+                return fail(
+                    format!("{}: {}", symlink_path.to_string_lossy(), e),
+                    "install TeamSpeak with `teamspeak-updater --repair` or point --symlink-path to the right place",
+                )
+            }
+            Ok(_) => {}
+        }
+    }
+
+    match local::installed_version(config).await {
+        Ok(Some(version)) => Status::Pass(format!("version {}", version)),
+        Ok(None) => fail(
+            "no installed version found",
+            "run `teamspeak-updater` to install the latest version",
+        ),
+        Err(e) => fail(
+            format!("{:#}", e),
+            "restore the release directory or run with --repair to install the latest version",
+        ),
+    }
+}
+
+fn writable(install_dir: &Path) -> Status {
+    match tempfile::tempfile_in(install_dir) {
+        Ok(_) => Status::Pass(install_dir.to_string_lossy().into_owned()),
+        Err(e) => fail(
+            format!("{}: {}", install_dir.to_string_lossy(), e),
+            "create the directory and make it writable for the user running the updater",
+        ),
+    }
+}
+
+async fn mirror(config: &Config, http: &reqwest::Client) -> Status {
+    if config.offline {
+        return Status::Skip("offline mode");
+    }
+
+    match remote::latest_version(config, http).await {
+        Ok(version) => Status::Pass(format!("latest published version {}", version)),
+        Err(e) => fail(
+            format!("{}: {:#}", config.mirror_url, e),
+            "check network connectivity and the --mirror-url option",
+        ),
+    }
+}
+
+fn target_tuple(config: &Config) -> Status {
+    let configured = config.target_tuple.to_string();
+
+    match target::Tuple::detect() {
+        Some(host) if host.to_string() == configured => Status::Pass(configured),
+        Some(host) => fail(
+            format!("configured {} but host looks like {}", configured, host),
+            "make sure --target-tuple is intended, the server binary may not run on this host",
+        ),
+        None => Status::Skip("host is not supported by TeamSpeak"),
+    }
+}
+
+#[cfg(unix)]
+fn disk_space(install_dir: &Path) -> Status {
+    let stat = match nix::sys::statvfs::statvfs(install_dir) {
+        Ok(stat) => stat,
+        Err(e) => {
+            return fail(
+                format!("{}: {}", install_dir.to_string_lossy(), e),
+                "make sure the install directory exists",
+            )
+        }
+    };
+    #[allow(clippy::unnecessary_cast)]
+    let available = stat.blocks_available() as u64 * stat.fragment_size() as u64;
+
+    if available < MIN_FREE_BYTES {
+        fail(
+            format!("only {} MiB available", available / 1024 / 1024),
+            "free up space, e.g. by removing old releases",
+        )
+    } else {
+        Status::Pass(format!("{} MiB available", available / 1024 / 1024))
+    }
+}
+
+#[cfg(not(unix))]
+fn disk_space(_install_dir: &Path) -> Status {
+    Status::Skip("not supported on this platform")
+}
+
+/// Checks that the dynamic loader `ts3server` of the target tuple is linked against is present.
+fn runtime_libraries(config: &Config) -> Status {
+    use target::Tuple;
+
+    let (loader, remediation) = match config.target_tuple {
+        Tuple::LinuxX8664 => (
+            "/lib64/ld-linux-x86-64.so.2",
+            "install glibc, or use --target-tuple linux_alpine on musl-based hosts",
+        ),
+        Tuple::LinuxX86 => (
+            "/lib/ld-linux.so.2",
+            "install 32-bit glibc, or use --target-tuple linux_alpine on musl-based hosts",
+        ),
+        Tuple::LinuxAlpine => (
+            "/lib/ld-musl-x86_64.so.1",
+            "install musl, or use --target-tuple linux_amd64 on glibc-based hosts",
+        ),
+        _ => return Status::Skip("only checked for Linux target tuples"),
+    };
+
+    if Path::new(loader).exists() {
+        Status::Pass(format!("{} present", loader))
+    } else {
+        fail(format!("{} is missing", loader), remediation)
+    }
+}
+
+async fn service(command: &DoctorCommand) -> Status {
+    let unit = match &command.service {
+        Some(unit) => unit,
+        None => return Status::Skip("pass --service to check the systemd unit"),
+    };
+
+    let output = tokio::process::Command::new("systemctl")
+        .arg("is-active")
+        .arg(unit)
+        .output()
+        .await;
+
+    match output {
+        Ok(output) if output.status.success() => Status::Pass(format!("{} is active", unit)),
+        Ok(output) => {
+            let state = String::from_utf8_lossy(&output.stdout).trim().to_owned();
+            let state = if state.is_empty() {
+                String::from_utf8_lossy(&output.stderr).trim().to_owned()
+            } else {
+                state
+            };
+
+            fail(
+                format!("{} is not active: {}", unit, state),
+                "check `systemctl status` and the server logs",
+            )
+        }
+        Err(e) => fail(
+            format!("failed to run systemctl: {}", e),
+            "service status can only be checked on systemd hosts",
+        ),
+    }
+}
@@ -0,0 +1,207 @@
+use crate::{
+    cli::{self, Config, DownloadCommand},
+    remote,
+    target::{Selection, Tuple},
+};
+use anyhow::{anyhow, Result};
+use semver::Version;
+use std::path::{Path, PathBuf};
+
+/// Fetches archives into a directory without touching the installation, e.g. to stage them on another host
+/// or to keep an internal mirror in sync.
+pub async fn run(config: &Config, command: &DownloadCommand) -> Result<()> {
+    let DownloadCommand {
+        version,
+        out,
+        sha256,
+        target_tuple,
+        mirror_layout,
+    } = command;
+
+    cli::print_header();
+
+    if !out.is_dir() {
+        return Err(anyhow!(
+            "output directory {} does not exist - create it first",
+            out.to_string_lossy()
+        ));
+    }
+
+    let tuples = tuples(config, target_tuple);
+    if sha256.is_some() && tuples.len() > 1 {
+        return Err(anyhow!(
+            "--sha256 can only be checked when downloading a single target tuple"
+        ));
+    }
+
+    let http = remote::client(config)?;
+    let version = match version {
+        Some(version) => version.clone(),
+        None => remote::latest_version(config, &http).await?,
+    };
+
+    let mut failures = 0;
+    for tuple in tuples {
+        // Same place the archive has on the mirror, so the directory can be served as one.
+        let archive_dir = if *mirror_layout {
+            let archive_path = remote::archive_path(config, tuple, &version);
+            match Path::new(&archive_path).parent() {
+                Some(parent) => out.join(parent),
+                None => out.clone(),
+            }
+        } else {
+            out.clone()
+        };
+
+        match download_into(
+            config,
+            &http,
+            tuple,
+            &version,
+            &archive_dir,
+            sha256.as_deref(),
+        )
+        .await
+        {
+            Ok(()) => {}
+            Err(e) if remote::is_not_found(&e) => {
+                println!("⏭️ {} is not published for {}", version, tuple)
+            }
+            Err(e) => {
+                failures += 1;
+                println!("❌ {}: {:#}", tuple, e);
+            }
+        }
+    }
+
+    if failures > 0 {
+        return Err(anyhow!("{} archive(s) failed to download", failures));
+    }
+
+    Ok(())
+}
+
+/// Tuples selected with `--target-tuple`, the configured one if none is given.
+fn tuples(config: &Config, selections: &[Selection]) -> Vec<Tuple> {
+    if selections.is_empty() {
+        return vec![config.target_tuple];
+    }
+
+    selected_tuples(selections)
+}
+
+/// Tuples of the selections in the order they were given, without duplicates.
+pub fn selected_tuples(selections: &[Selection]) -> Vec<Tuple> {
+    let mut tuples = vec![];
+    for selection in selections {
+        let selected = match selection {
+            Selection::All => Tuple::ALL.to_vec(),
+            Selection::Tuple(tuple) => vec![*tuple],
+        };
+        for tuple in selected {
+            if !tuples.contains(&tuple) {
+                tuples.push(tuple);
+            }
+        }
+    }
+
+    tuples
+}
+
+/// Downloads the archive with its checksum file into `archive_dir`, unless a matching one is already there.
+pub async fn download_into(
+    config: &Config,
+    http: &reqwest::Client,
+    tuple: Tuple,
+    version: &Version,
+    archive_dir: &Path,
+    sha256: Option<&str>,
+) -> Result<()> {
+    use tokio::io::AsyncSeekExt;
+
+    let file_name = tuple.archive_filename(&config.filename_template, version);
+    let archive_path = archive_dir.join(&file_name);
+    let checksum_path = archive_dir.join(format!("{}.sha256", file_name));
+
+    if let Some(actual_sha256) = verified_sha256(&archive_path, &checksum_path).await {
+        println!(
+            "✅ {} is already downloaded and matches its checksum",
+            archive_path.to_string_lossy()
+        );
+        return check_sha256(sha256, &actual_sha256, &file_name);
+    }
+
+    let remote::Download {
+        mut archive,
+        sha256: actual_sha256,
+        size,
+        ..
+    } = remote::download_archive(config, http, tuple, version).await?;
+    check_sha256(sha256, &actual_sha256, &file_name)?;
+
+    // A partially written archive must not be mistaken for a complete one.
+    let partial_path = archive_dir.join(format!("{}.part", file_name));
+    tokio::fs::create_dir_all(archive_dir).await?;
+
+    archive.seek(std::io::SeekFrom::Start(0)).await?;
+    let mut partial = tokio::fs::File::create(&partial_path).await?;
+    tokio::io::copy(&mut archive, &mut partial).await?;
+    partial.sync_all().await?;
+    tokio::fs::rename(&partial_path, &archive_path).await?;
+
+    // Same format as `sha256sum`, so the copy can be checked with `sha256sum -c` after moving it.
+    tokio::fs::write(
+        &checksum_path,
+        format!("{}  {}\n", actual_sha256, file_name),
+    )
+    .await?;
+
+    println!(
+        "✅ Saved TeamSpeak {} for {} to {} ({} KiB)",
+        version,
+        tuple,
+        archive_path.to_string_lossy(),
+        size / 1024
+    );
+    println!("   ↳ SHA256 {}", actual_sha256);
+
+    Ok(())
+}
+
+fn check_sha256(expected_sha256: Option<&str>, actual_sha256: &str, file_name: &str) -> Result<()> {
+    match expected_sha256 {
+        Some(expected_sha256) if !expected_sha256.eq_ignore_ascii_case(actual_sha256) => {
+            Err(anyhow!(
+                "checksum mismatch for {}: expected {}, got {}",
+                file_name,
+                expected_sha256,
+                actual_sha256
+            ))
+        }
+        Some(_) => {
+            println!("🔏 Checksum matches");
+            Ok(())
+        }
+        None => Ok(()),
+    }
+}
+
+/// Checksum of an archive downloaded by an earlier run, if it still matches its checksum file.
+async fn verified_sha256(archive_path: &Path, checksum_path: &Path) -> Option<String> {
+    use sha2::{Digest, Sha256};
+
+    let recorded = tokio::fs::read_to_string(checksum_path).await.ok()?;
+    let recorded = recorded.split_whitespace().next()?.to_owned();
+
+    let archive_path: PathBuf = archive_path.to_owned();
+    let actual = tokio::task::spawn_blocking(move || -> std::io::Result<String> {
+        let mut hasher = Sha256::new();
+        std::io::copy(&mut std::fs::File::open(archive_path)?, &mut hasher)?;
+        Ok(hex::encode(hasher.finalize()))
+    })
+    .await
+    .ok()?
+    .ok()?;
+
+    recorded.eq_ignore_ascii_case(&actual).then_some(actual)
+}
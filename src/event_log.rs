@@ -0,0 +1,76 @@
+use crate::{cli::Config, metrics::Report, update::Outcome};
+use anyhow::{anyhow, Result};
+
+/// Event source shown in the Windows Event Viewer.
+pub const EVENT_SOURCE: &str = "TeamSpeakUpdater";
+
+/// Event IDs of the run results, `eventcreate` accepts 1 to 1000.
+const UPDATED_EVENT_ID: u16 = 1;
+const NO_ACTION_EVENT_ID: u16 = 2;
+const PENDING_EVENT_ID: u16 = 3;
+const FAILED_EVENT_ID: u16 = 100;
+
+/// Writes the result of a run into the Application log of Windows with `eventcreate`.
+pub async fn write(config: &Config, report: &Report, result: &Result<Outcome>) -> Result<()> {
+    if !config.event_log {
+        return Ok(());
+    }
+
+    let version = |version: &Option<semver::Version>| {
+        version
+            .as_ref()
+            .map_or_else(|| String::from("unknown"), ToString::to_string)
+    };
+    let (event_type, event_id, description) = match result {
+        Ok(Outcome::Updated) => (
+            "INFORMATION",
+            UPDATED_EVENT_ID,
+            match &report.changes {
+                Some(changes) => format!(
+                    "TeamSpeak updated to {}. Files: {}.",
+                    version(&report.published_version),
+                    changes.summary()
+                ),
+                None => format!(
+                    "TeamSpeak updated to {}.",
+                    version(&report.published_version)
+                ),
+            },
+        ),
+        Ok(Outcome::NoAction) => (
+            "INFORMATION",
+            NO_ACTION_EVENT_ID,
+            format!(
+                "TeamSpeak {} is up to date.",
+                version(&report.installed_version)
+            ),
+        ),
+        Ok(Outcome::Pending) => (
+            "INFORMATION",
+            PENDING_EVENT_ID,
+            format!(
+                "Update to TeamSpeak {} is pending.",
+                version(&report.published_version)
+            ),
+        ),
+        Err(e) => (
+            "ERROR",
+            FAILED_EVENT_ID,
+            format!("TeamSpeak update failed: {:#}", e),
+        ),
+    };
+
+    let status = tokio::process::Command::new("eventcreate")
+        .args(["/L", "APPLICATION", "/SO", EVENT_SOURCE, "/T", event_type])
+        .args(["/ID", &event_id.to_string(), "/D", &description])
+        .stdout(std::process::Stdio::null())
+        .status()
+        .await
+        .map_err(|e| anyhow!("failed to run eventcreate: {}", e))?;
+
+    if status.success() {
+        Ok(())
+    } else {
+        Err(anyhow!("eventcreate failed with {}", status))
+    }
+}
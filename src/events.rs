@@ -0,0 +1,377 @@
+use crate::{cli::Config, state};
+use anyhow::Result;
+use serde::Serialize;
+use std::{
+    collections::VecDeque,
+    fmt::Display,
+    future::Future,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant, SystemTime},
+};
+use thiserror::Error;
+
+/// Names of all phases of an update, in the order they run.
+pub const PHASES: &[&str] = &[
+    "check",
+    "delta",
+    "download",
+    "extract",
+    "snapshot",
+    "preserve",
+    "overlay",
+    "config",
+    "database",
+    "permissions",
+    "link",
+    "license",
+    "ownership",
+    "selinux",
+    "libraries",
+    "stop",
+    "process",
+    "switch",
+    "start",
+    "artifact",
+];
+
+/// Downloaded bytes are reported every time another mebibyte arrives.
+const PROGRESS_STEP: u64 = 1 << 20;
+/// Number of most recent events kept for failure notifications.
+const TAIL_LENGTH: usize = 20;
+
+static TAIL: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+/// Phases which ended since the last summary.
+static TIMINGS: Mutex<Vec<PhaseTiming>> = Mutex::new(Vec::new());
+
+tokio::task_local! {
+    /// Blocking work started by the phase running in the current task.
+    static PHASE_WORK: PhaseWork;
+}
+
+/// Blocking work of a phase, which a timed-out phase cancels and waits for before its partial work is rolled back.
+#[derive(Clone, Default)]
+struct PhaseWork {
+    cancellation: Cancellation,
+    running: Arc<tokio::sync::RwLock<()>>,
+}
+
+/// Tells blocking work that its phase timed out, so it stops at the next file instead of racing the rollback.
+#[derive(Clone, Default)]
+pub struct Cancellation(Arc<AtomicBool>);
+
+impl Cancellation {
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn check(&self) -> Result<(), Cancelled> {
+        match self.0.load(Ordering::Relaxed) {
+            true => Err(Cancelled),
+            false => Ok(()),
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+#[error("cancelled, as the phase timed out")]
+pub struct Cancelled;
+
+/// Context attached to errors of a phase, so failure reports can tell where the run broke.
+#[derive(Debug, Error)]
+#[error("{0} phase failed")]
+pub struct PhaseFailed(pub String);
+
+/// Phase ran longer than its `--phase-timeout` and was cancelled.
+#[derive(Debug, Error)]
+#[error("{0} phase timed out after {1}")]
+pub struct PhaseTimedOut(pub String, pub humantime::Duration);
+
+/// Longest time a phase may run, written as `extract=10m`. Phase `*` applies to every phase without its own timeout.
+#[derive(Clone)]
+pub struct PhaseTimeout {
+    phase: String,
+    timeout: humantime::Duration,
+}
+
+#[derive(Debug, Error)]
+pub enum PhaseTimeoutError {
+    #[error("phase timeout not recognized: {0} (expected e.g. `extract=10m` or `*=30m`)")]
+    NotRecognized(String),
+    #[error("unknown phase {0} (expected one of {1})")]
+    UnknownPhase(String, String),
+}
+
+impl FromStr for PhaseTimeout {
+    type Err = PhaseTimeoutError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let not_recognized = || PhaseTimeoutError::NotRecognized(s.to_owned());
+        let (phase, timeout) = s.split_once('=').ok_or_else(not_recognized)?;
+        let phase = phase.trim();
+
+        if phase != "*" && !PHASES.contains(&phase) {
+            return Err(PhaseTimeoutError::UnknownPhase(
+                phase.to_owned(),
+                PHASES.join(", "),
+            ));
+        }
+
+        Ok(Self {
+            phase: phase.to_owned(),
+            timeout: timeout.trim().parse().map_err(|_| not_recognized())?,
+        })
+    }
+}
+
+impl Display for PhaseTimeout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}={}", self.phase, self.timeout)
+    }
+}
+
+/// Timeout of the phase, its own one taking precedence over the `*` one.
+fn phase_timeout(config: &Config, phase: &str) -> Option<humantime::Duration> {
+    let timeout_of = |name: &str| {
+        config
+            .phase_timeout
+            .iter()
+            .rev()
+            .find(|timeout| timeout.phase == name)
+            .map(|timeout| timeout.timeout)
+    };
+
+    timeout_of(phase).or_else(|| timeout_of("*"))
+}
+
+/// Machine-readable progress event, written as a JSON line to stderr under `--json-log`.
+#[derive(Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event<'a> {
+    PhaseStarted {
+        phase: &'a str,
+    },
+    PhaseFinished {
+        phase: &'a str,
+        duration_ms: u64,
+    },
+    PhaseFailed {
+        phase: &'a str,
+        error: String,
+    },
+    Downloaded {
+        bytes: u64,
+        total: Option<u64>,
+    },
+    FilesCopied {
+        count: usize,
+    },
+    Summary {
+        phases: Vec<PhaseTiming>,
+        total_ms: u64,
+    },
+}
+
+#[derive(Clone, Serialize)]
+pub struct PhaseTiming {
+    pub phase: String,
+    pub duration_ms: u64,
+    pub failed: bool,
+    #[serde(skip)]
+    pub started_at: SystemTime,
+    #[serde(skip)]
+    pub error: Option<String>,
+}
+
+#[derive(Serialize)]
+struct Line<'a> {
+    timestamp: u64,
+    #[serde(flatten)]
+    event: Event<'a>,
+}
+
+pub fn emit(config: &Config, event: Event) {
+    let line = Line {
+        timestamp: state::unix_timestamp(),
+        event,
+    };
+    let Ok(line) = serde_json::to_string(&line) else {
+        return;
+    };
+
+    if config.json_log {
+        eprintln!("{}", line);
+    }
+
+    let mut tail = TAIL.lock().expect("event tail lock is poisoned");
+    if tail.len() == TAIL_LENGTH {
+        tail.pop_front();
+    }
+    tail.push_back(line);
+}
+
+/// Most recent events as JSON lines, oldest first, whether or not `--json-log` is on.
+pub fn tail() -> Vec<String> {
+    TAIL.lock()
+        .expect("event tail lock is poisoned")
+        .iter()
+        .cloned()
+        .collect()
+}
+
+/// Runs `future` as a named phase, emitting its start and its outcome. Errors get [`PhaseFailed`] attached.
+/// The future is dropped with [`PhaseTimedOut`] when it runs longer than its `--phase-timeout`, once the blocking
+/// work it started with [`spawn_blocking`] returned.
+pub async fn phase<T>(
+    config: &Config,
+    phase: &str,
+    future: impl Future<Output = Result<T>>,
+) -> Result<T> {
+    emit(config, Event::PhaseStarted { phase });
+    let started_at = Instant::now();
+    let started_at_time = SystemTime::now();
+
+    let work = PhaseWork::default();
+    let future = PHASE_WORK.scope(work.clone(), future);
+    let result = match phase_timeout(config, phase) {
+        Some(timeout) => match tokio::time::timeout(*timeout, future).await {
+            Ok(result) => result,
+            Err(_) => {
+                work.cancellation.cancel();
+                let _finished = work.running.write().await;
+                Err(PhaseTimedOut(phase.to_owned(), timeout).into())
+            }
+        },
+        None => future.await,
+    };
+    let duration_ms = started_at.elapsed().as_millis() as u64;
+    TIMINGS
+        .lock()
+        .expect("phase timings lock is poisoned")
+        .push(PhaseTiming {
+            phase: phase.to_owned(),
+            duration_ms,
+            failed: result.is_err(),
+            started_at: started_at_time,
+            error: result.as_ref().err().map(|e| format!("{:#}", e)),
+        });
+
+    match &result {
+        Ok(_) => emit(config, Event::PhaseFinished { phase, duration_ms }),
+        Err(e) => emit(
+            config,
+            Event::PhaseFailed {
+                phase,
+                error: format!("{:#}", e),
+            },
+        ),
+    }
+
+    result.map_err(|e| e.context(PhaseFailed(phase.to_owned())))
+}
+
+/// Runs file work of the current phase on a blocking thread. `work` should check the [`Cancellation`] between
+/// files, as a timed-out phase waits for it to return before the partial release is removed.
+pub async fn spawn_blocking<T: Send + 'static>(
+    work: impl FnOnce(Cancellation) -> T + Send + 'static,
+) -> Result<T> {
+    let PhaseWork {
+        cancellation,
+        running,
+    } = PHASE_WORK.try_with(Clone::clone).unwrap_or_default();
+    let running = running.read_owned().await;
+
+    Ok(tokio::task::spawn_blocking(move || {
+        let _running = running;
+        work(cancellation)
+    })
+    .await?)
+}
+
+/// Prints how long each phase of the run took and emits them as a `summary` event.
+/// The table is left out when only the check ran, as in most daemon iterations.
+/// Returns the phases, so the run can be exported as a trace.
+pub fn summarize(config: &Config, total: Duration) -> Vec<PhaseTiming> {
+    let phases = std::mem::take(&mut *TIMINGS.lock().expect("phase timings lock is poisoned"));
+    let total_ms = total.as_millis() as u64;
+    let seconds = |duration_ms: u64| format!("{:.1}s", duration_ms as f64 / 1000.0);
+
+    if phases.len() > 1 {
+        let width = phases
+            .iter()
+            .map(|timing| timing.phase.len())
+            .max()
+            .unwrap_or(0);
+
+        println!();
+        println!("⏱️ Timing summary");
+        for PhaseTiming {
+            phase,
+            duration_ms,
+            failed,
+            ..
+        } in &phases
+        {
+            println!(
+                "   {:<width$} {:>8}{}",
+                phase,
+                seconds(*duration_ms),
+                if *failed { " (failed)" } else { "" },
+            );
+        }
+        println!("   {:<width$} {:>8}", "total", seconds(total_ms));
+    }
+
+    emit(
+        config,
+        Event::Summary {
+            phases: phases.clone(),
+            total_ms,
+        },
+    );
+
+    phases
+}
+
+/// Counts bytes received by all download connections.
+pub struct DownloadProgress<'a> {
+    config: &'a Config,
+    total: Option<u64>,
+    received: AtomicU64,
+}
+
+impl<'a> DownloadProgress<'a> {
+    pub fn new(config: &'a Config, total: Option<u64>) -> Self {
+        Self {
+            config,
+            total,
+            received: AtomicU64::new(0),
+        }
+    }
+
+    pub fn advance(&self, bytes: usize) {
+        let before = self.received.fetch_add(bytes as u64, Ordering::Relaxed);
+        let after = before + bytes as u64;
+
+        if before / PROGRESS_STEP != after / PROGRESS_STEP {
+            self.report(after);
+        }
+    }
+
+    pub fn finish(&self) {
+        self.report(self.received.load(Ordering::Relaxed));
+    }
+
+    fn report(&self, bytes: u64) {
+        emit(
+            self.config,
+            Event::Downloaded {
+                bytes,
+                total: self.total,
+            },
+        );
+    }
+}
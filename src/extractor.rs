@@ -1,36 +1,214 @@
-use crate::target::{self, ArchiveType};
-use anyhow::Result;
+use crate::{
+    events::{self, Cancellation},
+    target::{self, ArchiveType},
+};
+use anyhow::{anyhow, Result};
 use std::{
     io::{Seek, SeekFrom},
+    path::{Component, Path},
     sync::Arc,
 };
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum ExtractError {
+    #[error("archive entry {0} is absolute or points outside of the extraction directory")]
+    UnsafePath(String),
+    #[error("archive entry {0} links to {1}, outside of the extraction directory")]
+    UnsafeLink(String, String),
+}
+
+/// Rejects entry paths which are absolute or contain parent directory components.
+fn ensure_safe_path(path: &Path) -> Result<(), ExtractError> {
+    let safe = path
+        .components()
+        .all(|component| matches!(component, Component::Normal(_) | Component::CurDir));
+
+    if safe {
+        Ok(())
+    } else {
+        Err(ExtractError::UnsafePath(
+            path.to_string_lossy().into_owned(),
+        ))
+    }
+}
+
+/// Rejects symlink targets which are absolute or lead out of the extraction directory, seen from the link at `path`.
+/// Relative targets climbing up within the release are common, e.g. into a shared `redist` directory.
+fn ensure_safe_link(path: &Path, target: &Path) -> Result<(), ExtractError> {
+    let unsafe_link = || {
+        ExtractError::UnsafeLink(
+            path.to_string_lossy().into_owned(),
+            target.to_string_lossy().into_owned(),
+        )
+    };
+
+    // Depth of the directory the link is in, below the extraction directory.
+    let mut depth = path
+        .components()
+        .filter(|component| matches!(component, Component::Normal(_)))
+        .count()
+        .checked_sub(1)
+        .ok_or_else(unsafe_link)?;
+    for component in target.components() {
+        match component {
+            Component::Normal(_) => depth += 1,
+            Component::CurDir => {}
+            Component::ParentDir => depth = depth.checked_sub(1).ok_or_else(unsafe_link)?,
+            Component::RootDir | Component::Prefix(_) => return Err(unsafe_link()),
+        }
+    }
+
+    Ok(())
+}
+
+/// Detects the archive type from the magic bytes at the start of the archive.
+pub fn sniff(magic: &[u8]) -> Option<ArchiveType> {
+    [
+        ArchiveType::Zip,
+        ArchiveType::Bzip2Tarball,
+        ArchiveType::GzipTarball,
+        ArchiveType::XzTarball,
+    ]
+    .into_iter()
+    .find(|archive_type| magic.starts_with(archive_type.magic()))
+}
 
 pub async fn extract(
-    archive_type: &target::ArchiveType,
+    expected_type: &target::ArchiveType,
     tempdir: Arc<tempfile::TempDir>,
-    server_archive: tokio::fs::File,
+    mut server_archive: tokio::fs::File,
+    threads: usize,
 ) -> Result<()> {
+    use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+    // Mirrors may serve a different archive than the target tuple suggests, so the content wins.
+    let mut magic = Vec::with_capacity(6);
+    server_archive.seek(SeekFrom::Start(0)).await?;
+    (&mut server_archive)
+        .take(6)
+        .read_to_end(&mut magic)
+        .await?;
+    let archive_type = sniff(&magic).unwrap_or(*expected_type);
+
     match archive_type {
-        ArchiveType::Zip => extract_zip(tempdir, server_archive).await?,
-        ArchiveType::Bzip2Tarball => extract_tarball(tempdir, server_archive).await?,
+        ArchiveType::Zip => extract_zip(tempdir, server_archive, threads).await?,
+        ArchiveType::Bzip2Tarball | ArchiveType::GzipTarball | ArchiveType::XzTarball => {
+            extract_tarball(archive_type, tempdir, server_archive).await?
+        }
     };
 
     Ok(())
 }
 
+/// Unpacks a tarball read sequentially from `reader`, so it can be extracted while it is still being downloaded.
+pub async fn extract_stream(
+    expected_type: target::ArchiveType,
+    tempdir: Arc<tempfile::TempDir>,
+    reader: impl tokio::io::AsyncRead + Unpin + Send + 'static,
+) -> Result<()> {
+    use std::io::BufRead;
+
+    // The bridge has to be created inside the runtime, which it blocks on for every read.
+    let reader = tokio_util::io::SyncIoBridge::new(reader);
+
+    events::spawn_blocking(move |cancellation| {
+        let mut reader = std::io::BufReader::new(reader);
+        let archive_type = sniff(reader.fill_buf()?).unwrap_or(expected_type);
+        if archive_type == ArchiveType::Zip {
+            return Err(anyhow!(
+                "the archive is a zip file, which can't be extracted while downloading - run without --stream-extract"
+            ));
+        }
+
+        unpack_tarball(archive_type, reader, tempdir.path(), &cancellation)
+    })
+    .await?
+}
+
 async fn extract_zip(
     tempdir: Arc<tempfile::TempDir>,
     server_archive: tokio::fs::File,
+    threads: usize,
 ) -> Result<()> {
-    use std::io::BufReader;
+    use std::io::{Cursor, Read};
     use zip::ZipArchive;
-    let mut server_archive = BufReader::new(server_archive.into_std().await);
+    let mut server_archive = server_archive.into_std().await;
     let tempdir_ = tempdir.clone();
 
-    tokio::task::spawn_blocking::<_, Result<()>>(move || {
+    events::spawn_blocking(move |cancellation| -> Result<()> {
+        use std::{
+            fs,
+            sync::atomic::{AtomicUsize, Ordering},
+        };
+
+        // Every worker needs its own reader position, so the archive is shared from memory.
+        let mut archive_buf = vec![];
         server_archive.seek(SeekFrom::Start(0))?;
-        let mut archive = ZipArchive::new(server_archive)?;
-        archive.extract(tempdir_.path())?;
+        server_archive.read_to_end(&mut archive_buf)?;
+        let mut archive = ZipArchive::new(Cursor::new(Arc::<[u8]>::from(archive_buf)))?;
+
+        let mut files = vec![];
+        let mut dirs = vec![];
+        for index in 0..archive.len() {
+            cancellation.check()?;
+            let entry = archive.by_index(index)?;
+            let name = entry.name().replace('\\', "/");
+            ensure_safe_path(Path::new(&name))?;
+            let enclosed_name = entry
+                .enclosed_name()
+                .ok_or_else(|| ExtractError::UnsafePath(name.clone()))?;
+            let out_path = tempdir_.path().join(enclosed_name);
+
+            if entry.is_dir() {
+                fs::create_dir_all(&out_path)?;
+                dirs.push((out_path, entry.unix_mode()));
+            } else {
+                if let Some(parent) = out_path.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                files.push(index);
+            }
+        }
+
+        let next = AtomicUsize::new(0);
+        std::thread::scope(|scope| {
+            let workers = (0..threads.max(1))
+                .map(|_| {
+                    let mut archive = archive.clone();
+                    let (files, next, root, cancellation) =
+                        (&files, &next, tempdir_.path(), &cancellation);
+
+                    scope.spawn(move || -> Result<()> {
+                        while let Some(index) = files.get(next.fetch_add(1, Ordering::Relaxed)) {
+                            cancellation.check()?;
+                            let mut entry = archive.by_index(*index)?;
+                            let out_path =
+                                root.join(entry.enclosed_name().ok_or_else(|| {
+                                    ExtractError::UnsafePath(entry.name().into())
+                                })?);
+
+                            let mut out_file = fs::File::create(&out_path)?;
+                            std::io::copy(&mut entry, &mut out_file)?;
+                            set_mode(&out_path, entry.unix_mode())?;
+                        }
+
+                        Ok(())
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            workers.into_iter().try_for_each(|worker| {
+                worker
+                    .join()
+                    .map_err(|_| anyhow::anyhow!("zip extraction worker panicked"))?
+            })
+        })?;
+
+        // Directory modes are applied last, so read-only directories can still be filled.
+        for (dir, mode) in dirs {
+            set_mode(&dir, mode)?;
+        }
 
         Ok(())
     })
@@ -39,32 +217,269 @@ async fn extract_zip(
     Ok(())
 }
 
+#[cfg(unix)]
+fn set_mode(path: &Path, mode: Option<u32>) -> std::io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    match mode {
+        Some(mode) => std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode)),
+        None => Ok(()),
+    }
+}
+
+#[cfg(not(unix))]
+fn set_mode(_path: &Path, _mode: Option<u32>) -> std::io::Result<()> {
+    Ok(())
+}
+
 async fn extract_tarball(
+    compression: ArchiveType,
     tempdir: Arc<tempfile::TempDir>,
     server_archive: tokio::fs::File,
 ) -> Result<()> {
-    use bzip2::bufread::BzDecoder;
     use std::io::BufReader;
 
     let mut server_archive = BufReader::new(server_archive.into_std().await);
     let tempdir_ = tempdir.clone();
 
-    tokio::task::spawn_blocking::<_, Result<()>>(move || {
-        use std::io::prelude::*;
-        use tar::Archive;
+    events::spawn_blocking(move |cancellation| -> Result<()> {
         server_archive.seek(std::io::SeekFrom::Start(0))?;
+        unpack_tarball(compression, server_archive, tempdir_.path(), &cancellation)
+    })
+    .await??;
+
+    Ok(())
+}
+
+/// Decompresses and unpacks a tarball into `dir`, reading it front to back only once.
+fn unpack_tarball(
+    compression: ArchiveType,
+    server_archive: impl std::io::BufRead,
+    dir: &Path,
+    cancellation: &Cancellation,
+) -> Result<()> {
+    use bzip2::bufread::BzDecoder;
+    use flate2::bufread::GzDecoder;
+    use std::io::Read;
+    use tar::Archive;
+    use xz2::bufread::XzDecoder;
 
-        let mut decoder = BzDecoder::new(server_archive);
-        let mut tarball_buf = vec![];
+    let decoder: Box<dyn Read + '_> = match compression {
+        ArchiveType::GzipTarball => Box::new(GzDecoder::new(server_archive)),
+        ArchiveType::XzTarball => Box::new(XzDecoder::new(server_archive)),
+        _ => Box::new(BzDecoder::new(server_archive)),
+    };
+    let mut tarball = Archive::new(decoder);
 
-        decoder.read_to_end(&mut tarball_buf)?;
+    for entry in tarball.entries()? {
+        cancellation.check()?;
+        let mut entry = entry?;
+        let path = entry.path()?.into_owned();
+        ensure_safe_path(&path)?;
 
-        let mut tarball = Archive::new(tarball_buf.as_slice());
-        tarball.unpack(tempdir_.path())?;
+        // Hard links name another entry of the archive, symlinks are resolved from where they are.
+        let entry_type = entry.header().entry_type();
+        if let Some(link_name) = entry.link_name()? {
+            if entry_type.is_hard_link() {
+                ensure_safe_path(&link_name)?;
+            } else if entry_type.is_symlink() {
+                ensure_safe_link(&path, &link_name)?;
+            }
+        }
 
-        Ok(())
-    })
-    .await??;
+        entry.unpack_in(dir)?;
+    }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    /// Gzipped tarball of `(path, link target, contents)` entries, written as is - the `tar` builder refuses
+    /// the paths these tests need.
+    fn tarball(entries: &[(&str, Option<&str>, &[u8])]) -> Vec<u8> {
+        let mut builder = tar::Builder::new(flate2::write::GzEncoder::new(
+            vec![],
+            flate2::Compression::fast(),
+        ));
+        for (path, link_target, contents) in entries {
+            let mut header = tar::Header::new_old();
+            header.as_old_mut().name[..path.len()].copy_from_slice(path.as_bytes());
+            match link_target {
+                Some(link_target) => {
+                    header.set_entry_type(tar::EntryType::Symlink);
+                    header.set_link_name_literal(link_target).unwrap();
+                }
+                None => header.set_entry_type(tar::EntryType::Regular),
+            }
+            header.set_mode(0o644);
+            header.set_size(contents.len() as u64);
+            header.set_cksum();
+            builder.append(&header, *contents).unwrap();
+        }
+
+        builder.into_inner().unwrap().finish().unwrap()
+    }
+
+    fn unpack_cancellable(
+        archive: &[u8],
+        cancellation: &Cancellation,
+    ) -> (tempfile::TempDir, Result<()>) {
+        let root = tempfile::tempdir().unwrap();
+        let dir = root.path().join("extracted");
+        std::fs::create_dir(&dir).unwrap();
+        let result = unpack_tarball(ArchiveType::GzipTarball, archive, &dir, cancellation);
+
+        (root, result)
+    }
+
+    fn unpack(archive: &[u8]) -> (tempfile::TempDir, Result<()>) {
+        unpack_cancellable(archive, &Cancellation::default())
+    }
+
+    fn is_unsafe(result: Result<()>) -> bool {
+        result.is_err_and(|e| e.downcast_ref::<ExtractError>().is_some())
+    }
+
+    #[test]
+    fn tarball_extracts_safe_entries() {
+        let (root, result) = unpack(&tarball(&[
+            ("teamspeak/ts3server", None, b"server"),
+            ("teamspeak/redist/libts.so", None, b"library"),
+            ("teamspeak/libts.so", Some("redist/libts.so"), b""),
+            ("teamspeak/sql/libts.so", Some("../redist/libts.so"), b""),
+        ]));
+
+        result.unwrap();
+        let dir = root.path().join("extracted/teamspeak");
+        assert_eq!(std::fs::read(dir.join("ts3server")).unwrap(), b"server");
+        assert_eq!(std::fs::read(dir.join("sql/libts.so")).unwrap(), b"library");
+    }
+
+    #[test]
+    fn tarball_rejects_parent_dir_entries() {
+        let (root, result) = unpack(&tarball(&[("teamspeak/../../escaped", None, b"evil")]));
+
+        assert!(is_unsafe(result));
+        assert!(!root.path().join("escaped").exists());
+    }
+
+    #[test]
+    fn tarball_rejects_absolute_entries() {
+        let (root, result) = unpack(&tarball(&[("/tmp/escaped", None, b"evil")]));
+
+        assert!(is_unsafe(result));
+        assert!(!root.path().join("extracted/tmp/escaped").exists());
+    }
+
+    #[test]
+    fn tarball_rejects_escaping_symlinks() {
+        for target in ["../../escaped", "/etc/passwd", "redist/../../../escaped"] {
+            let (root, result) = unpack(&tarball(&[("teamspeak/link", Some(target), b"")]));
+
+            assert!(is_unsafe(result), "{} is accepted", target);
+            assert!(
+                std::fs::symlink_metadata(root.path().join("extracted/teamspeak/link")).is_err()
+            );
+        }
+    }
+
+    #[test]
+    fn tarball_rejects_escaping_hard_links() {
+        let mut archive = tar::Builder::new(vec![]);
+        let mut header = tar::Header::new_old();
+        header.set_path("teamspeak/link").unwrap();
+        header.set_entry_type(tar::EntryType::Link);
+        header.set_link_name_literal("../../etc/passwd").unwrap();
+        header.set_size(0);
+        header.set_cksum();
+        archive.append(&header, &[][..]).unwrap();
+        let mut gzipped = flate2::write::GzEncoder::new(vec![], flate2::Compression::fast());
+        gzipped.write_all(&archive.into_inner().unwrap()).unwrap();
+
+        let (_root, result) = unpack(&gzipped.finish().unwrap());
+
+        assert!(is_unsafe(result));
+    }
+
+    #[test]
+    fn sniff_recognizes_every_archive_type() {
+        let gzipped = tarball(&[("teamspeak/ts3server", None, b"server")]);
+        let zipped = zip_archive(&[("teamspeak/ts3server", b"server")]);
+
+        assert_eq!(sniff(&gzipped), Some(ArchiveType::GzipTarball));
+        assert_eq!(sniff(&zipped), Some(ArchiveType::Zip));
+        assert_eq!(sniff(b"BZh91AY&SY"), Some(ArchiveType::Bzip2Tarball));
+        assert_eq!(sniff(b"\xfd7zXZ\x00\x00"), Some(ArchiveType::XzTarball));
+        assert_eq!(sniff(b"<!DOCTYPE html>"), None);
+    }
+
+    #[test]
+    fn tarball_stops_when_cancelled() {
+        let cancellation = Cancellation::default();
+        cancellation.cancel();
+
+        let (root, result) = unpack_cancellable(
+            &tarball(&[("teamspeak/ts3server", None, b"server")]),
+            &cancellation,
+        );
+
+        assert!(result.is_err_and(|e| e.downcast_ref::<events::Cancelled>().is_some()));
+        assert!(!root.path().join("extracted/teamspeak").exists());
+    }
+
+    /// Zip archive of `(name, contents)` files.
+    fn zip_archive(entries: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut writer = zip::ZipWriter::new(std::io::Cursor::new(vec![]));
+        for (name, contents) in entries {
+            writer
+                .start_file(*name, zip::write::FileOptions::default())
+                .unwrap();
+            writer.write_all(contents).unwrap();
+        }
+
+        writer.finish().unwrap().into_inner()
+    }
+
+    async fn extract_zip_archive(archive: &[u8]) -> (tempfile::TempDir, Result<()>) {
+        let root = tempfile::tempdir().unwrap();
+        let extracted = Arc::new(tempfile::tempdir_in(root.path()).unwrap());
+        let mut file = tempfile::tempfile().unwrap();
+        file.write_all(archive).unwrap();
+        let result = extract_zip(extracted, tokio::fs::File::from_std(file), 2).await;
+
+        (root, result)
+    }
+
+    #[tokio::test]
+    async fn zip_extracts_safe_entries() {
+        let (_root, result) =
+            extract_zip_archive(&zip_archive(&[("teamspeak/ts3server", b"server")])).await;
+
+        result.unwrap();
+    }
+
+    #[tokio::test]
+    async fn zip_rejects_parent_dir_entries() {
+        for name in [
+            "../escaped",
+            "teamspeak/../../escaped",
+            "teamspeak\\..\\..\\escaped",
+        ] {
+            let (root, result) = extract_zip_archive(&zip_archive(&[(name, b"evil")])).await;
+
+            assert!(is_unsafe(result), "{} is accepted", name);
+            assert!(!root.path().join("escaped").exists());
+        }
+    }
+
+    #[tokio::test]
+    async fn zip_rejects_absolute_entries() {
+        let (_root, result) = extract_zip_archive(&zip_archive(&[("/tmp/escaped", b"evil")])).await;
+
+        assert!(is_unsafe(result));
+    }
+}
@@ -0,0 +1,96 @@
+use crate::{cli::Config, list, local, remote, update};
+use anyhow::Result;
+use serde::Serialize;
+use std::{fmt::Display, str::FromStr};
+use thiserror::Error;
+
+#[derive(Clone, Copy)]
+pub enum Format {
+    KeyValue,
+    Json,
+}
+
+#[derive(Debug, Error)]
+pub enum FormatError {
+    #[error("facts format not recognized: {0} (expected `kv` or `json`)")]
+    NotRecognized(String),
+}
+
+impl FromStr for Format {
+    type Err = FormatError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "kv" => Ok(Self::KeyValue),
+            "json" => Ok(Self::Json),
+            _ => Err(FormatError::NotRecognized(s.to_owned())),
+        }
+    }
+}
+
+impl Display for Format {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::KeyValue => "kv",
+            Self::Json => "json",
+        })
+    }
+}
+
+#[derive(Serialize)]
+struct Facts {
+    installed_version: Option<String>,
+    latest_version: String,
+    update_available: bool,
+    releases_count: usize,
+    /// Where the symlink points to, in the releases layout.
+    symlink_target: Option<String>,
+}
+
+/// Prints facts about the installation for configuration management, without any other output.
+///
+/// Unlike `check`, the exit code doesn't depend on whether an update is available.
+pub async fn run(config: &Config) -> Result<()> {
+    let http = remote::client(config)?;
+    let (installed_version, latest_version) = update::fetch_versions(config, &http).await?;
+
+    let symlink_target = match local::in_place_dir(config) {
+        Some(_) => None,
+        None => tokio::fs::read_link(&config.symlink_path)
+            .await
+            .ok()
+            .map(|target| target.to_string_lossy().into_owned()),
+    };
+
+    let facts = Facts {
+        update_available: installed_version.as_ref() < Some(&latest_version),
+        installed_version: installed_version.map(|version| version.to_string()),
+        latest_version: latest_version.to_string(),
+        releases_count: list::installed(config).await?.len(),
+        symlink_target,
+    };
+
+    match config.facts_format {
+        Format::KeyValue => {
+            let Facts {
+                installed_version,
+                latest_version,
+                update_available,
+                releases_count,
+                symlink_target,
+            } = facts;
+
+            println!(
+                "installed_version={}",
+                installed_version.unwrap_or_default()
+            );
+            println!("latest_version={}", latest_version);
+            println!("update_available={}", update_available);
+            println!("releases_count={}", releases_count);
+            println!("symlink_target={}", symlink_target.unwrap_or_default());
+        }
+        Format::Json => println!("{}", serde_json::to_string_pretty(&facts)?),
+    }
+
+    Ok(())
+}
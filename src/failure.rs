@@ -0,0 +1,165 @@
+use crate::{
+    cli::Config,
+    events::{self, PhaseFailed},
+    hooks::{self, Hook},
+    metrics::Report,
+    remote, state,
+};
+use anyhow::Result;
+use serde::Serialize;
+
+/// Kind of the notification record of failures in the state file.
+const FAILURE: &str = "failure";
+
+/// Failure notification posted to `--failure-webhook`.
+#[derive(Serialize)]
+struct Notification {
+    /// One line summary, so chat webhooks (Slack, Mattermost, ...) can show the payload as is.
+    text: String,
+    host: Option<String>,
+    installed_version: Option<String>,
+    published_version: Option<String>,
+    phase: Option<String>,
+    errors: Vec<String>,
+    log_tail: Vec<String>,
+    /// Times the same failure happened since the last notification, which the daemon holds back in between.
+    repeated: u32,
+}
+
+/// Runs `on-failure` hooks and sends the failure notification. Problems with either are only printed.
+/// The daemon notifies about a failure repeating the last one only once per `--reminder-interval`.
+pub async fn notify(
+    config: &Config,
+    http: &reqwest::Client,
+    report: &Report,
+    error: &anyhow::Error,
+) {
+    let repeated = match digest(config, error).await {
+        Ok(Some(repeated)) => repeated,
+        Ok(None) => {
+            println!(
+                "🔕 Same failure as before - holding back the notification until the next reminder"
+            );
+            return;
+        }
+        Err(e) => {
+            println!("⚠️ Failed to check earlier failure notifications: {:#}", e);
+            0
+        }
+    };
+
+    if let Err(e) = hooks::run(config, Hook::OnFailure, report, Some(error)).await {
+        println!("⚠️ {:#}", e);
+    }
+
+    if let Err(e) = send(config, http, report, error, repeated).await {
+        println!("⚠️ Failed to send failure notification: {:#}", e);
+    }
+}
+
+/// Forgets the last failure notification after a successful run, so the next failure is sent right away.
+pub async fn resolved(config: &Config) {
+    if config.reminder_interval().is_none() {
+        return;
+    }
+
+    let forgotten = async {
+        let mut state = state::State::load(config).await?;
+        if state.forget_notified(FAILURE) {
+            state.save(config).await?;
+        }
+        anyhow::Ok(())
+    };
+    if let Err(e) = forgotten.await {
+        println!(
+            "⚠️ Failed to record the recovery in the state file: {:#}",
+            e
+        );
+    }
+}
+
+/// Times the failure was held back since it was last notified, `None` while it shouldn't be notified again.
+/// Failures are told apart by their phase and root cause, as the rest of the chain may name temporary files.
+async fn digest(config: &Config, error: &anyhow::Error) -> Result<Option<u32>> {
+    let Some(interval) = config.reminder_interval() else {
+        return Ok(Some(0));
+    };
+
+    let phase = error
+        .downcast_ref::<PhaseFailed>()
+        .map(|PhaseFailed(phase)| phase.as_str())
+        .unwrap_or_default();
+    let key = format!("{}: {}", phase, error.root_cause());
+
+    let mut state = state::State::load(config).await?;
+    let repeated = state.digest(FAILURE, &key, interval);
+    state.save(config).await?;
+
+    Ok(repeated)
+}
+
+async fn send(
+    config: &Config,
+    http: &reqwest::Client,
+    report: &Report,
+    error: &anyhow::Error,
+    repeated: u32,
+) -> Result<()> {
+    let failure_webhook = match &config.failure_webhook {
+        Some(failure_webhook) => failure_webhook,
+        None => return Ok(()),
+    };
+
+    remote::ensure_online(config, "sending the failure notification")?;
+
+    let host = hostname();
+    let phase = error
+        .downcast_ref::<PhaseFailed>()
+        .map(|PhaseFailed(phase)| phase.clone());
+
+    let notification = Notification {
+        text: format!(
+            "🚨 TeamSpeak update{} failed: {:#}{}",
+            host.as_ref()
+                .map(|host| format!(" on {}", host))
+                .unwrap_or_default(),
+            error,
+            match repeated {
+                0 => String::new(),
+                repeated => format!(
+                    " (failed the same way {} more time(s) since the last notification)",
+                    repeated
+                ),
+            }
+        ),
+        host,
+        installed_version: report.installed_version.as_ref().map(ToString::to_string),
+        published_version: report.published_version.as_ref().map(ToString::to_string),
+        phase,
+        errors: error.chain().map(ToString::to_string).collect(),
+        log_tail: events::tail(),
+        repeated,
+    };
+
+    http.post(failure_webhook)
+        .json(&notification)
+        .send()
+        .await?
+        .error_for_status()?;
+
+    println!("📣 Failure notification sent to {}", failure_webhook);
+
+    Ok(())
+}
+
+#[cfg(unix)]
+pub fn hostname() -> Option<String> {
+    nix::unistd::gethostname()
+        .ok()
+        .map(|host| host.to_string_lossy().into_owned())
+}
+
+#[cfg(not(unix))]
+pub fn hostname() -> Option<String> {
+    std::env::var("COMPUTERNAME").ok()
+}
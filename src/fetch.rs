@@ -0,0 +1,148 @@
+use crate::{
+    adopt,
+    cli::{self, Config, FetchCommand},
+    journal::Journal,
+    license, lint, local, manifest, overlay, ownership, permissions, remote, selinux,
+    shared_config,
+    update::INTERRUPTED_EXIT_CODE,
+};
+use anyhow::{anyhow, Result};
+use futures::StreamExt;
+use semver::Version;
+
+/// Installs the most recent published versions as releases without touching the symlink, e.g. to have
+/// intermediate versions at hand for testing downgrades after a long time offline.
+pub async fn run(config: &mut Config, command: &FetchCommand) -> Result<()> {
+    let FetchCommand { last, concurrency } = command;
+
+    cli::print_header();
+    lint::validate(config)?;
+    let config = &*config;
+
+    if local::in_place_dir(config).is_some() {
+        return Err(anyhow!(
+            "in-place installs keep a single release - there is nowhere to fetch releases into"
+        ));
+    }
+    if *concurrency == 0 {
+        return Err(anyhow!("--concurrency must be at least 1"));
+    }
+
+    config.print_summary();
+    lint::check(config)?;
+    adopt::ensure_layout(config).await?;
+
+    let http = remote::client(config)?;
+    let mut versions = remote::published_versions(config, &http).await?;
+    versions.drain(..versions.len().saturating_sub(*last));
+
+    let mut missing = vec![];
+    for version in versions.into_iter().rev() {
+        if local::release_path(config, &version)?.is_dir() {
+            println!("⏭️ {} is installed already", version);
+        } else {
+            missing.push(version);
+        }
+    }
+    if missing.is_empty() {
+        println!("✅ The {} most recent version(s) are installed", last);
+        return Ok(());
+    }
+    println!(
+        "📥 Fetching {} release(s), {} at a time",
+        missing.len(),
+        concurrency
+    );
+    println!();
+
+    // Every release gets its own journal, so a failed one is removed without touching the others.
+    let journals = missing
+        .iter()
+        .map(|_| Journal::default())
+        .collect::<Vec<_>>();
+    let fetches = futures::stream::iter(missing.iter().zip(&journals))
+        .map(|(version, journal)| {
+            let http = &http;
+            async move {
+                let result = fetch(config, http, journal, version).await;
+                if result.is_err() {
+                    if let Err(e) = journal.rollback(config).await {
+                        println!("⚠️ Failed to clean up release {}: {:#}", version, e);
+                    }
+                }
+                (version, result)
+            }
+        })
+        .buffer_unordered(*concurrency)
+        .collect::<Vec<_>>();
+
+    let mut results = tokio::select! {
+        results = fetches => results,
+        _ = tokio::signal::ctrl_c() => {
+            println!();
+            println!("🛑 Interrupted - cleaning up partial work...");
+            for journal in &journals {
+                journal.rollback(config).await?;
+            }
+            std::process::exit(INTERRUPTED_EXIT_CODE);
+        }
+    };
+
+    // Fetches finish in any order, the summary lists them newest first.
+    results.sort_by(|(a, _), (b, _)| b.cmp(a));
+    println!();
+    let mut failures = 0;
+    for (version, result) in results {
+        match result {
+            Ok(()) => println!(
+                "✅ Fetched {} into {}",
+                version,
+                local::release_path(config, version)?.to_string_lossy()
+            ),
+            Err(e) if remote::is_not_found(&e) => {
+                println!(
+                    "⏭️ {} is not published for {}",
+                    version, config.target_tuple
+                )
+            }
+            Err(e) => {
+                failures += 1;
+                println!("❌ {}: {:#}", version, e);
+            }
+        }
+    }
+
+    if failures > 0 {
+        return Err(anyhow!("{} release(s) failed to fetch", failures));
+    }
+
+    Ok(())
+}
+
+/// Downloads and prepares a release like an update does up to the swap. State of the active release
+/// is not carried over, as it would be stale by the time the release gets activated.
+async fn fetch(
+    config: &Config,
+    http: &reqwest::Client,
+    journal: &Journal,
+    version: &Version,
+) -> Result<()> {
+    let download = remote::download_release(config, http, version).await?;
+    local::extract_archive(
+        download.archive.try_clone().await?,
+        config,
+        version,
+        journal,
+    )
+    .await?;
+
+    overlay::apply(config, version).await?;
+    shared_config::link(config, version).await?;
+    manifest::write(config, version, &download).await?;
+    permissions::apply(config, version).await?;
+    license::link_license(config, version).await?;
+    ownership::apply(config, version).await?;
+    selinux::relabel(config, version).await?;
+
+    Ok(())
+}
@@ -0,0 +1,325 @@
+use crate::{
+    cli::Config,
+    download, events,
+    hooks::{self, Hook},
+    local,
+    metrics::Report,
+    remote, state,
+    target::ArchiveType,
+    update::{self, Outcome},
+};
+use anyhow::{anyhow, Result};
+use semver::Version;
+use std::path::Path;
+
+/// Options making `ssh` fail instead of prompting, so an unattended run never hangs on a host.
+const SSH_OPTIONS: &[&str] = &["-o", "BatchMode=yes", "-o", "ConnectTimeout=30"];
+
+/// Updates the hosts given with `--ssh`: the archive is downloaded once, sent to every outdated host
+/// and installed there by a shell script, so the hosts only need `ssh`, `tar` and a POSIX shell.
+///
+/// Runs as the update of `update::run_once`, so holds, blackouts, `--min-release-age`, maintenance windows, hooks
+/// and the records of the run apply to the fleet like to a local install. Hooks run on the controller, not on the hosts.
+pub async fn update(
+    config: &Config,
+    http: &reqwest::Client,
+    report: &mut Report,
+    install: bool,
+) -> Result<Outcome> {
+    let tar_flag = match config.target_tuple.archive_type() {
+        ArchiveType::Bzip2Tarball => "j",
+        ArchiveType::GzipTarball => "z",
+        ArchiveType::XzTarball => "J",
+        ArchiveType::Zip => {
+            return Err(anyhow!(
+                "updating over SSH supports tarballs only, not archives of {}",
+                config.target_tuple
+            ))
+        }
+    };
+
+    let published_version = events::phase(config, "check", async {
+        println!("⏳ Checking for updates...");
+        let published_version = remote::latest_version(config, http).await?;
+        println!(
+            "🌐 Determined latest remote TeamSpeak version: {}",
+            published_version
+        );
+        println!();
+        Ok(published_version)
+    })
+    .await?;
+    report.published_version = Some(published_version.clone());
+
+    let hosts = hosts(config);
+    let mut installed_versions = vec![];
+    for host in &hosts {
+        let installed_version = installed_version(config, host).await;
+        match &installed_version {
+            Ok(Some(version)) => println!("🖥️ {}: TeamSpeak {} is installed", host, version),
+            Ok(None) => println!("🖥️ {}: TeamSpeak is not installed", host),
+            Err(e) => println!("❌ {}: {:#}", host, e),
+        }
+        installed_versions.push(installed_version);
+    }
+
+    // Hosts running a newer release, e.g. one installed by hand or before `--lag` was set, are not downgraded.
+    let outdated = hosts
+        .iter()
+        .zip(&installed_versions)
+        .filter_map(|(host, installed_version)| match installed_version {
+            Ok(installed_version) if installed_version.as_ref() < Some(&published_version) => {
+                Some((*host, installed_version.clone()))
+            }
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    let mut failures = installed_versions
+        .iter()
+        .filter(|installed_version| installed_version.is_err())
+        .count();
+    // Hooks and metrics see the oldest release of the fleet as the installed one.
+    report.installed_version = outdated
+        .iter()
+        .filter_map(|(_, installed_version)| installed_version.clone())
+        .min();
+
+    let mut outcome = Outcome::NoAction;
+    if outdated.is_empty() {
+        println!("✅ All hosts run TeamSpeak {} or newer", published_version);
+    } else {
+        println!();
+        println!(
+            "⚠️ Update to {} available for {} host(s)",
+            published_version,
+            outdated.len()
+        );
+        let mut state = state::State::load(config).await?;
+        if let Some(outcome) = update::deferred(
+            config,
+            http,
+            &mut state,
+            report,
+            &published_version,
+            install,
+        )
+        .await?
+        {
+            return Ok(outcome);
+        }
+
+        hooks::run(config, Hook::PreDownload, report, None).await?;
+        // Fetched once and reused by later runs, however many hosts need it.
+        let archive_dir = local::archive_cache_dir(config)?;
+        events::phase(
+            config,
+            "download",
+            download::download_into(
+                config,
+                http,
+                config.target_tuple,
+                &published_version,
+                &archive_dir,
+                None,
+            ),
+        )
+        .await?;
+        let archive_path = archive_dir.join(
+            config
+                .target_tuple
+                .archive_filename(&config.filename_template, &published_version),
+        );
+        report.download_bytes = tokio::fs::metadata(&archive_path).await?.len();
+
+        for (host, _) in outdated {
+            let installed = async {
+                hooks::run(config, Hook::PreSwap, report, None).await?;
+                install_on(config, host, &archive_path, tar_flag, &published_version).await?;
+                hooks::run(config, Hook::PostSwap, report, None).await
+            };
+            match installed.await {
+                Ok(()) => {
+                    outcome = Outcome::Updated;
+                    println!("✅ {}: updated to TeamSpeak {}", host, published_version);
+                }
+                Err(e) => {
+                    failures += 1;
+                    println!("❌ {}: {:#}", host, e);
+                }
+            }
+        }
+
+        if state.pending.take().is_some() {
+            state.save(config).await?;
+        }
+    }
+
+    if failures > 0 {
+        return Err(anyhow!("{} host(s) failed to update", failures));
+    }
+    if let Outcome::Updated = outcome {
+        report.previous_version = report.installed_version.replace(published_version);
+    }
+
+    Ok(outcome)
+}
+
+/// Destinations of `--ssh`, which takes comma-separated lists too.
+pub fn hosts(config: &Config) -> Vec<&str> {
+    config
+        .ssh
+        .iter()
+        .flat_map(|hosts| hosts.split(','))
+        .map(str::trim)
+        .filter(|host| !host.is_empty())
+        .collect()
+}
+
+/// Version the symlink on the host points to, `None` if there is nothing at its path. Hosts with something else
+/// than a symlink there, e.g. a directory of a manual install, are refused, as the swap would link inside it.
+async fn installed_version(config: &Config, host: &str) -> Result<Option<Version>> {
+    let symlink_path = quote(&config.symlink_path.to_string_lossy());
+    let output = ssh(
+        host,
+        &format!(
+            "if [ -L {0} ]; then readlink -f {0}; elif [ -e {0} ]; then echo {0} is not a symlink >&2; exit 1; fi",
+            symlink_path
+        ),
+        None,
+    )
+    .await?;
+
+    let release_path = output.trim();
+    if release_path.is_empty() {
+        return Ok(None);
+    }
+    let version = Path::new(release_path)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .and_then(|name| local::release_version(config, name))
+        .ok_or_else(|| anyhow!("symlink points to {}", release_path))?;
+
+    Ok(Some(version))
+}
+
+/// Sends the archive to the host, extracts it into a new release, carries over `--preserve` paths of the
+/// installed release and swaps the symlink, keeping the old one next to it like local updates do.
+async fn install_on(
+    config: &Config,
+    host: &str,
+    archive_path: &Path,
+    tar_flag: &str,
+    published_version: &Version,
+) -> Result<()> {
+    let Config {
+        symlink_path,
+        releases_path,
+        preserve,
+        ..
+    } = config;
+
+    let releases_path = releases_path.to_string_lossy();
+    let remote_archive = format!("{}/.ts-updater-{}.part", releases_path, published_version);
+    let release_path = format!(
+        "{}/{}",
+        releases_path,
+        config.release_dir_name.dir_name(published_version)
+    );
+
+    println!("📤 {}: sending the archive", host);
+    ssh(
+        host,
+        &format!(
+            "mkdir -p {} && cat > {}",
+            quote(&releases_path),
+            quote(&remote_archive)
+        ),
+        Some(archive_path),
+    )
+    .await?;
+
+    let preserve = preserve
+        .iter()
+        .map(|path| quote(path.to_string_lossy().trim_matches('/')))
+        .collect::<Vec<_>>()
+        .join(" ");
+    let script = format!(
+        r#"set -e
+archive={archive}
+release={release}
+staged={staged}
+symlink={symlink}
+trap 'rm -rf "$staged" "$archive"' EXIT
+if [ -e "$symlink" ] && [ ! -L "$symlink" ]; then echo "$symlink is not a symlink" >&2; exit 1; fi
+if [ -e "$release" ]; then echo "$release already exists" >&2; exit 1; fi
+rm -rf "$staged"
+mkdir "$staged"
+tar -x{tar_flag}f "$archive" -C "$staged" --strip-components=1
+current=$(readlink -f "$symlink" || true)
+for path in {preserve}; do
+  if [ -n "$current" ] && [ -e "$current/$path" ]; then
+    mkdir -p "$(dirname "$staged/$path")"
+    rm -rf "$staged/$path"
+    cp -a "$current/$path" "$staged/$path"
+  fi
+done
+mv "$staged" "$release"
+if [ -L "$symlink" ]; then mv "$symlink" "$symlink.$(date +%s)"; fi
+ln -s "$release" "$symlink"
+"#,
+        archive = quote(&remote_archive),
+        release = quote(&release_path),
+        staged = quote(&format!("{}.staging", release_path)),
+        symlink = quote(&symlink_path.to_string_lossy()),
+        tar_flag = tar_flag,
+        preserve = preserve,
+    );
+
+    println!("📦 {}: installing TeamSpeak {}", host, published_version);
+    ssh(host, &script, None).await?;
+
+    Ok(())
+}
+
+/// Runs a shell command on the host, feeding it `stdin` if given, and returns its output.
+async fn ssh(host: &str, command: &str, stdin: Option<&Path>) -> Result<String> {
+    use std::process::Stdio;
+
+    if host.starts_with('-') {
+        return Err(anyhow!(
+            "refusing host {}, which ssh would take for an option",
+            host
+        ));
+    }
+    let stdin = match stdin {
+        Some(path) => Stdio::from(std::fs::File::open(path)?),
+        None => Stdio::null(),
+    };
+    let output = tokio::process::Command::new("ssh")
+        .args(SSH_OPTIONS)
+        .arg("--")
+        .arg(host)
+        .arg(command)
+        .stdin(stdin)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| anyhow!("failed to run ssh: {}", e))?
+        .wait_with_output()
+        .await?;
+
+    if !output.status.success() {
+        return Err(anyhow!(
+            "ssh failed with {}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Quotes a value for the remote shell.
+fn quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
@@ -0,0 +1,186 @@
+use crate::{
+    cli::Config,
+    events::{self, Cancellation},
+    local, manifest, preserve,
+};
+use anyhow::Result;
+use semver::Version;
+use std::{collections::BTreeMap, path::Path};
+
+/// Replaces files of the new release which are identical to the ones of the installed release with hard links to them.
+pub async fn link_unchanged(
+    config: &Config,
+    installed_version: Option<&Version>,
+    published_version: &Version,
+) -> Result<()> {
+    let installed_version = match installed_version {
+        Some(installed_version) if config.link_unchanged => installed_version,
+        _ => return Ok(()),
+    };
+    // In-place installs have no previous release directory to link to.
+    if local::in_place_dir(config).is_some() {
+        return Ok(());
+    }
+
+    let previous_path = local::release_path(config, installed_version)?;
+    if !previous_path.is_dir() {
+        return Ok(());
+    }
+    let release_path = local::staged_release_path(config, published_version)?;
+    let files = match manifest::read(&release_path)
+        .await?
+        .and_then(|manifest| manifest.files)
+    {
+        Some(files) => files,
+        None => return Ok(()),
+    };
+    // Preserved files like the database are written by the server, so a link would share them with the old release.
+    let files = files
+        .into_iter()
+        .filter(|(relative, _)| !preserve::is_preserved(config, relative))
+        .collect();
+
+    let (linked, saved_bytes) = {
+        let previous_path = previous_path.clone();
+        events::spawn_blocking(move |cancellation| {
+            link_files(&previous_path, &release_path, &files, &cancellation)
+        })
+        .await??
+    };
+
+    println!(
+        "🔗 Hard-linked {} unchanged file(s) from {}, saving {} KiB",
+        linked,
+        previous_path.to_string_lossy(),
+        saved_bytes / 1024
+    );
+
+    Ok(())
+}
+
+fn link_files(
+    previous_path: &Path,
+    release_path: &Path,
+    files: &BTreeMap<String, String>,
+    cancellation: &Cancellation,
+) -> Result<(u64, u64)> {
+    use std::fs;
+
+    // Hashing the previous release instead of trusting its manifest skips files changed since it was installed.
+    let previous_files = manifest::file_digests(previous_path)?
+        .into_iter()
+        .collect::<BTreeMap<_, _>>();
+
+    let mut linked = 0;
+    let mut saved_bytes = 0;
+    for (relative, digest) in files {
+        cancellation.check()?;
+        if previous_files.get(relative) != Some(digest) {
+            continue;
+        }
+
+        let previous_file = previous_path.join(relative);
+        let release_file = release_path.join(relative);
+        let (previous_metadata, metadata) = (
+            fs::symlink_metadata(&previous_file)?,
+            fs::symlink_metadata(&release_file)?,
+        );
+        // Linked files share permissions, so only files which already agree on them are linked.
+        if !previous_metadata.is_file()
+            || !metadata.is_file()
+            || previous_metadata.permissions() != metadata.permissions()
+        {
+            continue;
+        }
+
+        let mut link_path = release_file.clone().into_os_string();
+        link_path.push(".ts-updater-link");
+        fs::hard_link(&previous_file, &link_path)?;
+        fs::rename(&link_path, &release_file)?;
+
+        linked += 1;
+        saved_bytes += metadata.len();
+    }
+
+    Ok((linked, saved_bytes))
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use argh::FromArgs;
+    use std::{fs, os::unix::fs::MetadataExt};
+
+    /// Releases directory holding `3.13.6` and the staged `3.13.7`, both with the same server and database files.
+    fn releases() -> tempfile::TempDir {
+        let releases = tempfile::tempdir().unwrap();
+        for version in ["3.13.6", "3.13.7"] {
+            let release = releases.path().join(version);
+            fs::create_dir(&release).unwrap();
+            fs::write(release.join("ts3server"), "server").unwrap();
+            fs::write(release.join("ts3server.sqlitedb"), "database").unwrap();
+        }
+
+        let files = manifest::file_digests(&releases.path().join("3.13.7")).unwrap();
+        let manifest = manifest::Manifest {
+            version: "3.13.7".into(),
+            installed_at: 0,
+            source_url: String::new(),
+            sha256: String::new(),
+            files: Some(files.into_iter().collect()),
+            sizes: None,
+        };
+        fs::write(
+            releases
+                .path()
+                .join("3.13.7")
+                .join(manifest::MANIFEST_FILE_NAME),
+            serde_json::to_vec(&manifest).unwrap(),
+        )
+        .unwrap();
+
+        releases
+    }
+
+    fn inode(path: &Path) -> u64 {
+        fs::metadata(path).unwrap().ino()
+    }
+
+    #[tokio::test]
+    async fn preserved_files_are_not_linked() {
+        let releases = releases();
+        let releases_path = releases.path().to_string_lossy().into_owned();
+        let config = Config::from_args(
+            &["teamspeak-updater"],
+            &[
+                "--releases-path",
+                &releases_path,
+                "--link-unchanged",
+                "--preserve",
+                "ts3server.sqlitedb",
+            ],
+        )
+        .unwrap();
+
+        link_unchanged(
+            &config,
+            Some(&Version::new(3, 13, 6)),
+            &Version::new(3, 13, 7),
+        )
+        .await
+        .unwrap();
+
+        let (previous, staged) = (
+            releases.path().join("3.13.6"),
+            releases.path().join("3.13.7"),
+        );
+        assert_eq!(
+            inode(&previous.join("ts3server")),
+            inode(&staged.join("ts3server"))
+        );
+        assert_ne!(
+            inode(&previous.join("ts3server.sqlitedb")),
+            inode(&staged.join("ts3server.sqlitedb"))
+        );
+    }
+}
@@ -0,0 +1,46 @@
+use crate::{
+    cli::{self, Config, HoldCommand},
+    state::{self, Hold},
+};
+use anyhow::Result;
+
+pub async fn hold(config: &Config, command: &HoldCommand) -> Result<()> {
+    cli::print_header();
+
+    let mut state = state::State::load(config).await?;
+    let since = match &state.hold {
+        Some(hold) => hold.since,
+        None => state::unix_timestamp(),
+    };
+    state.hold = Some(Hold {
+        since,
+        reason: command.reason.clone(),
+    });
+    state.save(config).await?;
+
+    println!(
+        "🔒 Updates are on hold since {} - new versions will be reported, but not installed.",
+        state::format_timestamp(since)
+    );
+    println!("   ↳ run `teamspeak-updater unhold` to allow updates again");
+
+    Ok(())
+}
+
+pub async fn unhold(config: &Config) -> Result<()> {
+    cli::print_header();
+
+    let mut state = state::State::load(config).await?;
+    match state.hold.take() {
+        Some(hold) => {
+            state.save(config).await?;
+            println!(
+                "🔓 Updates are allowed again, they were on hold since {}.",
+                state::format_timestamp(hold.since)
+            );
+        }
+        None => println!("🔓 Updates are not on hold."),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,167 @@
+use crate::{
+    cli::Config,
+    events::{self, PhaseFailed},
+    local,
+    metrics::Report,
+};
+use anyhow::{anyhow, Result};
+use std::{fmt::Display, path::PathBuf};
+
+/// Point of the update at which executables of `<hooks-dir>/<hook>.d/` are run.
+#[derive(Clone, Copy)]
+pub enum Hook {
+    PreDownload,
+    PostExtract,
+    PreSwap,
+    PostSwap,
+    OnFailure,
+    /// An update waits for the maintenance window.
+    Pending,
+}
+
+impl Display for Hook {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::PreDownload => "pre-download",
+            Self::PostExtract => "post-extract",
+            Self::PreSwap => "pre-swap",
+            Self::PostSwap => "post-swap",
+            Self::OnFailure => "on-failure",
+            Self::Pending => "pending",
+        })
+    }
+}
+
+/// Runs all executables of the hook directory in name order, stopping at the first one which fails.
+/// `on-failure` hooks are followed by the `--on-failure` command.
+///
+/// Hooks learn about the update from `TS_*` environment variables. `error` is passed to `on-failure` hooks.
+pub async fn run(
+    config: &Config,
+    hook: Hook,
+    report: &Report,
+    error: Option<&anyhow::Error>,
+) -> Result<()> {
+    let Config {
+        hooks_dir,
+        on_failure,
+        symlink_path,
+        releases_path,
+        ..
+    } = config;
+
+    let mut commands = match hooks_dir {
+        Some(hooks_dir) => executables(hooks_dir.join(format!("{}.d", hook)))
+            .await?
+            .into_iter()
+            .map(|executable| {
+                let name = executable.to_string_lossy().into_owned();
+                (name, tokio::process::Command::new(executable))
+            })
+            .collect(),
+        None => vec![],
+    };
+    if let (Hook::OnFailure, Some(on_failure)) = (hook, on_failure) {
+        commands.push((on_failure.clone(), shell_command(on_failure)));
+    }
+    if commands.is_empty() {
+        return Ok(());
+    }
+
+    let mut env = vec![
+        ("TS_HOOK", hook.to_string()),
+        (
+            "TS_SYMLINK_PATH",
+            symlink_path.to_string_lossy().into_owned(),
+        ),
+        (
+            "TS_RELEASES_PATH",
+            releases_path.to_string_lossy().into_owned(),
+        ),
+    ];
+    if let Some(installed_version) = &report.installed_version {
+        env.push(("TS_INSTALLED_VERSION", installed_version.to_string()));
+    }
+    if let Some(published_version) = &report.published_version {
+        env.push(("TS_PUBLISHED_VERSION", published_version.to_string()));
+        env.push((
+            "TS_RELEASE_DIR",
+            local::release_path(config, published_version)?
+                .to_string_lossy()
+                .into_owned(),
+        ));
+    }
+    if let Some(changes) = &report.changes {
+        env.push(("TS_CHANGES", changes.summary()));
+        env.push(("TS_CHANGED_FILES", changes.lines().join("\n")));
+    }
+    if let Some(error) = error {
+        env.push(("TS_ERROR", format!("{:#}", error)));
+        if let Some(PhaseFailed(phase)) = error.downcast_ref() {
+            env.push(("TS_FAILED_PHASE", phase.clone()));
+        }
+        env.push(("TS_LOG_TAIL", events::tail().join("\n")));
+    }
+
+    for (name, mut command) in commands {
+        println!("🪝 Running {} hook {}", hook, name);
+
+        let status = command
+            .envs(env.iter().cloned())
+            .status()
+            .await
+            .map_err(|e| anyhow!("failed to run hook {}: {}", name, e))?;
+
+        if !status.success() {
+            return Err(anyhow!("{} hook {} failed with {}", hook, name, status));
+        }
+    }
+
+    Ok(())
+}
+
+fn shell_command(command_line: &str) -> tokio::process::Command {
+    #[cfg(unix)]
+    let mut command = tokio::process::Command::new("sh");
+    #[cfg(unix)]
+    command.arg("-c");
+    #[cfg(windows)]
+    let mut command = tokio::process::Command::new("cmd");
+    #[cfg(windows)]
+    command.arg("/C");
+
+    command.arg(command_line);
+    command
+}
+
+async fn executables(hooks_dir: PathBuf) -> Result<Vec<PathBuf>> {
+    use std::io::ErrorKind;
+    use tokio::fs;
+
+    let mut read_dir = match fs::read_dir(&hooks_dir).await {
+        Ok(read_dir) => read_dir,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(vec![]),
+        Err(e) => return Err(e.into()),
+    };
+
+    let mut executables = vec![];
+    while let Some(entry) = read_dir.next_entry().await? {
+        let metadata = fs::metadata(entry.path()).await?;
+
+        #[cfg(unix)]
+        let executable = {
+            use std::os::unix::fs::PermissionsExt;
+            metadata.permissions().mode() & 0o111 != 0
+        };
+        #[cfg(not(unix))]
+        let executable = true;
+
+        // Like run-parts, anything not executable (READMEs, disabled hooks) is skipped.
+        if metadata.is_file() && executable {
+            executables.push(entry.path());
+        }
+    }
+
+    executables.sort();
+    Ok(executables)
+}
@@ -0,0 +1,163 @@
+use crate::{
+    cli::{self, BuildImageCommand, Config},
+    extractor, remote,
+    target::ArchiveType,
+};
+use anyhow::{anyhow, Result};
+use semver::Version;
+use std::{path::Path, sync::Arc};
+
+/// Directory of the build context holding the server files.
+const SERVER_DIR_NAME: &str = "server";
+/// Records the version in the build context, so a refresh can tell whether the server files are current.
+const VERSION_FILE_NAME: &str = ".ts-updater-version";
+const SERVER_HOME: &str = "/opt/teamspeak";
+
+/// Writes a `docker build` context with the server release and a Dockerfile into `--out`, and builds it on request.
+pub async fn build(config: &Config, command: &BuildImageCommand) -> Result<()> {
+    let BuildImageCommand {
+        version,
+        out,
+        name,
+        base_image,
+        build,
+        builder,
+    } = command;
+
+    cli::print_header();
+
+    if config.target_tuple.archive_type() == ArchiveType::Zip {
+        return Err(anyhow!(
+            "container images need a Linux target tuple, not {}",
+            config.target_tuple
+        ));
+    }
+    tokio::fs::create_dir_all(out).await?;
+
+    let http = remote::client(config)?;
+    let version = match version {
+        Some(version) => version.clone(),
+        None => remote::latest_version(config, &http).await?,
+    };
+
+    let server_path = out.join(SERVER_DIR_NAME);
+    let version_path = out.join(VERSION_FILE_NAME);
+    // The version on the first line, the URL the archive came from on the second.
+    let recorded = tokio::fs::read_to_string(&version_path)
+        .await
+        .unwrap_or_default();
+    let mut recorded = recorded.lines();
+    let source = if recorded.next() == Some(version.to_string().as_str()) && server_path.is_dir() {
+        println!("✅ Build context already contains TeamSpeak {}", version);
+        recorded.next().unwrap_or_default().to_owned()
+    } else {
+        let download =
+            remote::download_archive(config, &http, config.target_tuple, &version).await?;
+
+        // Extracted next to its final place, so moving it in is a rename.
+        let tempdir = Arc::new(tempfile::tempdir_in(out)?);
+        print!("📦 Extracting the archive... ");
+        extractor::extract(
+            &config.target_tuple.archive_type(),
+            tempdir.clone(),
+            download.archive,
+            config.extract_threads as usize,
+        )
+        .await?;
+        println!("✅");
+
+        // TeamSpeak archives contain a single top-level directory.
+        let mut read_dir = tokio::fs::read_dir(tempdir.path()).await?;
+        let extracted = read_dir
+            .next_entry()
+            .await?
+            .ok_or_else(|| anyhow!("archive of {} is empty", version))?
+            .path();
+        if tokio::fs::symlink_metadata(&server_path).await.is_ok() {
+            tokio::fs::remove_dir_all(&server_path).await?;
+        }
+        tokio::fs::rename(&extracted, &server_path).await?;
+        tokio::fs::write(&version_path, format!("{}\n{}\n", version, download.url)).await?;
+
+        download.url.to_string()
+    };
+
+    tokio::fs::write(
+        out.join("Dockerfile"),
+        dockerfile(config, base_image, &version, &source),
+    )
+    .await?;
+    println!(
+        "🐳 Build context for TeamSpeak {} written to {}",
+        version,
+        out.to_string_lossy()
+    );
+
+    let tags = [format!("{}:{}", name, version), format!("{}:latest", name)];
+    if !*build {
+        println!(
+            "   ↳ build it with `{} build -t {} -t {} {}`",
+            builder,
+            tags[0],
+            tags[1],
+            out.to_string_lossy()
+        );
+        return Ok(());
+    }
+
+    run_builder(builder, &tags, out).await?;
+    println!("✅ Built image {} ({})", tags[0], tags[1]);
+
+    Ok(())
+}
+
+fn dockerfile(config: &Config, base_image: &str, version: &Version, source: &str) -> String {
+    let binary = config.product.binary_name();
+    let ports = match config.product {
+        remote::Product::Ts3 => "9987/udp 10011/tcp 30033/tcp",
+        remote::Product::Ts6 => "9987/udp 10080/tcp 30033/tcp",
+    };
+
+    format!(
+        r#"# Generated by teamspeak-updater {updater_version} - changes are overwritten by the next build-image run.
+FROM {base_image}
+LABEL org.opencontainers.image.title="TeamSpeak server" \
+      org.opencontainers.image.version="{version}" \
+      org.opencontainers.image.source="{source}"
+RUN useradd --system --home-dir {home} teamspeak
+COPY --chown=teamspeak {server_dir}/ {home}/
+USER teamspeak
+WORKDIR {home}
+ENV LD_LIBRARY_PATH={home}
+EXPOSE {ports}
+ENTRYPOINT ["./{binary}"]
+"#,
+        updater_version = env!("CARGO_PKG_VERSION"),
+        base_image = base_image,
+        version = version,
+        source = source,
+        home = SERVER_HOME,
+        server_dir = SERVER_DIR_NAME,
+        ports = ports,
+        binary = binary,
+    )
+}
+
+async fn run_builder(builder: &str, tags: &[String], context: &Path) -> Result<()> {
+    let mut command = tokio::process::Command::new(builder);
+    command.arg("build");
+    for tag in tags {
+        command.args(["-t", tag]);
+    }
+
+    let status = command
+        .arg(context)
+        .status()
+        .await
+        .map_err(|e| anyhow!("failed to run {}: {}", builder, e))?;
+    if !status.success() {
+        return Err(anyhow!("{} build failed with {}", builder, status));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,239 @@
+use crate::{
+    adopt, checksum,
+    cli::{self, Config, InstallFromFileCommand},
+    events, failure,
+    journal::Journal,
+    lint, local,
+    metrics::Report,
+    otlp, preserve, remote, snapshot, state, state_db, target, update,
+};
+use anyhow::{anyhow, Result};
+use semver::Version;
+use std::path::Path;
+
+/// Installs a release from a local archive without contacting the mirror, e.g. on air-gapped hosts.
+pub async fn run(config: &mut Config, command: &InstallFromFileCommand) -> Result<()> {
+    cli::print_header();
+    lint::validate(config)?;
+
+    let config = &*config;
+    // Only used for artifact records and failure webhooks, if they are configured.
+    let http = remote::client(config)?;
+
+    config.print_summary();
+    lint::check(config)?;
+    adopt::ensure_layout(config).await?;
+
+    let journal = Journal::default();
+    let started_at = std::time::Instant::now();
+    let mut report = Report::default();
+
+    let result = update::rollback_on_abort(
+        config,
+        &journal,
+        install(config, &http, command, &journal, &mut report),
+    )
+    .await;
+
+    if let Err(e) = &result {
+        failure::notify(config, &http, &report, e).await;
+    }
+    report.success = result.is_ok();
+    report.duration = started_at.elapsed();
+    let phases = events::summarize(config, report.duration);
+    let outcome = result.as_ref().map(|_| "installed");
+    otlp::export(
+        config,
+        &http,
+        "install-from-file",
+        &report,
+        &phases,
+        outcome,
+    )
+    .await;
+    if let Err(e) = state_db::record(config, "install-from-file", &report, outcome).await {
+        println!("⚠️ Failed to record the run in the state database: {}", e);
+    }
+
+    result
+}
+
+async fn install(
+    config: &Config,
+    http: &reqwest::Client,
+    command: &InstallFromFileCommand,
+    journal: &Journal,
+    report: &mut Report,
+) -> Result<()> {
+    let InstallFromFileCommand {
+        archive,
+        version,
+        sha256_file,
+    } = command;
+
+    let archive_path = archive
+        .canonicalize()
+        .map_err(|e| anyhow!("{}: {}", archive.to_string_lossy(), e))?;
+    let os_file_name = archive_path
+        .file_name()
+        .ok_or_else(|| anyhow!("{} is not a file", archive_path.to_string_lossy()))?;
+    // Only used for matching against templates and checksum files, where non-UTF-8 names can't match anyway.
+    let file_name = &*os_file_name.to_string_lossy();
+
+    let version = match version {
+        Some(version) => version.clone(),
+        None => archive_version(config, file_name)?,
+    };
+
+    let installed_version = local::installed_version(config).await?;
+    report.installed_version = installed_version.clone();
+    report.published_version = Some(version.clone());
+
+    if installed_version.as_ref() == Some(&version) {
+        println!("✅ TeamSpeak {} is already installed.", version);
+        return Ok(());
+    }
+
+    println!(
+        "📁 Installing {} from {}",
+        version,
+        archive_path.to_string_lossy()
+    );
+
+    let sha256_file = match sha256_file {
+        Some(sha256_file) => Some(sha256_file.clone()),
+        None => {
+            let mut sha256_file_name = os_file_name.to_os_string();
+            sha256_file_name.push(".sha256");
+            Some(archive_path.with_file_name(sha256_file_name)).filter(|path| path.is_file())
+        }
+    };
+    let sha256 = archive_sha256(&archive_path).await?;
+    let verified = sha256_file.is_some().then_some(checksum::Algorithm::Sha256);
+    if let Some(sha256_file) = sha256_file {
+        let expected = expected_sha256(&sha256_file, file_name).await?;
+        if !expected.eq_ignore_ascii_case(&sha256) {
+            return Err(anyhow!(
+                "checksum mismatch for {}: {} expects {}, got {}",
+                file_name,
+                sha256_file.to_string_lossy(),
+                expected,
+                sha256
+            ));
+        }
+        println!("🔏 Checksum matches {}", sha256_file.to_string_lossy());
+    }
+
+    if let Err(e) = preserve::warn_unpreserved(config, installed_version.as_ref()).await {
+        println!(
+            "⚠️ Failed to check the installed release for local modifications: {:#}",
+            e
+        );
+    }
+
+    let archive = tokio::fs::File::open(&archive_path).await?;
+    let download = remote::Download {
+        size: archive.metadata().await?.len(),
+        archive,
+        url: reqwest::Url::from_file_path(&archive_path)
+            .map_err(|_| anyhow!("archive path can't be turned into an URL"))?,
+        sha256,
+        verified,
+    };
+
+    let mut state = state::State::load(config).await?;
+    let (extracted, snapshot) = tokio::join!(
+        update::extract_phase(
+            config,
+            journal,
+            &mut state,
+            &version,
+            local::extract_archive(
+                download.archive.try_clone().await?,
+                config,
+                &version,
+                journal,
+            ),
+        ),
+        events::phase(
+            config,
+            "snapshot",
+            snapshot::before_update(config, installed_version.as_ref()),
+        )
+    );
+    extracted?;
+    snapshot::print_before_update(config, snapshot?.as_deref());
+    update::install_release(
+        config,
+        http,
+        journal,
+        report,
+        installed_version.as_ref(),
+        &version,
+        &download,
+    )
+    .await?;
+
+    println!();
+    println!("✅ TeamSpeak {} successfully installed! ✅", version);
+    report.installed_version = Some(version);
+
+    Ok(())
+}
+
+/// Parses the version out of the archive file name, which has to be for the configured target tuple.
+fn archive_version(config: &Config, file_name: &str) -> Result<Version> {
+    let Config {
+        filename_template,
+        target_tuple,
+        ..
+    } = config;
+
+    if let Some(version) = target_tuple.archive_version(filename_template, file_name) {
+        return Ok(version);
+    }
+
+    match target::Tuple::ALL
+        .iter()
+        .find(|tuple| tuple.archive_version(filename_template, file_name).is_some())
+    {
+        Some(tuple) => Err(anyhow!(
+            "{} is an archive for {}, but the target tuple is {} - pass --target-tuple {} if that's intended",
+            file_name,
+            tuple,
+            target_tuple,
+            tuple
+        )),
+        None => Err(anyhow!(
+            "version can't be told from the file name {} (expected {}) - pass --version",
+            file_name,
+            filename_template
+        )),
+    }
+}
+
+async fn archive_sha256(archive_path: &Path) -> Result<String> {
+    use sha2::{Digest, Sha256};
+
+    let archive_path = archive_path.to_owned();
+    tokio::task::spawn_blocking(move || -> Result<String> {
+        let mut hasher = Sha256::new();
+        std::io::copy(&mut std::fs::File::open(archive_path)?, &mut hasher)?;
+        Ok(hex::encode(hasher.finalize()))
+    })
+    .await?
+}
+
+/// Reads the checksum of `file_name` from a file in `sha256sum` format, or a file with the bare checksum.
+async fn expected_sha256(sha256_file: &Path, file_name: &str) -> Result<String> {
+    let contents = tokio::fs::read_to_string(sha256_file)
+        .await
+        .map_err(|e| anyhow!("{}: {}", sha256_file.to_string_lossy(), e))?;
+    checksum::find_checksum(&contents, file_name).ok_or_else(|| {
+        anyhow!(
+            "{} has no checksum for {}",
+            sha256_file.to_string_lossy(),
+            file_name
+        )
+    })
+}
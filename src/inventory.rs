@@ -0,0 +1,260 @@
+use crate::{
+    cli::{Config, InventoryCommand},
+    local, manifest, state,
+};
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use std::{collections::BTreeMap, fmt::Display, path::Path, str::FromStr};
+use thiserror::Error;
+
+#[derive(Clone, Copy)]
+pub enum Format {
+    Json,
+    CycloneDx,
+}
+
+#[derive(Debug, Error)]
+pub enum FormatError {
+    #[error("inventory format not recognized: {0} (expected `json` or `cyclonedx`)")]
+    NotRecognized(String),
+}
+
+impl FromStr for Format {
+    type Err = FormatError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "json" => Ok(Self::Json),
+            "cyclonedx" => Ok(Self::CycloneDx),
+            _ => Err(FormatError::NotRecognized(s.to_owned())),
+        }
+    }
+}
+
+impl Display for Format {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Json => "json",
+            Self::CycloneDx => "cyclonedx",
+        })
+    }
+}
+
+#[derive(Serialize)]
+struct Inventory {
+    updater_version: &'static str,
+    generated_at: String,
+    product: String,
+    target: String,
+    version: String,
+    release_path: String,
+    source_url: Option<String>,
+    archive_sha256: Option<String>,
+    installed_at: Option<String>,
+    files: Vec<InventoryFile>,
+}
+
+#[derive(Serialize)]
+struct InventoryFile {
+    path: String,
+    sha256: String,
+    size: Option<u64>,
+    /// Content differs from the hash recorded at install time.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    modified: bool,
+    /// Recorded at install time, but gone since. Its hash is the recorded one.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    missing: bool,
+}
+
+/// Prints a machine-readable inventory of a release, for compliance reporting of deployed binaries.
+pub async fn run(config: &Config, command: &InventoryCommand) -> Result<()> {
+    let InventoryCommand {
+        version,
+        format,
+        out,
+    } = command;
+
+    let version = match version {
+        Some(version) => version.clone(),
+        None => local::installed_version(config)
+            .await?
+            .ok_or_else(|| anyhow!("no release is installed"))?,
+    };
+    let release_path = local::release_path(config, &version)?;
+    if !release_path.is_dir() {
+        return Err(anyhow!("release {} is not installed", version));
+    }
+
+    let manifest = manifest::read(&release_path).await?;
+    let recorded = manifest
+        .as_ref()
+        .and_then(|manifest| manifest.files.clone());
+    let files = {
+        let release_path = release_path.clone();
+        tokio::task::spawn_blocking(move || files(&release_path, recorded.as_ref())).await??
+    };
+
+    let inventory = Inventory {
+        updater_version: env!("CARGO_PKG_VERSION"),
+        generated_at: humantime::format_rfc3339_seconds(std::time::SystemTime::now()).to_string(),
+        product: config.product.to_string(),
+        target: config.target_tuple.to_string(),
+        version: version.to_string(),
+        release_path: release_path.to_string_lossy().into_owned(),
+        source_url: manifest
+            .as_ref()
+            .map(|manifest| manifest.source_url.clone()),
+        archive_sha256: manifest.as_ref().map(|manifest| manifest.sha256.clone()),
+        installed_at: manifest
+            .as_ref()
+            .map(|manifest| state::format_timestamp(manifest.installed_at).to_string()),
+        files,
+    };
+
+    let contents = match format {
+        Format::Json => serde_json::to_string_pretty(&inventory)?,
+        Format::CycloneDx => serde_json::to_string_pretty(&cyclonedx(&inventory))?,
+    };
+
+    match out {
+        Some(out) => {
+            tokio::fs::write(out, contents + "\n").await?;
+            println!(
+                "🧾 Inventory of release {} written to {}",
+                version,
+                out.to_string_lossy()
+            );
+        }
+        None => println!("{}", contents),
+    }
+
+    Ok(())
+}
+
+/// Files shipped with the release, as recorded in its manifest, hashed as they are now.
+/// Releases without recorded hashes list every file in their directory.
+fn files(
+    release_path: &Path,
+    recorded: Option<&BTreeMap<String, String>>,
+) -> Result<Vec<InventoryFile>> {
+    let digests = manifest::file_digests(release_path)?
+        .into_iter()
+        .collect::<BTreeMap<_, _>>();
+    let size = |path: &str| {
+        std::fs::symlink_metadata(release_path.join(path))
+            .ok()
+            .map(|metadata| metadata.len())
+    };
+
+    let recorded = match recorded {
+        Some(recorded) => recorded,
+        None => {
+            return Ok(digests
+                .into_iter()
+                .map(|(path, sha256)| InventoryFile {
+                    size: size(&path),
+                    path,
+                    sha256,
+                    modified: false,
+                    missing: false,
+                })
+                .collect())
+        }
+    };
+
+    Ok(recorded
+        .iter()
+        .map(|(path, recorded_sha256)| match digests.get(path) {
+            Some(sha256) => InventoryFile {
+                path: path.clone(),
+                sha256: sha256.clone(),
+                size: size(path),
+                modified: sha256 != recorded_sha256,
+                missing: false,
+            },
+            None => InventoryFile {
+                path: path.clone(),
+                sha256: recorded_sha256.clone(),
+                size: None,
+                modified: false,
+                missing: true,
+            },
+        })
+        .collect())
+}
+
+/// CycloneDX 1.5 BOM with the server as the described component and its files as components.
+fn cyclonedx(inventory: &Inventory) -> serde_json::Value {
+    use serde_json::json;
+
+    let hashes = |sha256: &str| json!([{ "alg": "SHA-256", "content": sha256 }]);
+
+    let mut properties = vec![
+        json!({ "name": "teamspeak-updater:product", "value": inventory.product }),
+        json!({ "name": "teamspeak-updater:target", "value": inventory.target }),
+        json!({ "name": "teamspeak-updater:release-path", "value": inventory.release_path }),
+    ];
+    if let Some(installed_at) = &inventory.installed_at {
+        properties.push(json!({ "name": "teamspeak-updater:installed-at", "value": installed_at }));
+    }
+    let mut server = json!({
+        "type": "application",
+        "bom-ref": format!("teamspeak-server@{}", inventory.version),
+        "name": "teamspeak-server",
+        "version": inventory.version,
+        "properties": properties,
+    });
+    if let Some(source_url) = &inventory.source_url {
+        let mut reference = json!({ "type": "distribution", "url": source_url });
+        if let Some(archive_sha256) = &inventory.archive_sha256 {
+            reference["hashes"] = hashes(archive_sha256);
+        }
+        server["externalReferences"] = json!([reference]);
+    }
+
+    let components = inventory
+        .files
+        .iter()
+        .map(|file| {
+            let mut component = json!({
+                "type": "file",
+                "name": file.path,
+                "hashes": hashes(&file.sha256),
+            });
+            let mut properties = vec![];
+            if let Some(size) = file.size {
+                properties
+                    .push(json!({ "name": "teamspeak-updater:size", "value": size.to_string() }));
+            }
+            if file.modified {
+                properties.push(json!({ "name": "teamspeak-updater:modified", "value": "true" }));
+            }
+            if file.missing {
+                properties.push(json!({ "name": "teamspeak-updater:missing", "value": "true" }));
+            }
+            if !properties.is_empty() {
+                component["properties"] = json!(properties);
+            }
+            component
+        })
+        .collect::<Vec<_>>();
+
+    json!({
+        "bomFormat": "CycloneDX",
+        "specVersion": "1.5",
+        "version": 1,
+        "metadata": {
+            "timestamp": inventory.generated_at,
+            "tools": {
+                "components": [{
+                    "type": "application",
+                    "name": "teamspeak-updater",
+                    "version": inventory.updater_version,
+                }],
+            },
+            "component": server,
+        },
+        "components": components,
+    })
+}
@@ -0,0 +1,111 @@
+use crate::{cli::Config, privileged, service};
+use anyhow::Result;
+use std::{
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+/// Records filesystem changes made during an update, so they can be undone when it gets interrupted.
+#[derive(Default)]
+pub struct Journal {
+    entries: Mutex<Entries>,
+}
+
+#[derive(Default)]
+struct Entries {
+    staging_dirs: Vec<PathBuf>,
+    created_release: Option<PathBuf>,
+    moved_symlink: Option<(PathBuf, PathBuf)>,
+    /// Server stopped for the swap, with the version to start again.
+    stopped_server: Option<semver::Version>,
+}
+
+impl Journal {
+    pub fn staging_dir(&self, path: &Path) {
+        self.entries().staging_dirs.push(path.to_owned());
+    }
+
+    pub fn created_release(&self, path: &Path) {
+        self.entries().created_release = Some(path.to_owned());
+    }
+
+    pub fn moved_symlink(&self, from: &Path, to: &Path) {
+        self.entries().moved_symlink = Some((from.to_owned(), to.to_owned()));
+    }
+
+    pub fn stopped_server(&self, version: &semver::Version) {
+        self.entries().stopped_server = Some(version.clone());
+    }
+
+    pub fn started_server(&self) {
+        self.entries().stopped_server = None;
+    }
+
+    /// Keeps the new release and symlink from being undone, once the symlink points to the release. A server
+    /// stopped for the swap is started in the new release from then on.
+    pub fn commit(&self, version: &semver::Version) {
+        let mut entries = self.entries();
+        entries.created_release = None;
+        entries.moved_symlink = None;
+        if entries.stopped_server.is_some() {
+            entries.stopped_server = Some(version.clone());
+        }
+    }
+
+    pub async fn rollback(&self, config: &Config) -> Result<()> {
+        use tokio::fs;
+
+        let Entries {
+            staging_dirs,
+            created_release,
+            moved_symlink,
+            stopped_server,
+        } = std::mem::take(&mut *self.entries());
+
+        if let Some((symlink_path, backup_path)) = moved_symlink {
+            println!(
+                "↩️ Restoring symlink {}",
+                symlink_path.as_os_str().to_string_lossy()
+            );
+            if fs::symlink_metadata(&symlink_path).await.is_ok() {
+                privileged::remove_symlink(config, &symlink_path).await?;
+            }
+            privileged::rename(config, &backup_path, &symlink_path).await?;
+        }
+
+        if let Some(release_path) = created_release {
+            println!(
+                "🧹 Removing partial release {}",
+                release_path.as_os_str().to_string_lossy()
+            );
+            fs::remove_dir_all(release_path)
+                .await
+                .or_else(ignore_not_found)?;
+        }
+
+        for staging_dir in staging_dirs {
+            fs::remove_dir_all(staging_dir)
+                .await
+                .or_else(ignore_not_found)?;
+        }
+
+        if let Some(version) = stopped_server {
+            service::restart_after_failure(config, Some(&version)).await;
+        }
+
+        Ok(())
+    }
+
+    fn entries(&self) -> std::sync::MutexGuard<'_, Entries> {
+        self.entries.lock().expect("journal lock is poisoned")
+    }
+}
+
+fn ignore_not_found(e: std::io::Error) -> std::io::Result<()> {
+    if e.kind() == ErrorKind::NotFound {
+        Ok(())
+    } else {
+        Err(e)
+    }
+}
@@ -0,0 +1,97 @@
+use crate::{
+    cli::{Config, GenerateLaunchdCommand},
+    service,
+};
+use anyhow::Result;
+
+/// Label of the generated LaunchDaemon when `--launchd-label` isn't given.
+const DEFAULT_LABEL: &str = "com.teamspeak.server";
+
+/// Prints a LaunchDaemon property list running the server through the symlink, so it survives updates unchanged.
+pub async fn generate(config: &Config, command: &GenerateLaunchdCommand) -> Result<()> {
+    let GenerateLaunchdCommand {
+        user,
+        accept_license,
+        out,
+    } = command;
+
+    let label = config.launchd_label.as_deref().unwrap_or(DEFAULT_LABEL);
+    let server_dir = config.symlink_path.to_string_lossy();
+
+    let mut program_arguments = vec![format!("{}/ts3server", server_dir)];
+    if *accept_license {
+        program_arguments.push(String::from("license_accepted=1"));
+    }
+
+    let mut plist = String::from(concat!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+        "<!DOCTYPE plist PUBLIC \"-//Apple//DTD PLIST 1.0//EN\" \"http://www.apple.com/DTDs/PropertyList-1.0.dtd\">\n",
+        "<plist version=\"1.0\">\n",
+        "<dict>\n",
+    ));
+    let mut entry = |key: &str, value: String| {
+        plist.push_str(&format!("    <key>{}</key>\n    {}\n", key, value));
+    };
+    let string = |value: &str| format!("<string>{}</string>", escape(value));
+
+    entry("Label", string(label));
+    entry(
+        "ProgramArguments",
+        format!(
+            "<array>\n{}    </array>",
+            program_arguments
+                .iter()
+                .map(|argument| format!("        {}\n", string(argument)))
+                .collect::<String>()
+        ),
+    );
+    entry("WorkingDirectory", string(&server_dir));
+    // The server loads its libraries from its own directory, like `ts3server_minimal_runscript.sh` sets up.
+    entry(
+        "EnvironmentVariables",
+        format!(
+            "<dict>\n        <key>DYLD_LIBRARY_PATH</key>\n        {}\n    </dict>",
+            string(&server_dir)
+        ),
+    );
+    if let Some(user) = user {
+        entry("UserName", string(user));
+    }
+    entry("RunAtLoad", String::from("<true/>"));
+    entry("KeepAlive", String::from("<true/>"));
+    entry(
+        "StandardOutPath",
+        string(&format!("{}/logs/launchd.log", server_dir)),
+    );
+    entry(
+        "StandardErrorPath",
+        string(&format!("{}/logs/launchd.log", server_dir)),
+    );
+    plist.push_str("</dict>\n</plist>\n");
+
+    match out {
+        Some(out) => {
+            tokio::fs::write(out, &plist).await?;
+            println!(
+                "✅ LaunchDaemon {} written to {}",
+                label,
+                out.to_string_lossy()
+            );
+            println!(
+                "   ↳ install it as {} and load it with `launchctl load`, then update with --launchd-label {}",
+                service::plist_path(label),
+                label
+            );
+        }
+        None => print!("{}", plist),
+    }
+
+    Ok(())
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
@@ -0,0 +1,138 @@
+use semver::Version;
+use std::{fmt::Display, str::FromStr};
+use thiserror::Error;
+
+/// Format of the `{date}` placeholder.
+const DATE_FORMAT: &str = "%Y-%m-%d";
+/// Length of a date written in `DATE_FORMAT`.
+const DATE_LENGTH: usize = 10;
+
+/// How release directories inside the releases directory are named, e.g. `teamspeak-{version}` or `{date}_{version}`.
+#[derive(Clone, PartialEq, Eq)]
+pub struct ReleaseLayout {
+    segments: Vec<Segment>,
+}
+
+#[derive(Clone, PartialEq, Eq)]
+enum Segment {
+    Literal(String),
+    Version,
+    /// Day the release got installed.
+    Date,
+}
+
+#[derive(Debug, Error)]
+pub enum ReleaseLayoutError {
+    #[error("release directory name {0} must contain `{{version}}` exactly once")]
+    MissingVersion(String),
+    #[error("release directory name {0} has an unknown placeholder (expected `{{version}}` or `{{date}}`)")]
+    UnknownPlaceholder(String),
+    #[error("release directory name {0} must not contain path separators or start with a dot")]
+    NotADirectoryName(String),
+}
+
+impl Default for ReleaseLayout {
+    fn default() -> Self {
+        Self {
+            segments: vec![Segment::Version],
+        }
+    }
+}
+
+impl FromStr for ReleaseLayout {
+    type Err = ReleaseLayoutError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.contains(['/', '\\']) || s.starts_with('.') {
+            return Err(ReleaseLayoutError::NotADirectoryName(s.to_owned()));
+        }
+
+        let mut segments = vec![];
+        let mut rest = s;
+        while let Some(start) = rest.find('{') {
+            let end = rest[start..]
+                .find('}')
+                .map(|end| start + end)
+                .ok_or_else(|| ReleaseLayoutError::UnknownPlaceholder(s.to_owned()))?;
+            if start > 0 {
+                segments.push(Segment::Literal(rest[..start].to_owned()));
+            }
+            segments.push(match &rest[start + 1..end] {
+                "version" => Segment::Version,
+                "date" => Segment::Date,
+                _ => return Err(ReleaseLayoutError::UnknownPlaceholder(s.to_owned())),
+            });
+            rest = &rest[end + 1..];
+        }
+        if !rest.is_empty() {
+            segments.push(Segment::Literal(rest.to_owned()));
+        }
+
+        if segments
+            .iter()
+            .filter(|segment| **segment == Segment::Version)
+            .count()
+            != 1
+        {
+            return Err(ReleaseLayoutError::MissingVersion(s.to_owned()));
+        }
+
+        Ok(Self { segments })
+    }
+}
+
+impl Display for ReleaseLayout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for segment in &self.segments {
+            match segment {
+                Segment::Literal(literal) => f.write_str(literal)?,
+                Segment::Version => f.write_str("{version}")?,
+                Segment::Date => f.write_str("{date}")?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl ReleaseLayout {
+    /// Name of the directory for a release of the version installed today.
+    pub fn dir_name(&self, version: &Version) -> String {
+        let today = chrono::Local::now().date_naive();
+        self.segments
+            .iter()
+            .map(|segment| match segment {
+                Segment::Literal(literal) => literal.clone(),
+                Segment::Version => version.to_string(),
+                Segment::Date => today.format(DATE_FORMAT).to_string(),
+            })
+            .collect()
+    }
+
+    /// Version of the release in a directory named after this layout.
+    pub fn parse(&self, dir_name: &str) -> Option<Version> {
+        parse_segments(&self.segments, dir_name).flatten()
+    }
+}
+
+/// `None` if the name doesn't match the segments, otherwise the version it holds, if any.
+fn parse_segments(segments: &[Segment], name: &str) -> Option<Option<Version>> {
+    match segments.split_first() {
+        None => name.is_empty().then_some(None),
+        Some((Segment::Literal(literal), rest)) => {
+            parse_segments(rest, name.strip_prefix(literal.as_str())?)
+        }
+        Some((Segment::Date, rest)) => {
+            let date = name.get(..DATE_LENGTH)?;
+            chrono::NaiveDate::parse_from_str(date, DATE_FORMAT).ok()?;
+            parse_segments(rest, &name[DATE_LENGTH..])
+        }
+        // Versions can contain the separators of the pattern (`1.0.0-beta`), so every split is tried.
+        Some((Segment::Version, rest)) => (1..=name.len())
+            .filter(|&end| name.is_char_boundary(end))
+            .find_map(|end| {
+                let version = Version::parse(&name[..end]).ok()?;
+                parse_segments(rest, &name[end..]).map(|_| Some(version))
+            }),
+    }
+}
@@ -0,0 +1,121 @@
+use crate::cli::Config;
+use anyhow::Result;
+use semver::Version;
+
+/// Makes sure the dynamic loader of this host resolves everything the server binary of the new release needs,
+/// before it gets activated. Catches e.g. glibc releases on Alpine or a missing `libstdc++`.
+#[cfg(target_os = "linux")]
+pub async fn check(config: &Config, published_version: &Version) -> Result<()> {
+    use crate::{local, target::Tuple};
+    use anyhow::anyhow;
+
+    if config.skip_library_check
+        || !matches!(
+            config.target_tuple,
+            Tuple::LinuxX8664 | Tuple::LinuxX86 | Tuple::LinuxAlpine
+        )
+    {
+        return Ok(());
+    }
+
+    let staged_path = local::staged_release_path(config, published_version)?;
+    let binary_name = config.product.binary_name();
+    let binary = staged_path.join(binary_name);
+    if !binary.is_file() {
+        return Ok(());
+    }
+
+    // The start scripts put the release directory on the library path, for the libraries shipped with the server.
+    let library_path = match std::env::var_os("LD_LIBRARY_PATH") {
+        Some(library_path) => {
+            let mut paths = vec![staged_path.clone()];
+            paths.extend(std::env::split_paths(&library_path));
+            std::env::join_paths(paths)?
+        }
+        None => staged_path.clone().into_os_string(),
+    };
+    let output = match tokio::process::Command::new("ldd")
+        .arg(&binary)
+        .env("LD_LIBRARY_PATH", library_path)
+        .output()
+        .await
+    {
+        Ok(output) => output,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            println!(
+                "⚠️ ldd is not installed - not checking shared libraries of {}",
+                binary_name
+            );
+            return Ok(());
+        }
+        Err(e) => return Err(anyhow!("failed to run ldd: {}", e)),
+    };
+
+    let report = format!(
+        "{}{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let missing = missing(&report);
+    if !missing.is_empty() {
+        return Err(anyhow!(
+            "{} of release {} can't run on this host, missing {} - install them, or check that --target-tuple matches the host's C library (linux_alpine for musl)",
+            binary_name,
+            published_version,
+            missing.join(", ")
+        ));
+    }
+    // Static binaries have nothing to resolve, which ldd reports with a failure.
+    if !output.status.success() && !report.contains("not a dynamic executable") {
+        return Err(anyhow!(
+            "ldd failed to check {} of release {}: {}",
+            binary_name,
+            published_version,
+            report.trim()
+        ));
+    }
+    println!("🧩 Shared libraries of {} are present", binary_name);
+
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+pub async fn check(_config: &Config, _published_version: &Version) -> Result<()> {
+    Ok(())
+}
+
+/// Libraries and symbol versions the loader couldn't resolve, as reported by glibc's or musl's `ldd`.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn missing(report: &str) -> Vec<String> {
+    let mut missing = vec![];
+    for line in report.lines().map(str::trim) {
+        let found = if let Some((library, _)) = line.split_once(" => not found") {
+            // glibc: `libstdc++.so.6 => not found`
+            Some(library.trim().to_owned())
+        } else if let Some(rest) = line.strip_prefix("Error loading shared library ") {
+            // musl: `Error loading shared library libstdc++.so.6: No such file or directory (needed by ...)`
+            rest.split_once(':').map(|(library, _)| library.to_owned())
+        } else if line.contains("version `") && line.contains("' not found") {
+            // glibc: `.../ts3server: /lib64/libc.so.6: version `GLIBC_2.34' not found (required by ...)`
+            line.split_once("version `")
+                .and_then(|(_, rest)| rest.split_once('\''))
+                .map(|(version, _)| version.to_owned())
+        } else if let Some(rest) = line.strip_prefix("Error relocating ") {
+            // musl: `Error relocating ts3server: gnu_get_libc_version: symbol not found`
+            rest.split(": ")
+                .nth(1)
+                .filter(|_| line.ends_with("symbol not found"))
+                .map(|symbol| format!("symbol {}", symbol))
+        } else {
+            None
+        };
+
+        if let Some(found) = found {
+            if !missing.contains(&found) {
+                missing.push(found);
+            }
+        }
+    }
+
+    missing
+}
@@ -0,0 +1,218 @@
+use crate::{cli::Config, local, state};
+use anyhow::{anyhow, Result};
+use std::path::Path;
+
+pub const LICENSE_FILE_NAME: &str = "licensekey.dat";
+
+pub async fn link_license(config: &Config, published_version: &semver::Version) -> Result<()> {
+    use std::io::ErrorKind;
+    use tokio::fs;
+
+    let Config { license_path, .. } = config;
+    let license_path = match license_path {
+        Some(license_path) => fs::canonicalize(license_path).await?,
+        None => return Ok(()),
+    };
+
+    let release_license = local::release_path(config, published_version)?.join(LICENSE_FILE_NAME);
+
+    match fs::remove_file(&release_license).await {
+        Err(e) if e.kind() != ErrorKind::NotFound => return Err(e.into()),
+        _ => {}
+    }
+
+    #[cfg(unix)]
+    fs::symlink(&license_path, &release_license).await?;
+    #[cfg(windows)]
+    fs::symlink_file(&license_path, &release_license).await?;
+
+    println!(
+        "🔑 Linked license file {}",
+        license_path.as_os_str().to_string_lossy()
+    );
+
+    validate_license(&release_license).await
+}
+
+/// Seconds from the Unix epoch to 2013-01-01, which timestamps in license blocks count from.
+const LICENSE_EPOCH: u64 = 0x50e2_2700;
+/// Key kind, public key, block type and the two timestamps every license block starts with.
+const BLOCK_HEADER_LEN: usize = 42;
+/// Licenses expiring sooner than this get a warning, so there's time to renew them.
+const EXPIRY_WARNING: u64 = 14 * 24 * 60 * 60;
+
+/// Unix timestamps a license chain is valid between, the overlap of all its blocks.
+#[derive(Debug, PartialEq, Eq)]
+struct Validity {
+    not_before: u64,
+    not_after: u64,
+}
+
+/// Checks the license file linked into the release is present and valid right now, so the server doesn't restart
+/// unlicensed. A license whose dates can't be read only gets a warning, the server is the final judge.
+async fn validate_license(release_license: &Path) -> Result<()> {
+    let metadata = tokio::fs::metadata(release_license).await.map_err(|e| {
+        anyhow!(
+            "license file {} is not accessible: {}",
+            release_license.to_string_lossy(),
+            e
+        )
+    })?;
+
+    if !metadata.is_file() || metadata.len() == 0 {
+        return Err(anyhow!(
+            "license file {} is empty or not a regular file",
+            release_license.to_string_lossy()
+        ));
+    }
+
+    let contents = tokio::fs::read(release_license).await?;
+    let Some(Validity {
+        not_before,
+        not_after,
+    }) = parse_validity(&contents)
+    else {
+        println!(
+            "⚠️ Validity dates of license file {} are not in a known format - not checking whether it expired",
+            release_license.to_string_lossy()
+        );
+        return Ok(());
+    };
+
+    let now = state::unix_timestamp();
+    if now < not_before {
+        return Err(anyhow!(
+            "license file {} is not valid before {}",
+            release_license.to_string_lossy(),
+            state::format_timestamp(not_before)
+        ));
+    }
+    if now > not_after {
+        return Err(anyhow!(
+            "license file {} expired on {}",
+            release_license.to_string_lossy(),
+            state::format_timestamp(not_after)
+        ));
+    }
+
+    if not_after - now < EXPIRY_WARNING {
+        println!(
+            "⚠️ License expires on {} - renew it soon",
+            state::format_timestamp(not_after)
+        );
+    } else {
+        println!(
+            "🔑 License is valid until {}",
+            state::format_timestamp(not_after)
+        );
+    }
+
+    Ok(())
+}
+
+/// Validity of a license key file, a version byte followed by a chain of license blocks, either raw or
+/// base64-encoded. `None` when the contents don't follow that format.
+fn parse_validity(contents: &[u8]) -> Option<Validity> {
+    use base64::Engine;
+
+    let text = std::str::from_utf8(contents)
+        .ok()
+        .map(|text| text.split_whitespace().collect::<String>());
+    let decoded = text.and_then(|text| base64::engine::general_purpose::STANDARD.decode(text).ok());
+    let chain = decoded.as_deref().unwrap_or(contents);
+
+    let (&version, mut blocks) = chain.split_first()?;
+    if version != 1 || blocks.is_empty() {
+        return None;
+    }
+
+    let mut validity = Validity {
+        not_before: 0,
+        not_after: u64::MAX,
+    };
+    while !blocks.is_empty() {
+        let header = blocks.get(..BLOCK_HEADER_LEN)?;
+        // Only public keys of kind 0 are known.
+        if header[0] != 0 {
+            return None;
+        }
+        let timestamp = |offset: usize| {
+            let bytes = header[offset..offset + 4].try_into().ok()?;
+            Some(u64::from(u32::from_be_bytes(bytes)) + LICENSE_EPOCH)
+        };
+        validity.not_before = validity.not_before.max(timestamp(34)?);
+        validity.not_after = validity.not_after.min(timestamp(38)?);
+
+        // Blocks end with content specific to their type, in which a name runs up to a NUL byte.
+        let content = &blocks[BLOCK_HEADER_LEN..];
+        let content_len = match header[33] {
+            // Intermediate: 4 bytes, then the issuer.
+            0 => 4 + content.get(4..)?.iter().position(|&byte| byte == 0)? + 1,
+            // Server: license type and 4 bytes, then the holder.
+            2 => 5 + content.get(5..)?.iter().position(|&byte| byte == 0)? + 1,
+            // Ephemeral: nothing.
+            32 => 0,
+            _ => return None,
+        };
+        blocks = blocks.get(BLOCK_HEADER_LEN + content_len..)?;
+    }
+
+    Some(validity)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block(block_type: u8, not_before: u64, not_after: u64, content: &[u8]) -> Vec<u8> {
+        let mut block = vec![0; 34];
+        block[33] = block_type;
+        block.extend(((not_before - LICENSE_EPOCH) as u32).to_be_bytes());
+        block.extend(((not_after - LICENSE_EPOCH) as u32).to_be_bytes());
+        block.extend(content);
+        block
+    }
+
+    fn chain() -> Vec<u8> {
+        let mut chain = vec![1];
+        chain.extend(block(0, 1_400_000_000, 1_900_000_000, b"\0\0\0\0Issuer\0"));
+        chain.extend(block(
+            2,
+            1_500_000_000,
+            1_800_000_000,
+            b"\x01\0\0\0\x20Holder\0",
+        ));
+        chain.extend(block(32, 1_450_000_000, 1_850_000_000, b""));
+        chain
+    }
+
+    #[test]
+    fn validity_is_the_overlap_of_all_blocks() {
+        let validity = Validity {
+            not_before: 1_500_000_000,
+            not_after: 1_800_000_000,
+        };
+
+        assert_eq!(parse_validity(&chain()), Some(validity));
+    }
+
+    #[test]
+    fn base64_encoded_keys_are_decoded() {
+        use base64::Engine;
+
+        let encoded = base64::engine::general_purpose::STANDARD.encode(chain());
+        let wrapped = format!("{}\n{}\n", &encoded[..40], &encoded[40..]);
+
+        assert_eq!(parse_validity(wrapped.as_bytes()), parse_validity(&chain()));
+    }
+
+    #[test]
+    fn unknown_formats_have_no_validity() {
+        let mut truncated = chain();
+        truncated.truncate(60);
+
+        assert_eq!(parse_validity(b"not a license"), None);
+        assert_eq!(parse_validity(&truncated), None);
+        assert_eq!(parse_validity(&[2]), None);
+    }
+}
@@ -0,0 +1,420 @@
+use crate::{
+    cli::Config,
+    local::{self, InstallMode},
+    remote, selinux, target,
+};
+use anyhow::{anyhow, Result};
+
+/// Checks that configuration is usable at all, reporting all problems together. Normalizes the mirror URL.
+pub fn validate(config: &mut Config) -> Result<()> {
+    let mut problems = vec![];
+
+    match reqwest::Url::parse(&config.mirror_url) {
+        Ok(url) if !["http", "https"].contains(&url.scheme()) => problems.push(format!(
+            "mirror URL {} must use http or https scheme",
+            config.mirror_url
+        )),
+        Ok(url) if !remote::is_allowed(&config.allowed_hosts, &url) => problems.push(format!(
+            "mirror URL {} is not on one of the --allowed-hosts",
+            config.mirror_url
+        )),
+        Ok(_) => {
+            if !config.mirror_url.ends_with('/') {
+                config.mirror_url.push('/');
+            }
+        }
+        Err(e) => problems.push(format!(
+            "mirror URL {} is not a valid URL: {}",
+            config.mirror_url, e
+        )),
+    }
+
+    let Config {
+        releases_path,
+        symlink_path,
+        license_path,
+        overlay_dir,
+        shared_config_dir,
+        hooks_dir,
+        work_dir,
+        cache_dir,
+        selinux_relabel,
+        selinux_context,
+        query_snapshot,
+        query_password,
+        install_mode,
+        target_dir,
+        ssh,
+        startscript,
+        launchd_label,
+        rc_service,
+        stage_only,
+        ipv4_only,
+        ipv6_only,
+        bind_address,
+        assume_version,
+        ..
+    } = config;
+
+    if [*startscript, launchd_label.is_some(), rc_service.is_some()]
+        .iter()
+        .filter(|given| **given)
+        .count()
+        > 1
+    {
+        problems.push(String::from(
+            "--startscript, --launchd-label and --rc-service all manage the server - pass only one of them",
+        ));
+    }
+
+    if *stage_only && (*install_mode == InstallMode::InPlace || !ssh.is_empty()) {
+        problems.push(String::from(
+            "--stage-only needs the releases install mode and can't be combined with --ssh",
+        ));
+    }
+
+    if assume_version.is_some() && !ssh.is_empty() {
+        problems.push(String::from(
+            "--assume-version can't be combined with --ssh, as the hosts may have different versions installed",
+        ));
+    }
+
+    // ssh would take such a host for an option, e.g. `-oProxyCommand=...`.
+    for host in ssh.iter().filter(|host| host.starts_with('-')) {
+        problems.push(format!("--ssh host {} can't start with a dash", host));
+    }
+
+    match bind_address {
+        _ if *ipv4_only && *ipv6_only => problems.push(String::from(
+            "--ipv4-only and --ipv6-only can't be combined",
+        )),
+        Some(address) if (*ipv4_only && address.is_ipv6()) || (*ipv6_only && address.is_ipv4()) => {
+            problems.push(format!(
+                "--bind-address {} is of the IP version excluded by --ipv4-only or --ipv6-only",
+                address
+            ))
+        }
+        _ => (),
+    }
+
+    match (install_mode, target_dir) {
+        (InstallMode::InPlace, _) if !ssh.is_empty() => problems.push(String::from(
+            "updating over SSH supports the releases install mode only",
+        )),
+        // Paths name locations on the remote hosts.
+        (InstallMode::Releases, _) if !ssh.is_empty() => {}
+        (InstallMode::InPlace, None) => {
+            problems.push(String::from("in-place install mode requires --target-dir"))
+        }
+        (InstallMode::InPlace, Some(target_dir)) => {
+            if !target_dir.is_dir() {
+                problems.push(format!(
+                    "target directory {} does not exist - create it first",
+                    target_dir.to_string_lossy()
+                ));
+            } else if let Err(e) = tempfile::tempfile_in(&target_dir) {
+                problems.push(format!(
+                    "target directory {} is not writable: {}",
+                    target_dir.to_string_lossy(),
+                    e
+                ));
+            }
+        }
+        (InstallMode::Releases, _) => {
+            if !releases_path.is_dir() {
+                problems.push(format!(
+                    "releases directory {} does not exist - create it first",
+                    releases_path.to_string_lossy()
+                ));
+            } else if let Err(e) = tempfile::tempfile_in(&releases_path) {
+                problems.push(format!(
+                    "releases directory {} is not writable: {}",
+                    releases_path.to_string_lossy(),
+                    e
+                ));
+            }
+
+            match symlink_path.parent() {
+                Some(parent) if symlink_path.file_name().is_some() => {
+                    if !parent.as_os_str().is_empty() && !parent.is_dir() {
+                        problems.push(format!(
+                            "parent directory of the symlink {} does not exist",
+                            symlink_path.to_string_lossy()
+                        ));
+                    }
+                }
+                _ => problems.push(format!(
+                    "symlink path {} must name a file inside some directory",
+                    symlink_path.to_string_lossy()
+                )),
+            }
+        }
+    }
+
+    if let Some(license_path) = license_path {
+        if !license_path.is_file() {
+            problems.push(format!(
+                "license file {} does not exist",
+                license_path.to_string_lossy()
+            ));
+        }
+    }
+
+    if let Some(overlay_dir) = overlay_dir {
+        if !overlay_dir.is_dir() {
+            problems.push(format!(
+                "overlay directory {} does not exist",
+                overlay_dir.to_string_lossy()
+            ));
+        }
+    }
+
+    if let Some(shared_config_dir) = shared_config_dir {
+        if !shared_config_dir.is_dir() {
+            problems.push(format!(
+                "shared config directory {} does not exist - create it first",
+                shared_config_dir.to_string_lossy()
+            ));
+        }
+    }
+
+    if let Some(hooks_dir) = hooks_dir {
+        if !hooks_dir.is_dir() {
+            problems.push(format!(
+                "hooks directory {} does not exist",
+                hooks_dir.to_string_lossy()
+            ));
+        }
+    }
+
+    if let Some(work_dir) = work_dir {
+        if !work_dir.is_dir() {
+            problems.push(format!(
+                "work directory {} does not exist - create it first",
+                work_dir.to_string_lossy()
+            ));
+        } else if let Err(e) = tempfile::tempfile_in(&work_dir) {
+            problems.push(format!(
+                "work directory {} is not writable: {}",
+                work_dir.to_string_lossy(),
+                e
+            ));
+        }
+    }
+
+    if let Some(cache_dir) = cache_dir {
+        if !cache_dir.is_dir() {
+            problems.push(format!(
+                "cache directory {} does not exist - create it first",
+                cache_dir.to_string_lossy()
+            ));
+        }
+    }
+
+    if *query_snapshot && query_password.is_none() {
+        problems.push(String::from("--query-snapshot requires --query-password"));
+    }
+
+    if *selinux_relabel && selinux_context.is_some() {
+        problems.push(String::from(
+            "--selinux-relabel and --selinux-context are mutually exclusive",
+        ));
+    }
+
+    if problems.is_empty() {
+        return Ok(());
+    }
+
+    println!("🩺 Configuration errors");
+    for problem in &problems {
+        println!("❌ {}", problem);
+    }
+    println!();
+
+    Err(anyhow!(
+        "configuration is invalid ({} problem(s)), see the list above",
+        problems.len()
+    ))
+}
+
+struct Lint {
+    problem: String,
+    remediation: &'static str,
+}
+
+pub fn check(config: &Config) -> Result<()> {
+    let lints = lints(config);
+
+    if lints.is_empty() {
+        return Ok(());
+    }
+
+    println!("🩺 Configuration warnings");
+    for Lint {
+        problem,
+        remediation,
+    } in &lints
+    {
+        println!("⚠️ {}", problem);
+        println!("   ↳ {}", remediation);
+    }
+    println!();
+
+    if config.strict {
+        Err(anyhow!(
+            "refusing to proceed with {} configuration warning(s) in strict mode",
+            lints.len()
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+fn lints(config: &Config) -> Vec<Lint> {
+    let mut lints = vec![];
+
+    if local::in_place_dir(config).is_none() {
+        lints.extend(releases_inside_active_release(config));
+        lints.extend(license_inside_releases(config));
+        lints.extend(shared_config_inside_releases(config));
+    }
+    lints.extend(tiny_tempdir(config));
+    lints.extend(stream_extract_ignored(config));
+    lints.extend(unlabeled_releases(config));
+    lints.extend(doubtful_target_tuple());
+
+    lints
+}
+
+fn releases_inside_active_release(config: &Config) -> Option<Lint> {
+    let Config {
+        symlink_path,
+        releases_path,
+        ..
+    } = config;
+
+    let active_release = symlink_path.canonicalize().ok()?;
+    let releases_path = releases_path.canonicalize().ok()?;
+
+    releases_path.starts_with(&active_release).then(|| Lint {
+        problem: format!(
+            "releases directory {} is inside the active release {}",
+            releases_path.to_string_lossy(),
+            active_release.to_string_lossy()
+        ),
+        remediation: "move the releases directory outside of any release, e.g. next to the symlink",
+    })
+}
+
+fn shared_config_inside_releases(config: &Config) -> Option<Lint> {
+    let Config {
+        releases_path,
+        shared_config_dir,
+        ..
+    } = config;
+
+    let shared_config_dir = shared_config_dir.as_ref()?.canonicalize().ok()?;
+    let releases_path = releases_path.canonicalize().ok()?;
+
+    shared_config_dir.starts_with(&releases_path).then(|| Lint {
+        problem: format!(
+            "shared config directory {} is inside the releases directory",
+            shared_config_dir.to_string_lossy()
+        ),
+        remediation: "keep the shared config directory outside of releases directory, e.g. in /etc/teamspeak",
+    })
+}
+
+fn license_inside_releases(config: &Config) -> Option<Lint> {
+    let Config {
+        releases_path,
+        license_path,
+        ..
+    } = config;
+
+    let license_path = license_path.as_ref()?.canonicalize().ok()?;
+    let releases_path = releases_path.canonicalize().ok()?;
+
+    license_path.starts_with(&releases_path).then(|| Lint {
+        problem: format!(
+            "license file {} is stored inside the releases directory",
+            license_path.to_string_lossy()
+        ),
+        remediation: "keep licensekey.dat outside of releases directory so it survives removing old releases",
+    })
+}
+
+fn stream_extract_ignored(config: &Config) -> Option<Lint> {
+    (config.stream_extract && !crate::remote::streams_extraction(config)).then(|| Lint {
+        problem: String::from(
+            "--stream-extract is ignored for zip archives and downloads over several connections",
+        ),
+        remediation: "drop --stream-extract, or --download-connections for tarball targets",
+    })
+}
+
+fn doubtful_target_tuple() -> Option<Lint> {
+    target::deduction_doubt().map(|doubt| Lint {
+        problem: format!("detected target tuple may be wrong: {}", doubt),
+        remediation:
+            "pass --target-tuple explicitly (linux_amd64 for glibc, linux_alpine for musl)",
+    })
+}
+
+fn unlabeled_releases(config: &Config) -> Option<Lint> {
+    let Config {
+        selinux_relabel,
+        selinux_context,
+        ..
+    } = config;
+
+    (!selinux_relabel && selinux_context.is_none() && selinux::enforcing()).then(|| Lint {
+        problem: String::from("SELinux is enforcing, but new releases are not relabeled"),
+        remediation: "pass --selinux-relabel (or --selinux-context) so the service may execute the new release",
+    })
+}
+
+#[cfg(target_os = "linux")]
+fn tiny_tempdir(config: &Config) -> Option<Lint> {
+    const TINY_TEMPDIR_BYTES: u64 = 256 * 1024 * 1024;
+
+    let tempdir = local::work_dir(config).ok()?.canonicalize().ok()?;
+    let mount_point = tmpfs_mount_point(&tempdir)?;
+    let stat = nix::sys::statvfs::statvfs(&mount_point).ok()?;
+    #[allow(clippy::unnecessary_cast)]
+    let available = stat.blocks_available() as u64 * stat.fragment_size() as u64;
+
+    (available < TINY_TEMPDIR_BYTES).then(|| Lint {
+        problem: format!(
+            "temporary directory {} is on tmpfs with only {} MiB available",
+            tempdir.to_string_lossy(),
+            available / 1024 / 1024
+        ),
+        remediation:
+            "point --work-dir to a disk-backed directory with enough space for download and extraction",
+    })
+}
+
+#[cfg(not(target_os = "linux"))]
+fn tiny_tempdir(_config: &Config) -> Option<Lint> {
+    None
+}
+
+#[cfg(target_os = "linux")]
+fn tmpfs_mount_point(path: &std::path::Path) -> Option<std::path::PathBuf> {
+    use std::path::PathBuf;
+
+    let mounts = std::fs::read_to_string("/proc/mounts").ok()?;
+
+    mounts
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let mount_point = PathBuf::from(fields.nth(1)?);
+            let fs_type = fields.next()?;
+            Some((mount_point, fs_type == "tmpfs"))
+        })
+        .filter(|(mount_point, _)| path.starts_with(mount_point))
+        .max_by_key(|(mount_point, _)| mount_point.as_os_str().len())
+        .and_then(|(mount_point, is_tmpfs)| is_tmpfs.then_some(mount_point))
+}
@@ -0,0 +1,141 @@
+use crate::{
+    cli::{self, Config, ListCommand, VersionsCommand},
+    local, manifest, remote, state,
+};
+use anyhow::Result;
+use semver::Version;
+use serde::Serialize;
+
+const DATE_FORMAT: &str = "%Y-%m-%d %H:%M";
+
+#[derive(Serialize)]
+struct PublishedVersion {
+    version: String,
+    /// RFC 3339, `None` when the mirror doesn't tell.
+    published_at: Option<String>,
+}
+
+pub async fn run(config: &Config, command: &ListCommand) -> Result<()> {
+    cli::print_header();
+
+    let installed = installed(config).await?;
+    let active_version = local::installed_version(config).await.ok().flatten();
+    let markers = |version: &Version| {
+        let mut markers = vec![];
+        // Every locally listed release is installed, so only remote versions are marked.
+        if command.remote && installed.iter().any(|(installed, _)| installed == version) {
+            markers.push("installed");
+        }
+        if active_version.as_ref() == Some(version) {
+            markers.push("active");
+        }
+        if markers.is_empty() {
+            String::new()
+        } else {
+            format!("  ({})", markers.join(", "))
+        }
+    };
+
+    if command.remote {
+        let http = remote::client(config)?;
+        let releases = remote::published_releases(config, &http).await?;
+
+        println!("🌐 Published versions on {}", config.mirror_url);
+        for (version, published_at) in releases.iter().rev() {
+            let published_at = published_at.map_or_else(
+                || String::from("unknown date"),
+                |published_at| published_at.format(DATE_FORMAT).to_string(),
+            );
+            println!("   {:<16} {}{}", version, published_at, markers(version));
+        }
+        return Ok(());
+    }
+
+    if installed.is_empty() {
+        println!("📦 No releases are installed");
+        return Ok(());
+    }
+    println!("📦 Installed releases");
+    for (version, installed_at) in &installed {
+        let installed_at = installed_at.map_or_else(
+            || String::from("install date unknown"),
+            |installed_at| format!("installed {}", state::format_timestamp(installed_at)),
+        );
+        println!("   {:<16} {}{}", version, installed_at, markers(version));
+    }
+
+    Ok(())
+}
+
+/// Prints versions published on the mirror for scripts, newest first: a version and its release date per line, or JSON.
+pub async fn versions(config: &Config, command: &VersionsCommand) -> Result<()> {
+    let VersionsCommand { limit, json } = command;
+
+    let http = remote::client(config)?;
+    let releases = remote::published_releases(config, &http).await?;
+    let versions = releases
+        .into_iter()
+        .rev()
+        .take(limit.unwrap_or(usize::MAX))
+        .map(|(version, published_at)| PublishedVersion {
+            version: version.to_string(),
+            published_at: published_at.map(|published_at| {
+                published_at.to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+            }),
+        })
+        .collect::<Vec<_>>();
+
+    if *json {
+        println!("{}", serde_json::to_string_pretty(&versions)?);
+        return Ok(());
+    }
+    for PublishedVersion {
+        version,
+        published_at,
+    } in versions
+    {
+        match published_at {
+            Some(published_at) => println!("{}\t{}", version, published_at),
+            None => println!("{}", version),
+        }
+    }
+
+    Ok(())
+}
+
+/// Installed releases with their install times from the manifest, newest first.
+pub async fn installed(config: &Config) -> Result<Vec<(Version, Option<u64>)>> {
+    let versions = match local::in_place_dir(config) {
+        Some(_) => local::installed_version(config)
+            .await?
+            .into_iter()
+            .collect(),
+        None => {
+            let mut versions = vec![];
+            let mut read_dir = match tokio::fs::read_dir(&config.releases_path).await {
+                Ok(read_dir) => read_dir,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+                Err(e) => return Err(e.into()),
+            };
+            while let Some(entry) = read_dir.next_entry().await? {
+                let version = entry
+                    .file_name()
+                    .to_str()
+                    .and_then(|name| local::release_version(config, name));
+                if let (Some(version), true) = (version, entry.file_type().await?.is_dir()) {
+                    versions.push(version);
+                }
+            }
+            versions.sort_by(|a, b| b.cmp(a));
+            versions
+        }
+    };
+
+    let mut installed = vec![];
+    for version in versions {
+        let manifest = manifest::read(&local::release_path(config, &version)?).await?;
+        installed.push((version, manifest.map(|manifest| manifest.installed_at)));
+    }
+
+    Ok(installed)
+}
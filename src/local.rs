@@ -1,28 +1,182 @@
-use crate::{cli::Config, extractor};
+use crate::{
+    cli::Config,
+    events::{self, Event},
+    extractor,
+    journal::Journal,
+    layout::ReleaseLayout,
+    manifest, privileged,
+};
 use anyhow::Result;
-use futures::stream::FuturesUnordered;
 use semver::Version;
-use std::{io::Error, path::PathBuf, sync::Arc};
+use std::{
+    fmt::Display,
+    io::Error,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::Arc,
+};
+use thiserror::Error;
 
-pub async fn installed_version(config: &Config) -> Result<Version> {
-    let Config { symlink_path, .. } = config;
+const STAGING_DIR_NAME: &str = ".ts-updater-staging";
+/// Entries of the target directory replaced by an in-place update, kept until the new ones are all in place.
+const PREVIOUS_DIR_NAME: &str = ".ts-updater-previous";
+/// Default work directory inside the releases directory, or the target directory of in-place installs.
+const WORK_DIR_NAME: &str = ".work";
+/// Start of the names of everything the updater creates in the work directory, which `clean` may remove.
+/// Other files can share a `--work-dir` like `/var/tmp`.
+pub const WORK_ENTRY_PREFIX: &str = ".ts-updater-";
+
+/// How releases are laid out on disk.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum InstallMode {
+    /// Every version gets its own directory inside releases directory, the symlink points to the active one.
+    Releases,
+    /// Files of the latest version are moved over a single target directory.
+    InPlace,
+}
+
+#[derive(Debug, Error)]
+pub enum InstallModeError {
+    #[error("install mode not recognized: {0} (expected `releases` or `in-place`)")]
+    NotRecognized(String),
+}
+
+impl FromStr for InstallMode {
+    type Err = InstallModeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "releases" => Ok(Self::Releases),
+            "in-place" => Ok(Self::InPlace),
+            _ => Err(InstallModeError::NotRecognized(s.to_owned())),
+        }
+    }
+}
+
+impl Display for InstallMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Releases => "releases",
+            Self::InPlace => "in-place",
+        })
+    }
+}
+
+/// Target directory when installing in place, `None` for the releases layout.
+pub fn in_place_dir(config: &Config) -> Option<&Path> {
+    match config.install_mode {
+        InstallMode::InPlace => config.target_dir.as_deref(),
+        InstallMode::Releases => None,
+    }
+}
+
+/// Directory for downloads and extraction, either `--work-dir` or `.work` inside the releases directory,
+/// which is created when missing. It sits on the filesystem of the releases, so moving files in is a rename.
+pub fn work_dir(config: &Config) -> Result<PathBuf> {
+    if let Some(work_dir) = &config.work_dir {
+        return Ok(work_dir.clone());
+    }
+
+    let work_dir = in_place_dir(config)
+        .unwrap_or(&config.releases_path)
+        .join(WORK_DIR_NAME);
+    std::fs::create_dir_all(&work_dir).map_err(|e| {
+        anyhow::anyhow!(
+            "failed to create work directory {}: {} - pass --work-dir",
+            work_dir.to_string_lossy(),
+            e
+        )
+    })?;
+
+    Ok(work_dir)
+}
+
+/// Directory in the work directory keeping archives for later runs, e.g. the one `--ssh` sends to every host.
+pub fn archive_cache_dir(config: &Config) -> Result<PathBuf> {
+    Ok(work_dir(config)?.join(format!("{}archives", WORK_ENTRY_PREFIX)))
+}
+
+/// Returns `None` when no version is installed, which happens only for dangling symlinks under `--repair`.
+pub async fn installed_version(config: &Config) -> Result<Option<Version>> {
+    let Config {
+        symlink_path,
+        repair,
+        ..
+    } = config;
     use anyhow::anyhow;
+    use std::io::ErrorKind;
     use tokio::fs;
 
-    let real_path = fs::canonicalize(&symlink_path).await?;
-    if real_path.is_dir() {
-        let version_path = real_path.file_name().and_then(|name| name.to_str());
+    if let Some(assume_version) = &config.assume_version {
+        return Ok(Some(assume_version.clone()));
+    }
+    if let Some(target_dir) = in_place_dir(config) {
+        return manifest::read(target_dir)
+            .await?
+            .map(|manifest| Version::parse(&manifest.version))
+            .transpose()
+            .map_err(Into::into);
+    }
 
-        match version_path {
-            Some(version_path) => Ok(Version::parse(version_path).map(|version| {
+    let real_path = match canonical_link_target(config).await {
+        Err(e) if e.kind() == ErrorKind::NotFound => {
+            let missing_target = match fs::read_link(&symlink_path).await {
+                Ok(missing_target) => missing_target,
+                Err(_) => return Err(e.into()),
+            };
+
+            if *repair {
                 println!(
-                    "🏠 Determined locally installed TeamSpeak version: {}",
-                    version
+                    "🩹 Symlink points to missing release {} - treating as no version installed.",
+                    missing_target.to_string_lossy()
                 );
-                version
-            })?),
+                return Ok(None);
+            }
+
+            return Err(anyhow!(
+                "symlink {} points to release directory {} which does not exist - restore it or run with --repair to install the latest version",
+                symlink_path.to_string_lossy(),
+                missing_target.to_string_lossy()
+            ));
+        }
+        real_path => real_path?,
+    };
+
+    if real_path.is_dir() {
+        // Directory names can be changed by hand, so the manifest is preferred when present.
+        if let Some(manifest) = manifest::read(&real_path).await? {
+            return Ok(Some(Version::parse(&manifest.version)?));
+        }
+
+        // Versions are ASCII, so names which aren't valid UTF-8 are still read, through the lenient parsing.
+        let version_path = real_path.file_name().map(|name| name.to_string_lossy());
+
+        match version_path.as_deref() {
+            Some(version_path) => match release_version(config, version_path) {
+                Some(version) if !has_suffix(config, &version) => Ok(Some(version)),
+                _ => match (config.strict_release_names, lenient_version(config, version_path)) {
+                    (false, Some(version)) => {
+                        println!(
+                            "⚠️ Release directory {} is not named exactly after a version - assuming {}",
+                            version_path, version
+                        );
+                        Ok(Some(version))
+                    }
+                    (true, Some(version)) => Err(anyhow!(
+                        "release directory {} is not named exactly after version {} - rename it or run without --strict-release-names",
+                        version_path,
+                        version
+                    )),
+                    _ => Err(anyhow!(
+                        "no version in the name of release directory {} - it doesn't match --release-dir-name {}",
+                        version_path,
+                        config.release_dir_name
+                    )),
+                },
+            },
             None => Err(anyhow!(
-                "Directory the symlink is pointing to is not valid UTF-8"
+                "symlink points to {} which is not a release directory",
+                real_path.to_string_lossy()
             )),
         }
     } else {
@@ -30,20 +184,179 @@ pub async fn installed_version(config: &Config) -> Result<Version> {
     }
 }
 
+/// Path the symlink points to on this host, before resolving further symlinks.
+///
+/// Under `--jail`, absolute targets outside of the jail's root were made inside the jail and are resolved within it.
+pub async fn link_target(config: &Config) -> std::io::Result<PathBuf> {
+    let Config {
+        symlink_path, jail, ..
+    } = config;
+
+    let target = tokio::fs::read_link(symlink_path).await?;
+    if let Some(jail) = jail {
+        if let Some(target) = jailed_path(jail, &target).await? {
+            return Ok(target);
+        }
+    }
+
+    Ok(match symlink_path.parent() {
+        Some(parent) => parent.join(target),
+        None => target,
+    })
+}
+
+/// Symlinks saved as `<symlink>.<timestamp>` by earlier swaps, newest first.
+pub async fn symlink_backups(config: &Config) -> Result<Vec<PathBuf>> {
+    let Config { symlink_path, .. } = config;
+    let (Some(parent), Some(file_name)) = (symlink_path.parent(), symlink_path.file_name()) else {
+        return Ok(vec![]);
+    };
+    let prefix = format!("{}.", file_name.to_string_lossy());
+    let parent = if parent.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        parent
+    };
+
+    let mut backups = vec![];
+    let mut read_dir = tokio::fs::read_dir(parent).await?;
+    while let Some(entry) = read_dir.next_entry().await? {
+        let timestamp = entry
+            .file_name()
+            .to_string_lossy()
+            .strip_prefix(&prefix)
+            .and_then(|timestamp| timestamp.parse::<u64>().ok());
+
+        if let (Some(timestamp), true) = (timestamp, entry.file_type().await?.is_symlink()) {
+            backups.push((timestamp, entry.path()));
+        }
+    }
+    backups.sort_by(|(a, _), (b, _)| b.cmp(a));
+
+    Ok(backups.into_iter().map(|(_, backup)| backup).collect())
+}
+
+pub async fn canonical_link_target(config: &Config) -> std::io::Result<PathBuf> {
+    match &config.jail {
+        Some(_)
+            if tokio::fs::symlink_metadata(&config.symlink_path)
+                .await?
+                .is_symlink() =>
+        {
+            tokio::fs::canonicalize(link_target(config).await?).await
+        }
+        _ => tokio::fs::canonicalize(&config.symlink_path).await,
+    }
+}
+
+/// Host path of an absolute path inside the jail, `None` for relative paths and ones already on the host side.
+async fn jailed_path(jail: &str, path: &Path) -> std::io::Result<Option<PathBuf>> {
+    if !path.is_absolute() {
+        return Ok(None);
+    }
+
+    let root = jail_root(jail).await?;
+    if path.starts_with(&root) {
+        return Ok(None);
+    }
+
+    Ok(Some(root.join(path.strip_prefix("/").unwrap_or(path))))
+}
+
+/// Root directory of the jail, as reported by `jls`.
+async fn jail_root(jail: &str) -> std::io::Result<PathBuf> {
+    let output = tokio::process::Command::new("jls")
+        .args(["-j", jail, "path"])
+        .output()
+        .await?;
+    if !output.status.success() {
+        return Err(std::io::Error::other(format!(
+            "jls could not find jail {}: {}",
+            jail,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+
+    Ok(PathBuf::from(
+        String::from_utf8_lossy(&output.stdout).trim(),
+    ))
+}
+
+/// What to point the symlink at for the release. Under `--jail` it's relative to the symlink,
+/// so it resolves the same on the host and inside the jail.
+pub fn link_source(config: &Config, release_path: &Path) -> Result<PathBuf> {
+    let Config {
+        symlink_path, jail, ..
+    } = config;
+
+    let parent = match symlink_path.parent() {
+        Some(parent) if jail.is_some() => parent,
+        _ => return Ok(release_path.to_owned()),
+    };
+    let parent = if parent.as_os_str().is_empty() {
+        std::env::current_dir()?
+    } else {
+        parent.canonicalize()?
+    };
+    let release_path = release_path.canonicalize()?;
+
+    let common = release_path
+        .components()
+        .zip(parent.components())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    Ok(parent
+        .components()
+        .skip(common)
+        .map(|_| std::path::Component::ParentDir)
+        .chain(release_path.components().skip(common))
+        .collect())
+}
+
 pub async fn extract_archive(
     server_archive: tokio::fs::File,
     config: &Config,
     published_version: &semver::Version,
+    journal: &Journal,
 ) -> Result<()> {
-    let tempdir = Arc::new(tempfile::tempdir()?);
+    let tempdir = extraction_dir(config, journal)?;
     let archive_type = config.target_tuple.archive_type();
 
     print!("📦 Extracting the archive... ");
-    extractor::extract(&archive_type, tempdir.clone(), server_archive).await?;
+    extractor::extract(
+        &archive_type,
+        tempdir.clone(),
+        server_archive,
+        config.extract_threads as usize,
+    )
+    .await?;
     println!("✅");
 
+    move_extracted(tempdir, config, published_version, journal).await
+}
+
+/// Temporary directory the archive gets unpacked into, removed by the journal on rollback.
+pub fn extraction_dir(config: &Config, journal: &Journal) -> Result<Arc<tempfile::TempDir>> {
+    let tempdir = Arc::new(
+        tempfile::Builder::new()
+            .prefix(WORK_ENTRY_PREFIX)
+            .tempdir_in(work_dir(config)?)?,
+    );
+    journal.staging_dir(tempdir.path());
+
+    Ok(tempdir)
+}
+
+/// Moves the files unpacked into `tempdir` into the new release.
+pub async fn move_extracted(
+    tempdir: Arc<tempfile::TempDir>,
+    config: &Config,
+    published_version: &semver::Version,
+    journal: &Journal,
+) -> Result<()> {
     print!("📦 Moving files to new release...");
-    move_extracted_files(tempdir, config, published_version).await?;
+    move_extracted_files(tempdir, config, published_version, journal).await?;
     println!("✅");
 
     Ok(())
@@ -53,14 +366,16 @@ async fn move_extracted_files(
     tempdir: Arc<tempfile::TempDir>,
     config: &Config,
     published_version: &semver::Version,
+    journal: &Journal,
 ) -> Result<()> {
     use futures::prelude::*;
     use tokio::fs;
 
-    let Config { releases_path, .. } = config;
-
-    let mut version_path = PathBuf::from(releases_path).canonicalize()?;
-    version_path.push(published_version.to_string());
+    let version_path = staged_release_path(config, published_version)?;
+    // Leftovers of an interrupted in-place update must not end up in the target directory.
+    if in_place_dir(config).is_some() && fs::symlink_metadata(&version_path).await.is_ok() {
+        fs::remove_dir_all(&version_path).await?;
+    }
 
     let mut read_dir = fs::read_dir(tempdir.path()).await?;
 
@@ -81,9 +396,10 @@ async fn move_extracted_files(
         }
     };
 
-    fs::create_dir(&version_path)
-        .await
-        .or_else(ignore_exists_error)?;
+    match fs::create_dir(&version_path).await {
+        Ok(()) => journal.created_release(&version_path),
+        Err(e) => ignore_exists_error(e)?,
+    }
 
     let version_path = version_path.canonicalize()?;
 
@@ -109,65 +425,380 @@ async fn move_extracted_files(
             }
         }
 
-        read_queue.extend(append_dirs.into_iter());
+        read_queue.extend(append_dirs);
+    }
+
+    let count = file_paths.len();
+    let copies = file_paths
+        .into_iter()
+        .map(|path| {
+            let relative = path
+                .strip_prefix(tempdir.path())
+                .map(|relative| relative.iter().skip(1).collect::<PathBuf>())?;
+            Ok((path, relative))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    // Every copy holds two file descriptors open, so only a limited number of them runs at once.
+    // All files are attempted, so a failure reports every file that didn't make it.
+    let mut failures = stream::iter(copies)
+        .map(|(from, relative)| {
+            let to = version_path.join(&relative);
+            async move {
+                let copied = events::spawn_blocking(move |cancellation| {
+                    cancellation.check().map_err(Error::other)?;
+                    std::fs::copy(from, to)
+                })
+                .await
+                .unwrap_or_else(|e| Err(Error::other(e)));
+                copied.err().map(|e| (relative, e))
+            }
+        })
+        .buffer_unordered(config.copy_concurrency.max(1) as usize)
+        .filter_map(future::ready)
+        .collect::<Vec<_>>()
+        .await;
+
+    if !failures.is_empty() {
+        failures.sort_by(|(a, _), (b, _)| a.cmp(b));
+        return Err(CopyFailed { count, failures }.into());
+    }
+    events::emit(config, Event::FilesCopied { count });
+
+    Ok(())
+}
+
+/// Files of a release which couldn't be copied into it.
+#[derive(Debug, Error)]
+#[error("{}", self.report())]
+pub struct CopyFailed {
+    count: usize,
+    failures: Vec<(PathBuf, std::io::Error)>,
+}
+
+/// Failed files listed in the error, the rest is only counted.
+const REPORTED_COPY_FAILURES: usize = 10;
+
+impl CopyFailed {
+    fn report(&self) -> String {
+        let mut report = format!(
+            "failed to copy {} of {} files into the release:",
+            self.failures.len(),
+            self.count
+        );
+        for (path, e) in self.failures.iter().take(REPORTED_COPY_FAILURES) {
+            report.push_str(&format!("\n   {}: {}", path.to_string_lossy(), e));
+        }
+        if self.failures.len() > REPORTED_COPY_FAILURES {
+            report.push_str(&format!(
+                "\n   … and {} more",
+                self.failures.len() - REPORTED_COPY_FAILURES
+            ));
+        }
+
+        let errors = || self.failures.iter().map(|(_, e)| e);
+        // EMFILE, the same number on Linux, macOS and the BSDs.
+        if errors().any(|e| e.raw_os_error() == Some(24)) {
+            report.push_str("\n   ↳ too many open files - lower --copy-concurrency or raise the limit with `ulimit -n`");
+        }
+        if errors().any(|e| e.kind() == std::io::ErrorKind::StorageFull) {
+            report.push_str("\n   ↳ the disk is full - free space in the releases directory");
+        }
+        if errors().any(|e| e.kind() == std::io::ErrorKind::PermissionDenied) {
+            report.push_str(
+                "\n   ↳ permission denied - run the updater as a user who may write to the release",
+            );
+        }
+
+        report
+    }
+}
+
+/// Directory in-place updates stage files in before moving them over the target directory.
+pub fn in_place_staging_dir(config: &Config) -> Option<PathBuf> {
+    in_place_dir(config).map(|target_dir| target_dir.join(STAGING_DIR_NAME))
+}
+
+/// Directory the release gets extracted to before it is activated.
+pub fn staged_release_path(config: &Config, version: &semver::Version) -> Result<PathBuf> {
+    match in_place_dir(config) {
+        // Files are staged next to the ones they replace, so moving them in is a rename.
+        Some(target_dir) => Ok(target_dir.canonicalize()?.join(STAGING_DIR_NAME)),
+        None => release_path(config, version),
+    }
+}
+
+pub fn release_path(config: &Config, version: &semver::Version) -> Result<PathBuf> {
+    if let Some(target_dir) = in_place_dir(config) {
+        return Ok(target_dir.canonicalize()?);
+    }
+
+    let releases_path = config.releases_path.canonicalize()?;
+    let layout = &config.release_dir_name;
+    if *layout != ReleaseLayout::default() {
+        // Installed releases keep the name they got, whatever day it is or however the layout changed since.
+        for entry in std::fs::read_dir(&releases_path)? {
+            let entry = entry?;
+            let is_release = entry.file_type()?.is_dir()
+                && entry
+                    .file_name()
+                    .to_str()
+                    .and_then(|name| release_version(config, name))
+                    .as_ref()
+                    == Some(version);
+            if is_release {
+                return Ok(entry.path());
+            }
+        }
+    }
+
+    Ok(releases_path.join(layout.dir_name(version)))
+}
+
+/// Version of the release directory with the given name, following `--release-dir-name`.
+/// Directories named by the bare version, the default, are recognized as well, as releases installed
+/// before the layout changed keep their names.
+pub fn release_version(config: &Config, dir_name: &str) -> Option<Version> {
+    config
+        .release_dir_name
+        .parse(dir_name)
+        .or_else(|| Version::parse(dir_name).ok())
+}
+
+/// Pre-releases and build metadata of products never publishing them come from a suffix added by hand, like `-hotfix1`.
+fn has_suffix(config: &Config, version: &Version) -> bool {
+    !config.product.has_prereleases() && (!version.pre.is_empty() || !version.build.is_empty())
+}
+
+/// First `major.minor.patch` within a release directory name which was changed by hand, like `3.13.7-hotfix1`
+/// or `3.13.7 (copy)`. Pre-release tags (`6.0.0-beta2`) are kept for products publishing them.
+fn lenient_version(config: &Config, dir_name: &str) -> Option<Version> {
+    let is_version_char = |c: char| c.is_ascii_digit() || c == '.';
+
+    dir_name
+        .char_indices()
+        .filter(|(index, c)| c.is_ascii_digit() && !dir_name[..*index].ends_with(is_version_char))
+        .find_map(|(index, _)| {
+            let rest = &dir_name[index..];
+            let core_end = rest.find(|c| !is_version_char(c)).unwrap_or(rest.len());
+            let mut numbers = rest[..core_end]
+                .split('.')
+                .map(|number| number.parse().ok());
+            let mut version = Version::new(numbers.next()??, numbers.next()??, numbers.next()??);
+
+            if config.product.has_prereleases() && numbers.next().is_none() {
+                if let Some(pre) = rest[core_end..].strip_prefix('-') {
+                    let pre_end = pre
+                        .find(|c: char| !c.is_ascii_alphanumeric() && c != '.')
+                        .unwrap_or(pre.len());
+                    version.pre = semver::Prerelease::new(&pre[..pre_end]).unwrap_or_default();
+                }
+            }
+
+            Some(version)
+        })
+}
+
+/// Total size of regular files within the directory, not following symlinks.
+pub fn dir_size(path: &Path) -> Result<u64> {
+    let mut size = 0;
+    let mut queue = vec![path.to_owned()];
+
+    while let Some(dir) = queue.pop() {
+        for entry in std::fs::read_dir(&dir)? {
+            let entry = entry?;
+            let metadata = entry.metadata()?;
+
+            if metadata.is_dir() {
+                queue.push(entry.path());
+            } else if metadata.is_file() {
+                size += metadata.len();
+            }
+        }
+    }
+
+    Ok(size)
+}
+
+/// Makes the freshly extracted release the active one.
+pub async fn activate_release(
+    config: &Config,
+    published_version: &semver::Version,
+    journal: &Journal,
+) -> Result<()> {
+    match in_place_dir(config) {
+        Some(target_dir) => replace_in_place(target_dir).await,
+        None => swap_link(config, published_version, journal).await,
     }
+}
 
-    let mut file_copying = Box::pin(
-        file_paths
-            .into_iter()
-            .map(|path| {
-                let relative = path
-                    .strip_prefix(tempdir.path())
-                    .map(|relative| relative.iter().skip(1).collect::<PathBuf>());
+/// Swaps the staged release with the one in the target directory by renaming whole top-level entries. The replaced
+/// entries are moved aside first and put back when a rename fails, so the target never ends up with a mix of both
+/// releases. Files the old manifest listed but the new release doesn't ship are gone with them, while files the
+/// releases don't ship, like the database or logs, are kept.
+async fn replace_in_place(target_dir: &Path) -> Result<()> {
+    let target_dir = target_dir.canonicalize()?;
+    let shipped = manifest::read(&target_dir)
+        .await?
+        .and_then(|manifest| manifest.files)
+        .map(|files| files.into_keys().collect())
+        .unwrap_or_default();
 
-                relative
-                    .map(|relative| version_path.join(relative))
-                    .map(|to| fs::copy(path.clone(), to))
-            })
-            .collect::<Result<Vec<_>, _>>()?
-            .into_iter()
-            .collect::<FuturesUnordered<_>>(),
+    println!(
+        "🧠 Replacing files in {}",
+        target_dir.as_os_str().to_string_lossy()
     );
 
-    future::try_join_all(file_copying.as_mut().iter_pin_mut()).await?;
+    // Once started, the renames run to the end, so a cancelled update can't stop halfway through them.
+    tokio::task::spawn_blocking(move || swap_in_place(&target_dir, &shipped)).await?
+}
+
+fn swap_in_place(target_dir: &Path, shipped: &std::collections::BTreeSet<String>) -> Result<()> {
+    use std::{collections::BTreeSet, ffi::OsString, fs};
+
+    let staging_path = target_dir.join(STAGING_DIR_NAME);
+    let previous_path = target_dir.join(PREVIOUS_DIR_NAME);
+
+    let staged = fs::read_dir(&staging_path)?
+        .map(|entry| Ok(entry?.file_name()))
+        .collect::<std::io::Result<BTreeSet<OsString>>>()?;
+    // Top-level entries of the old release are replaced too, even when the new one doesn't ship them anymore.
+    let replaced = staged
+        .iter()
+        .cloned()
+        .chain(
+            shipped
+                .iter()
+                .filter_map(|relative| relative.split('/').next().map(OsString::from)),
+        )
+        .filter(|name| target_dir.join(name).symlink_metadata().is_ok())
+        .collect::<BTreeSet<_>>();
+
+    for name in &replaced {
+        keep_unshipped(target_dir, &staging_path, Path::new(name), shipped)?;
+    }
+
+    if previous_path.symlink_metadata().is_ok() {
+        fs::remove_dir_all(&previous_path)?;
+    }
+    fs::create_dir(&previous_path)?;
+
+    let mut moved_aside = vec![];
+    let mut moved_in = vec![];
+    let swapped = (|| -> std::io::Result<()> {
+        for name in &replaced {
+            fs::rename(target_dir.join(name), previous_path.join(name))?;
+            moved_aside.push(name);
+        }
+        for name in &staged {
+            fs::rename(staging_path.join(name), target_dir.join(name))?;
+            moved_in.push(name);
+        }
+        Ok(())
+    })();
+
+    if let Err(e) = swapped {
+        for name in moved_in {
+            fs::rename(target_dir.join(name), staging_path.join(name))?;
+        }
+        for name in moved_aside {
+            fs::rename(previous_path.join(name), target_dir.join(name))?;
+        }
+        fs::remove_dir(&previous_path)?;
+        return Err(e.into());
+    }
+
+    fs::remove_dir_all(&staging_path)?;
+    fs::remove_dir_all(&previous_path)?;
 
     Ok(())
 }
 
-pub async fn swap_link(config: &Config, published_version: &semver::Version) -> Result<()> {
-    let Config {
-        releases_path,
-        symlink_path,
-        ..
-    } = config;
+/// Links files of `relative` in the target directory which the old release didn't ship and the new one doesn't
+/// replace into the staged tree, so they survive the swap.
+fn keep_unshipped(
+    target_dir: &Path,
+    staging_path: &Path,
+    relative: &Path,
+    shipped: &std::collections::BTreeSet<String>,
+) -> Result<()> {
+    use std::fs;
 
-    use tokio::fs;
+    let (current, staged) = (target_dir.join(relative), staging_path.join(relative));
+    let metadata = fs::symlink_metadata(&current)?;
+    if metadata.is_dir() {
+        for entry in fs::read_dir(&current)? {
+            keep_unshipped(
+                target_dir,
+                staging_path,
+                &relative.join(entry?.file_name()),
+                shipped,
+            )?;
+        }
+        return Ok(());
+    }
+
+    let is_shipped = shipped.contains(relative.to_string_lossy().as_ref());
+    if is_shipped || fs::symlink_metadata(&staged).is_ok() {
+        return Ok(());
+    }
+    if let Some(parent) = staged.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    if metadata.is_symlink() {
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(fs::read_link(&current)?, &staged)?;
+        #[cfg(not(unix))]
+        fs::copy(&current, &staged)?;
+    } else if fs::hard_link(&current, &staged).is_err() {
+        fs::copy(&current, &staged)?;
+    }
+
+    Ok(())
+}
 
-    let symlink_file_name = symlink_path
-        .file_name()
-        .expect("symlink should expose filename")
-        .to_str()
-        .expect("symlink filename is valid utf-8");
+async fn swap_link(
+    config: &Config,
+    published_version: &semver::Version,
+    journal: &Journal,
+) -> Result<()> {
+    let Config { symlink_path, .. } = config;
 
-    let mut new_path = symlink_path.clone();
-    let unix_timestamp = std::time::SystemTime::now()
+    let symlink_file_name = symlink_path.file_name().ok_or_else(|| {
+        anyhow::anyhow!(
+            "symlink path {} has no file name",
+            symlink_path.to_string_lossy()
+        )
+    })?;
+    let mut unix_timestamp = std::time::SystemTime::now()
         .duration_since(std::time::UNIX_EPOCH)
-        .unwrap()
+        .unwrap_or_default()
         .as_secs();
-    new_path.set_file_name(format!("{}.{}", symlink_file_name, unix_timestamp));
-
-    let new_symlink_src = {
-        let mut path = releases_path.clone().canonicalize()?;
-        path.push(published_version.to_string());
-        path
+    // Switching twice within a second must not overwrite the older backup.
+    let new_path = loop {
+        let mut backup_file_name = symlink_file_name.to_os_string();
+        backup_file_name.push(format!(".{}", unix_timestamp));
+        let new_path = symlink_path.with_file_name(backup_file_name);
+        if std::fs::symlink_metadata(&new_path).is_err() {
+            break new_path;
+        }
+        unix_timestamp += 1;
     };
 
+    let new_symlink_src = release_path(config, published_version)?;
+
     println!(
         "🧠 Swapping symbolic links (old saved to {})",
         &new_path.as_os_str().to_string_lossy()
     );
-    fs::rename(symlink_path, new_path).await?;
-    fs::symlink_dir(new_symlink_src, symlink_path).await?;
+    privileged::rename(config, symlink_path, &new_path).await?;
+    journal.moved_symlink(symlink_path, &new_path);
+    privileged::symlink_dir(
+        config,
+        &link_source(config, &new_symlink_src)?,
+        symlink_path,
+    )
+    .await?;
 
     Ok(())
 }
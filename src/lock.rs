@@ -0,0 +1,71 @@
+use crate::{cli::Config, local};
+use anyhow::Result;
+use std::path::PathBuf;
+
+const LOCK_FILE_NAME: &str = ".ts-updater.lock";
+
+/// Exclusive lock of the installation, released when dropped.
+pub struct Lock {
+    #[cfg(unix)]
+    _file: nix::fcntl::Flock<std::fs::File>,
+}
+
+/// Lock file next to the files it protects, so every updater sharing them sees it.
+fn lock_path(config: &Config) -> PathBuf {
+    match local::in_place_dir(config) {
+        Some(target_dir) => target_dir.join(LOCK_FILE_NAME),
+        None => config.releases_path.join(LOCK_FILE_NAME),
+    }
+}
+
+/// Takes the lock of the installation, waiting while another updater (e.g. another replica sharing the volume) holds it.
+#[cfg(unix)]
+pub async fn acquire(config: &Config) -> Result<Lock> {
+    use nix::{
+        errno::Errno,
+        fcntl::{Flock, FlockArg},
+    };
+    use std::io::Write;
+
+    let path = lock_path(config);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let file = std::fs::OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&path)?;
+
+    let mut file = match Flock::lock(file, FlockArg::LockExclusiveNonblock) {
+        Ok(file) => file,
+        Err((file, Errno::EWOULDBLOCK)) => {
+            let holder = std::fs::read_to_string(&path).unwrap_or_default();
+            println!(
+                "⏳ Another updater holds {} ({}) - waiting for it to finish",
+                path.to_string_lossy(),
+                holder.trim()
+            );
+            tokio::task::spawn_blocking(move || Flock::lock(file, FlockArg::LockExclusive))
+                .await?
+                .map_err(|(_, e)| e)?
+        }
+        Err((_, e)) => return Err(e.into()),
+    };
+
+    // Tells whoever waits for the lock who holds it.
+    let hostname = nix::unistd::gethostname()
+        .map(|hostname| hostname.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    file.set_len(0)?;
+    write!(file, "pid {} on {}", std::process::id(), hostname)?;
+
+    Ok(Lock { _file: file })
+}
+
+/// Locking isn't available on this platform, so concurrent updaters aren't kept apart.
+#[cfg(not(unix))]
+pub async fn acquire(config: &Config) -> Result<Lock> {
+    let _ = lock_path(config);
+    Ok(Lock {})
+}
@@ -1,54 +1,125 @@
-use std::process::exit;
-
 use anyhow::Result;
 
+mod activate;
+mod adopt;
+mod api;
+mod artifact;
+mod changes;
+mod check;
+mod checksum;
+mod clean;
 mod cli;
+mod compare;
+mod completions;
+mod daemon;
+mod database;
+mod delta;
+mod doctor;
+mod download;
+mod event_log;
+mod events;
 mod extractor;
+mod facts;
+mod failure;
+mod fetch;
+mod fleet;
+mod hardlink;
+mod hold;
+mod hooks;
+mod image;
+mod install_from_file;
+mod inventory;
+mod journal;
+mod launchd;
+mod layout;
+mod libraries;
+mod license;
+mod lint;
+mod list;
 mod local;
+mod lock;
+mod manifest;
+mod metrics;
+mod mirror_sync;
+mod otlp;
+mod overlay;
+mod ownership;
+mod permissions;
+mod preserve;
+mod privileged;
 mod remote;
+mod schedule;
+mod scheduled_task;
+mod selinux;
+mod server_process;
+mod server_query;
+mod service;
+mod settings;
+mod shared_config;
+mod sidecar;
+mod snapshot;
+mod state;
+mod state_db;
+mod status;
+mod system_log;
 mod target;
-
-async fn determine_teamspeak_versions(
-    config: &cli::Config,
-    http: &reqwest::Client,
-) -> Result<(semver::Version, semver::Version)> {
-    println!("⏳ Checking for updates...");
-    let (last_installed_version, last_published_version) = tokio::try_join!(
-        local::installed_version(config),
-        remote::latest_version(config, http)
-    )?;
-    println!();
-
-    Ok((last_installed_version, last_published_version))
-}
+mod throttle;
+mod uninstall;
+mod update;
+mod verify;
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let config: cli::Config = argh::from_env();
-    let http = reqwest::Client::new();
-
-    cli::print_header();
-    config.print_summary();
-
-    let (installed_version, published_version) =
-        determine_teamspeak_versions(&config, &http).await?;
-
-    if installed_version < published_version {
-        println!(
-            "⚠️ Update available - local {}, remote {}",
-            installed_version, published_version
-        );
-
-        let server_archive = remote::download_release(&config, &http, &published_version).await?;
-        local::extract_archive(server_archive, &config, &published_version).await?;
-        local::swap_link(&config, &published_version).await?;
+    let mut config = settings::parse(&settings::args()?);
+    if config.facts {
+        return facts::run(&config).await;
+    }
 
-        println!();
-        println!("✅ TeamSpeak successfully updated! ✅");
-    } else {
-        println!("✅ You are running the newest version of TeamSpeak.");
-        exit(1);
+    // The daemon command stays in the config, which tells its runs about the reminder interval.
+    if let Some(cli::Command::Daemon(_)) = &config.command {
+        return daemon::run(&mut config).await;
     }
 
-    Ok(())
+    match config.command.take() {
+        Some(cli::Command::Completions(command)) => {
+            completions::print(command.shell);
+            Ok(())
+        }
+        Some(cli::Command::Adopt(command)) => adopt::run(&mut config, &command).await,
+        Some(cli::Command::Install(command)) => activate::install(&mut config, &command).await,
+        Some(cli::Command::Activate(command)) => activate::activate(&mut config, &command).await,
+        Some(cli::Command::Deactivate(_)) => activate::deactivate(&mut config).await,
+        Some(cli::Command::Commit(command)) => activate::commit(&mut config, &command).await,
+        Some(cli::Command::Check(_)) => check::run(&config).await,
+        Some(cli::Command::Doctor(command)) => doctor::run(&config, &command).await,
+        Some(cli::Command::BuildDelta(command)) => delta::build(&command).await,
+        Some(cli::Command::Download(command)) => download::run(&config, &command).await,
+        Some(cli::Command::Fetch(command)) => fetch::run(&mut config, &command).await,
+        Some(cli::Command::InstallFromFile(command)) => {
+            install_from_file::run(&mut config, &command).await
+        }
+        Some(cli::Command::MirrorSync(command)) => mirror_sync::run(&config, &command).await,
+        Some(cli::Command::Snapshot(command)) => snapshot::run(&mut config, &command).await,
+        Some(cli::Command::Restore(command)) => snapshot::restore(&mut config, &command).await,
+        Some(cli::Command::RestoreSnapshot(command)) => {
+            snapshot::restore_servers(&mut config, &command).await
+        }
+        Some(cli::Command::RegisterTask(command)) => scheduled_task::register(&command).await,
+        Some(cli::Command::Status(command)) => status::run(&config, &command).await,
+        Some(cli::Command::Report(command)) => state_db::report(&config, &command).await,
+        Some(cli::Command::Inventory(command)) => inventory::run(&config, &command).await,
+        Some(cli::Command::Hold(command)) => hold::hold(&config, &command).await,
+        Some(cli::Command::Unhold(_)) => hold::unhold(&config).await,
+        Some(cli::Command::List(command)) => list::run(&config, &command).await,
+        Some(cli::Command::Versions(command)) => list::versions(&config, &command).await,
+        Some(cli::Command::Compare(command)) => compare::run(&config, &command).await,
+        Some(cli::Command::GenerateLaunchd(command)) => launchd::generate(&config, &command).await,
+        Some(cli::Command::BuildImage(command)) => image::build(&config, &command).await,
+        Some(cli::Command::Sidecar(command)) => sidecar::run(&mut config, &command).await,
+        Some(cli::Command::Uninstall(command)) => uninstall::run(&mut config, &command).await,
+        Some(cli::Command::Clean(command)) => clean::run(&mut config, &command).await,
+        Some(cli::Command::Verify(command)) => verify::run(&mut config, &command).await,
+        Some(cli::Command::Daemon(_)) => unreachable!("the daemon runs above"),
+        Some(cli::Command::Update(_)) | None => update::run(&mut config).await,
+    }
 }
@@ -0,0 +1,122 @@
+use crate::{cli::Config, local, remote::Download, state};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, path::Path};
+
+pub const MANIFEST_FILE_NAME: &str = ".ts-updater.json";
+
+/// Metadata of an installed release, stored inside the release directory.
+#[derive(Serialize, Deserialize)]
+pub struct Manifest {
+    pub version: String,
+    pub installed_at: u64,
+    pub source_url: String,
+    pub sha256: String,
+    /// SHA256 of every file in the release, keyed by path relative to the release directory.
+    #[serde(default)]
+    pub files: Option<BTreeMap<String, String>>,
+    /// Size of every file in the release in bytes, keyed like `files`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sizes: Option<BTreeMap<String, u64>>,
+}
+
+impl Manifest {
+    pub fn size(&self, path: &str) -> Option<u64> {
+        self.sizes.as_ref()?.get(path).copied()
+    }
+}
+
+/// Writes the manifest into the extracted, not yet activated release.
+pub async fn write(
+    config: &Config,
+    published_version: &semver::Version,
+    download: &Download,
+) -> Result<()> {
+    let staged_path = local::staged_release_path(config, published_version)?;
+    let (files, sizes) = {
+        let staged_path = staged_path.clone();
+        tokio::task::spawn_blocking(move || -> Result<_> {
+            let files = file_digests(&staged_path)?;
+            let sizes = files
+                .iter()
+                .map(|(relative, _)| {
+                    Ok((
+                        relative.clone(),
+                        std::fs::metadata(staged_path.join(relative))?.len(),
+                    ))
+                })
+                .collect::<Result<BTreeMap<_, _>>>()?;
+            Ok((files, sizes))
+        })
+        .await??
+    };
+
+    let manifest = Manifest {
+        version: published_version.to_string(),
+        installed_at: state::unix_timestamp(),
+        source_url: download.url.to_string(),
+        sha256: download.sha256.clone(),
+        files: Some(files.into_iter().collect()),
+        sizes: Some(sizes),
+    };
+
+    tokio::fs::write(
+        staged_path.join(MANIFEST_FILE_NAME),
+        serde_json::to_vec_pretty(&manifest)?,
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Reads the manifest of a release, returning `None` for releases installed without one.
+pub async fn read(release_path: &Path) -> Result<Option<Manifest>> {
+    use std::io::ErrorKind;
+
+    match tokio::fs::read(release_path.join(MANIFEST_FILE_NAME)).await {
+        Ok(contents) => Ok(Some(serde_json::from_slice(&contents)?)),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Hashes all files within the directory except the manifest, sorted by their relative paths.
+pub fn file_digests(root: &Path) -> Result<Vec<(String, String)>> {
+    use sha2::{Digest, Sha256};
+    use std::fs;
+
+    let mut digests = vec![];
+    let mut queue = vec![root.to_owned()];
+
+    while let Some(dir) = queue.pop() {
+        for entry in fs::read_dir(&dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            let file_type = entry.file_type()?;
+            let relative = path
+                .strip_prefix(root)?
+                .components()
+                .map(|component| component.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+
+            if relative == MANIFEST_FILE_NAME {
+                continue;
+            } else if file_type.is_dir() {
+                queue.push(path);
+            } else if file_type.is_symlink() {
+                let target = fs::read_link(&path)?;
+                let digest = Sha256::digest(target.as_os_str().to_string_lossy().as_bytes());
+                digests.push((relative, hex::encode(digest)));
+            } else {
+                let mut file = fs::File::open(&path)?;
+                let mut hasher = Sha256::new();
+                std::io::copy(&mut file, &mut hasher)?;
+                digests.push((relative, hex::encode(hasher.finalize())));
+            }
+        }
+    }
+
+    digests.sort();
+    Ok(digests)
+}
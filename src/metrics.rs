@@ -0,0 +1,130 @@
+use crate::{changes::Changes, cli::Config, state};
+use anyhow::Result;
+use semver::Version;
+use std::{fmt::Write, time::Duration};
+
+/// Outcome of a single run, exported as Prometheus metrics.
+#[derive(Default)]
+pub struct Report {
+    pub installed_version: Option<Version>,
+    pub published_version: Option<Version>,
+    /// Version installed before the update, set once it succeeded.
+    pub previous_version: Option<Version>,
+    pub download_bytes: u64,
+    pub duration: Duration,
+    pub success: bool,
+    /// Files changed by the update, when both releases have a manifest.
+    pub changes: Option<Changes>,
+}
+
+pub async fn write_textfile(config: &Config, report: &Report) -> Result<()> {
+    use tokio::fs;
+
+    let metrics_textfile = match &config.metrics_textfile {
+        Some(metrics_textfile) => metrics_textfile,
+        None => return Ok(()),
+    };
+
+    // node_exporter may read the file at any time, so it gets replaced atomically.
+    let mut temp_path = metrics_textfile.clone().into_os_string();
+    temp_path.push(".tmp");
+
+    fs::write(&temp_path, render(report)?).await?;
+    fs::rename(&temp_path, metrics_textfile).await?;
+
+    Ok(())
+}
+
+fn render(report: &Report) -> Result<String> {
+    let Report {
+        installed_version,
+        published_version,
+        download_bytes,
+        duration,
+        success,
+        ..
+    } = report;
+
+    let mut out = String::new();
+
+    if let Some(version) = installed_version {
+        metric(
+            &mut out,
+            "teamspeak_updater_installed_version_info",
+            "Locally installed TeamSpeak version.",
+            "gauge",
+            &format!("{{version=\"{}\"}}", version),
+            1,
+        )?;
+    }
+
+    if let Some(version) = published_version {
+        metric(
+            &mut out,
+            "teamspeak_updater_published_version_info",
+            "Latest TeamSpeak version published on the mirror.",
+            "gauge",
+            &format!("{{version=\"{}\"}}", version),
+            1,
+        )?;
+    }
+
+    if let (Some(installed), Some(published)) = (installed_version, published_version) {
+        metric(
+            &mut out,
+            "teamspeak_updater_update_available",
+            "Whether a newer TeamSpeak version is available.",
+            "gauge",
+            "",
+            u8::from(installed < published),
+        )?;
+    }
+
+    metric(
+        &mut out,
+        "teamspeak_updater_last_run_timestamp_seconds",
+        "Unix timestamp of the last updater run.",
+        "gauge",
+        "",
+        state::unix_timestamp(),
+    )?;
+    metric(
+        &mut out,
+        "teamspeak_updater_last_run_success",
+        "Whether the last updater run finished without errors.",
+        "gauge",
+        "",
+        u8::from(*success),
+    )?;
+    metric(
+        &mut out,
+        "teamspeak_updater_download_bytes",
+        "Size of the archive downloaded during the last run.",
+        "gauge",
+        "",
+        download_bytes,
+    )?;
+    metric(
+        &mut out,
+        "teamspeak_updater_duration_seconds",
+        "Duration of the last updater run.",
+        "gauge",
+        "",
+        duration.as_secs_f64(),
+    )?;
+
+    Ok(out)
+}
+
+fn metric(
+    out: &mut String,
+    name: &str,
+    help: &str,
+    kind: &str,
+    labels: &str,
+    value: impl std::fmt::Display,
+) -> std::fmt::Result {
+    writeln!(out, "# HELP {} {}", name, help)?;
+    writeln!(out, "# TYPE {} {}", name, kind)?;
+    writeln!(out, "{}{} {}", name, labels, value)
+}
@@ -0,0 +1,144 @@
+use crate::{
+    cli::{self, Config, MirrorSyncCommand},
+    download,
+    remote::{self, Product},
+    target::Tuple,
+};
+use anyhow::{anyhow, Result};
+use semver::Version;
+use std::path::Path;
+
+/// Replicates the upstream mirror for the selected target tuples into a directory other instances can use as `--mirror-url`.
+pub async fn run(config: &Config, command: &MirrorSyncCommand) -> Result<()> {
+    let MirrorSyncCommand {
+        dest,
+        last,
+        target_tuple,
+        prune,
+    } = command;
+
+    cli::print_header();
+
+    if config.product != Product::Ts3 {
+        return Err(anyhow!(
+            "mirror-sync replicates the TeamSpeak 3 mirror only - TeamSpeak 6 releases are published on GitHub"
+        ));
+    }
+    if !dest.is_dir() {
+        return Err(anyhow!(
+            "destination directory {} does not exist - create it first",
+            dest.to_string_lossy()
+        ));
+    }
+
+    let tuples = if target_tuple.is_empty() {
+        Tuple::ALL.to_vec()
+    } else {
+        download::selected_tuples(target_tuple)
+    };
+
+    let http = remote::client(config)?;
+    let mut versions = remote::published_versions(config, &http).await?;
+    if let Some(last) = last {
+        versions.drain(..versions.len().saturating_sub(*last));
+    }
+    println!(
+        "🪞 Syncing {} version(s) for {} target tuple(s) into {}",
+        versions.len(),
+        tuples.len(),
+        dest.to_string_lossy()
+    );
+
+    let mut failures = 0;
+    for version in &versions {
+        for tuple in &tuples {
+            let archive_path = remote::archive_path(config, *tuple, version);
+            let archive_dir = match Path::new(&archive_path).parent() {
+                Some(parent) => dest.join(parent),
+                None => dest.clone(),
+            };
+
+            match download::download_into(config, &http, *tuple, version, &archive_dir, None).await
+            {
+                Ok(()) => {}
+                Err(e) if remote::is_not_found(&e) => {
+                    println!("⏭️ {} is not published for {}", version, tuple)
+                }
+                Err(e) => {
+                    failures += 1;
+                    println!("❌ {} for {}: {:#}", version, tuple, e);
+                }
+            }
+        }
+    }
+
+    if *prune {
+        prune_versions(dest, &versions).await?;
+    }
+    write_listing(dest).await?;
+
+    if failures > 0 {
+        return Err(anyhow!("{} archive(s) failed to sync", failures));
+    }
+
+    println!("✅ Mirror {} is in sync", dest.to_string_lossy());
+    Ok(())
+}
+
+/// Version directories in the destination, oldest first.
+async fn version_dirs(dest: &Path) -> Result<Vec<Version>> {
+    let mut versions = vec![];
+    let mut read_dir = tokio::fs::read_dir(dest).await?;
+
+    while let Some(entry) = read_dir.next_entry().await? {
+        let version = entry
+            .file_name()
+            .to_str()
+            .and_then(|name| name.parse().ok());
+        if let (Some(version), true) = (version, entry.file_type().await?.is_dir()) {
+            versions.push(version);
+        }
+    }
+    versions.sort();
+
+    Ok(versions)
+}
+
+/// Removes version directories which are no longer part of the synced versions.
+async fn prune_versions(dest: &Path, synced: &[Version]) -> Result<()> {
+    for version in version_dirs(dest).await? {
+        if !synced.contains(&version) {
+            println!("🧹 Removing {} from the mirror", version);
+            tokio::fs::remove_dir_all(dest.join(version.to_string())).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Writes `index.html` in the format of the upstream listing, so no directory listing needs to be enabled on the web server.
+async fn write_listing(dest: &Path) -> Result<()> {
+    let mut listing = String::from("<html><body><pre><a href=\"../\">../</a>\n");
+    for version in version_dirs(dest).await? {
+        // Versions without any archive for the synced tuples would only lead instances to missing archives.
+        let version_dir = dest.join(version.to_string());
+        if tokio::fs::read_dir(&version_dir)
+            .await?
+            .next_entry()
+            .await?
+            .is_none()
+        {
+            continue;
+        }
+
+        listing.push_str(&format!("<a href=\"{0}/\">{0}</a>\n", version));
+    }
+    listing.push_str("</pre></body></html>\n");
+
+    let path = dest.join("index.html");
+    let temp_path = dest.join("index.html.tmp");
+    tokio::fs::write(&temp_path, listing).await?;
+    tokio::fs::rename(&temp_path, &path).await?;
+
+    Ok(())
+}
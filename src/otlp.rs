@@ -0,0 +1,230 @@
+use crate::{cli::Config, events::PhaseTiming, failure, metrics::Report, remote};
+use anyhow::Result;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const SERVICE_NAME: &str = "teamspeak-updater";
+const TRACES_PATH: &str = "/v1/traces";
+
+/// Span kind `SPAN_KIND_INTERNAL` and status codes of the OTLP protocol.
+const SPAN_KIND_INTERNAL: u8 = 1;
+const STATUS_CODE_OK: u8 = 1;
+const STATUS_CODE_ERROR: u8 = 2;
+
+/// URL traces get posted to, from `--otlp-endpoint` or the standard OpenTelemetry environment variables.
+/// Base endpoints get the `/v1/traces` path appended, as OpenTelemetry SDKs do.
+pub fn traces_endpoint(config: &Config) -> Option<String> {
+    let with_path = |endpoint: String| {
+        let endpoint = endpoint.trim_end_matches('/');
+        if endpoint.ends_with(TRACES_PATH) {
+            endpoint.to_owned()
+        } else {
+            format!("{}{}", endpoint, TRACES_PATH)
+        }
+    };
+
+    match &config.otlp_endpoint {
+        Some(endpoint) => Some(with_path(endpoint.clone())),
+        None => env("OTEL_EXPORTER_OTLP_TRACES_ENDPOINT")
+            .or_else(|| env("OTEL_EXPORTER_OTLP_ENDPOINT").map(with_path)),
+    }
+}
+
+fn env(name: &str) -> Option<String> {
+    std::env::var(name).ok().filter(|value| !value.is_empty())
+}
+
+/// Exports the run as a trace: a root span named after the run with a child span for every phase.
+/// Problems with the collector are only printed, they never fail the run.
+pub async fn export(
+    config: &Config,
+    http: &reqwest::Client,
+    name: &str,
+    report: &Report,
+    phases: &[PhaseTiming],
+    outcome: Result<&str, &anyhow::Error>,
+) {
+    let endpoint = match traces_endpoint(config) {
+        Some(endpoint) => endpoint,
+        None => return,
+    };
+
+    if let Err(e) = send(config, http, &endpoint, name, report, phases, outcome).await {
+        println!("⚠️ Failed to export the trace to {}: {:#}", endpoint, e);
+    }
+}
+
+async fn send(
+    config: &Config,
+    http: &reqwest::Client,
+    endpoint: &str,
+    name: &str,
+    report: &Report,
+    phases: &[PhaseTiming],
+    outcome: Result<&str, &anyhow::Error>,
+) -> Result<()> {
+    remote::ensure_online(config, "exporting the trace")?;
+
+    let ended_at = SystemTime::now();
+    let started_at = ended_at - report.duration;
+    let trace_id = random_id(16);
+    let root_span_id = random_id(8);
+
+    let mut attributes = vec![
+        attribute("teamspeak.product", config.product.to_string()),
+        attribute("teamspeak.target", config.target_tuple.to_string()),
+        attribute("teamspeak.outcome", outcome.unwrap_or("failed")),
+        int_attribute("teamspeak.download_bytes", report.download_bytes),
+    ];
+    if let Some(version) = &report.installed_version {
+        attributes.push(attribute(
+            "teamspeak.installed_version",
+            version.to_string(),
+        ));
+    }
+    if let Some(version) = &report.published_version {
+        attributes.push(attribute(
+            "teamspeak.published_version",
+            version.to_string(),
+        ));
+    }
+    if let Some(changes) = &report.changes {
+        attributes.push(int_attribute(
+            "teamspeak.files_changed",
+            (changes.added.len() + changes.removed.len() + changes.changed.len()) as u64,
+        ));
+    }
+
+    let mut spans = vec![span(
+        &trace_id,
+        &root_span_id,
+        None,
+        name,
+        started_at,
+        ended_at,
+        attributes,
+        outcome.err().map(|e| format!("{:#}", e)),
+    )];
+    for phase in phases {
+        spans.push(span(
+            &trace_id,
+            &random_id(8),
+            Some(&root_span_id),
+            &phase.phase,
+            phase.started_at,
+            phase.started_at + Duration::from_millis(phase.duration_ms),
+            vec![],
+            phase.error.clone(),
+        ));
+    }
+
+    let mut resource_attributes = vec![
+        attribute(
+            "service.name",
+            env("OTEL_SERVICE_NAME").unwrap_or_else(|| SERVICE_NAME.to_owned()),
+        ),
+        attribute("service.version", env!("CARGO_PKG_VERSION")),
+    ];
+    if let Some(host) = failure::hostname() {
+        resource_attributes.push(attribute("host.name", host));
+    }
+
+    let request = json!({
+        "resourceSpans": [{
+            "resource": { "attributes": resource_attributes },
+            "scopeSpans": [{
+                "scope": { "name": SERVICE_NAME, "version": env!("CARGO_PKG_VERSION") },
+                "spans": spans,
+            }],
+        }],
+    });
+
+    let mut post = http.post(endpoint).json(&request);
+    for (name, value) in headers() {
+        post = post.header(name, value);
+    }
+    post.send().await?.error_for_status()?;
+
+    println!("🔭 Trace {} exported", trace_id);
+
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+fn span(
+    trace_id: &str,
+    span_id: &str,
+    parent_span_id: Option<&str>,
+    name: &str,
+    started_at: SystemTime,
+    ended_at: SystemTime,
+    attributes: Vec<Value>,
+    error: Option<String>,
+) -> Value {
+    let status = match error {
+        Some(message) => json!({ "code": STATUS_CODE_ERROR, "message": message }),
+        None => json!({ "code": STATUS_CODE_OK }),
+    };
+
+    json!({
+        "traceId": trace_id,
+        "spanId": span_id,
+        "parentSpanId": parent_span_id.unwrap_or_default(),
+        "name": name,
+        "kind": SPAN_KIND_INTERNAL,
+        "startTimeUnixNano": unix_nanos(started_at),
+        "endTimeUnixNano": unix_nanos(ended_at),
+        "attributes": attributes,
+        "status": status,
+    })
+}
+
+fn attribute(key: &str, value: impl Into<String>) -> Value {
+    json!({ "key": key, "value": { "stringValue": value.into() } })
+}
+
+/// 64-bit integers are written as strings in OTLP/JSON.
+fn int_attribute(key: &str, value: u64) -> Value {
+    json!({ "key": key, "value": { "intValue": value.to_string() } })
+}
+
+fn unix_nanos(time: SystemTime) -> String {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos()
+        .to_string()
+}
+
+/// Headers of `OTEL_EXPORTER_OTLP_HEADERS`, written as `key1=value1,key2=value2`, e.g. for authentication.
+fn headers() -> Vec<(String, String)> {
+    let headers = env("OTEL_EXPORTER_OTLP_TRACES_HEADERS")
+        .or_else(|| env("OTEL_EXPORTER_OTLP_HEADERS"))
+        .unwrap_or_default();
+
+    headers
+        .split(',')
+        .filter_map(|header| header.split_once('='))
+        .map(|(name, value)| {
+            (
+                name.trim().to_owned(),
+                percent_encoding::percent_decode_str(value.trim())
+                    .decode_utf8_lossy()
+                    .into_owned(),
+            )
+        })
+        .collect()
+}
+
+/// Hex-encoded ID of `bytes` length, unique enough for traces without pulling in a random number generator.
+pub fn random_id(bytes: usize) -> String {
+    use std::sync::atomic::{AtomicU64, Ordering};
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+    let mut hasher = Sha256::new();
+    hasher.update(unix_nanos(SystemTime::now()));
+    hasher.update(std::process::id().to_le_bytes());
+    hasher.update(COUNTER.fetch_add(1, Ordering::Relaxed).to_le_bytes());
+
+    hex::encode(&hasher.finalize()[..bytes])
+}
@@ -0,0 +1,110 @@
+use crate::{
+    cli::Config,
+    events::{self, Cancellation},
+    local,
+};
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+
+/// Overlay files with this suffix get their placeholders substituted and are installed without it.
+const TEMPLATE_SUFFIX: &str = ".tmpl";
+
+/// Copies files from the overlay directory into the extracted, not yet activated release.
+pub async fn apply(config: &Config, published_version: &semver::Version) -> Result<()> {
+    let Config { overlay_dir, .. } = config;
+    let overlay_dir = match overlay_dir {
+        Some(overlay_dir) => overlay_dir.canonicalize()?,
+        None => return Ok(()),
+    };
+
+    let staged_path = local::staged_release_path(config, published_version)?;
+    let variables = variables(config, published_version)?;
+    let copied = {
+        let overlay_dir = overlay_dir.clone();
+        events::spawn_blocking(move |cancellation| {
+            copy_overlay(&overlay_dir, &staged_path, &variables, &cancellation)
+        })
+        .await??
+    };
+
+    println!(
+        "🧩 Applied {} overlay file(s) from {}",
+        copied,
+        overlay_dir.to_string_lossy()
+    );
+
+    Ok(())
+}
+
+fn variables(
+    config: &Config,
+    published_version: &semver::Version,
+) -> Result<Vec<(String, String)>> {
+    let path = |path: &Path| path.to_string_lossy().into_owned();
+
+    Ok(vec![
+        ("{version}".into(), published_version.to_string()),
+        ("{target}".into(), config.target_tuple.to_string()),
+        (
+            "{release_dir}".into(),
+            path(&local::release_path(config, published_version)?),
+        ),
+        ("{symlink_path}".into(), path(&config.symlink_path)),
+        ("{releases_path}".into(), path(&config.releases_path)),
+    ])
+}
+
+fn copy_overlay(
+    overlay_dir: &Path,
+    release_dir: &Path,
+    variables: &[(String, String)],
+    cancellation: &Cancellation,
+) -> Result<u64> {
+    use std::fs;
+
+    let mut copied = 0;
+    let mut queue = vec![PathBuf::new()];
+
+    while let Some(relative_dir) = queue.pop() {
+        fs::create_dir_all(release_dir.join(&relative_dir))?;
+
+        for entry in fs::read_dir(overlay_dir.join(&relative_dir))? {
+            cancellation.check()?;
+            let entry = entry?;
+            let relative = relative_dir.join(entry.file_name());
+            let source = entry.path();
+
+            if source.is_dir() {
+                queue.push(relative);
+                continue;
+            }
+
+            let file_name = entry.file_name().to_string_lossy().into_owned();
+            match file_name.strip_suffix(TEMPLATE_SUFFIX) {
+                Some(installed_name) => {
+                    let destination = release_dir.join(&relative_dir).join(installed_name);
+                    let contents = fs::read_to_string(&source).with_context(|| {
+                        format!("overlay template {} is not valid UTF-8", source.display())
+                    })?;
+                    let contents = variables
+                        .iter()
+                        .fold(contents, |contents, (placeholder, value)| {
+                            contents.replace(placeholder, value)
+                        });
+
+                    let _ = fs::remove_file(&destination);
+                    fs::write(&destination, contents)?;
+                    fs::set_permissions(&destination, fs::metadata(&source)?.permissions())?;
+                }
+                None => {
+                    let destination = release_dir.join(&relative);
+                    let _ = fs::remove_file(&destination);
+                    fs::copy(&source, &destination)?;
+                }
+            }
+            copied += 1;
+        }
+    }
+
+    Ok(copied)
+}
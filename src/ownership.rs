@@ -0,0 +1,146 @@
+use crate::{
+    cli::Config,
+    events::{self, Cancellation},
+    local, privileged,
+};
+use anyhow::{anyhow, Result};
+
+#[cfg(unix)]
+pub async fn apply(config: &Config, published_version: &semver::Version) -> Result<()> {
+    let owner = match &config.owner {
+        Some(owner) => resolve_owner(owner)?,
+        None => return Ok(()),
+    };
+
+    let release_path = local::release_path(config, published_version)?;
+
+    print!(
+        "👤 Changing owner of {} to {}... ",
+        release_path.to_string_lossy(),
+        owner.spec
+    );
+    let (uid, gid) = (owner.uid, owner.gid);
+    let owner_ids = format!("{}:{}", uid, gid);
+    if !privileged::chown_tree(config, &owner_ids, &release_path).await? {
+        let release_path = release_path.clone();
+        events::spawn_blocking(move |cancellation| {
+            chown_tree(&release_path, uid, gid, &cancellation)
+        })
+        .await??;
+    }
+    println!("✅");
+
+    if !accessible_by(&owner, &release_path) {
+        println!(
+            "⚠️ {} is not readable by {} - check permissions of its parent directories.",
+            release_path.to_string_lossy(),
+            owner.spec
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub async fn apply(config: &Config, _published_version: &semver::Version) -> Result<()> {
+    match &config.owner {
+        Some(_) => Err(anyhow!("--owner is supported only on Unix systems")),
+        None => Ok(()),
+    }
+}
+
+#[cfg(unix)]
+struct Owner {
+    spec: String,
+    uid: u32,
+    gid: u32,
+    groups: Vec<u32>,
+}
+
+#[cfg(unix)]
+fn resolve_owner(spec: &str) -> Result<Owner> {
+    use nix::unistd::{Group, User};
+
+    let (user_name, group_name) = match spec.split_once(':') {
+        Some((user_name, group_name)) => (user_name, Some(group_name)),
+        None => (spec, None),
+    };
+
+    let user = User::from_name(user_name)?.ok_or_else(|| anyhow!("unknown user {}", user_name))?;
+    let gid = match group_name {
+        Some(group_name) => {
+            Group::from_name(group_name)?
+                .ok_or_else(|| anyhow!("unknown group {}", group_name))?
+                .gid
+        }
+        None => user.gid,
+    };
+
+    let user_name = std::ffi::CString::new(user.name.as_str())?;
+    let groups = nix::unistd::getgrouplist(&user_name, user.gid)
+        .map(|groups| groups.into_iter().map(|group| group.as_raw()).collect())
+        .unwrap_or_else(|_| vec![user.gid.as_raw()]);
+
+    Ok(Owner {
+        spec: spec.to_owned(),
+        uid: user.uid.as_raw(),
+        gid: gid.as_raw(),
+        groups,
+    })
+}
+
+#[cfg(unix)]
+fn chown_tree(
+    root: &std::path::Path,
+    uid: u32,
+    gid: u32,
+    cancellation: &Cancellation,
+) -> Result<()> {
+    use std::{fs, os::unix::fs::lchown};
+
+    let mut queue = vec![root.to_owned()];
+    lchown(root, Some(uid), Some(gid))?;
+
+    while let Some(dir) = queue.pop() {
+        for entry in fs::read_dir(dir)? {
+            cancellation.check()?;
+            let entry = entry?;
+            lchown(entry.path(), Some(uid), Some(gid))?;
+
+            if entry.file_type()?.is_dir() {
+                queue.push(entry.path());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks whether all ancestors are searchable and the directory itself is readable by the owner.
+#[cfg(unix)]
+fn accessible_by(owner: &Owner, path: &std::path::Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+
+    const READ: u32 = 0o4;
+    const EXECUTE: u32 = 0o1;
+
+    let permits = |path: &std::path::Path, wanted: u32| {
+        std::fs::metadata(path)
+            .map(|metadata| {
+                let mode = metadata.mode();
+                let granted = if owner.uid == 0 {
+                    0o7
+                } else if metadata.uid() == owner.uid {
+                    mode >> 6
+                } else if owner.groups.contains(&metadata.gid()) {
+                    mode >> 3
+                } else {
+                    mode
+                };
+                granted & wanted == wanted
+            })
+            .unwrap_or(false)
+    };
+
+    path.ancestors().all(|ancestor| permits(ancestor, EXECUTE)) && permits(path, READ)
+}
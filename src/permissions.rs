@@ -0,0 +1,120 @@
+use crate::{
+    cli::Config,
+    events::{self, Cancellation},
+    local,
+};
+use anyhow::{anyhow, Result};
+use std::{fmt::Display, str::FromStr};
+use thiserror::Error;
+
+/// Permission bits written in octal, e.g. `755`.
+#[derive(Clone, Copy)]
+pub struct Mode(u32);
+
+#[derive(Debug, Error)]
+pub enum ModeError {
+    #[error("mode not recognized: {0} (expected octal permission bits, e.g. `755`)")]
+    NotRecognized(String),
+}
+
+impl FromStr for Mode {
+    type Err = ModeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        u32::from_str_radix(s, 8)
+            .ok()
+            .filter(|mode| *mode <= 0o7777)
+            .map(Self)
+            .ok_or_else(|| ModeError::NotRecognized(s.to_owned()))
+    }
+}
+
+impl Display for Mode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:04o}", self.0)
+    }
+}
+
+/// Sets modes of every directory and file in the staged release, regardless of the umask of the updater.
+#[cfg(unix)]
+pub async fn apply(config: &Config, published_version: &semver::Version) -> Result<()> {
+    let Config {
+        dir_mode,
+        file_mode,
+        ..
+    } = config;
+
+    if dir_mode.is_none() && file_mode.is_none() {
+        return Ok(());
+    }
+
+    let staged_path = local::staged_release_path(config, published_version)?;
+    let (dir_mode, file_mode) = (*dir_mode, *file_mode);
+
+    print!("🔒 Setting permissions... ");
+    events::spawn_blocking(move |cancellation| {
+        set_modes(&staged_path, dir_mode, file_mode, &cancellation)
+    })
+    .await??;
+    println!("✅");
+
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub async fn apply(config: &Config, _published_version: &semver::Version) -> Result<()> {
+    match (config.dir_mode, config.file_mode) {
+        (None, None) => Ok(()),
+        _ => Err(anyhow!(
+            "--dir-mode and --file-mode are supported only on Unix systems"
+        )),
+    }
+}
+
+#[cfg(unix)]
+fn set_modes(
+    root: &std::path::Path,
+    dir_mode: Option<Mode>,
+    file_mode: Option<Mode>,
+    cancellation: &Cancellation,
+) -> Result<()> {
+    use std::{fs, os::unix::fs::PermissionsExt};
+
+    let set_mode = |path: &std::path::Path, mode: u32| {
+        fs::set_permissions(path, fs::Permissions::from_mode(mode))
+            .map_err(|e| anyhow!("{}: {}", path.to_string_lossy(), e))
+    };
+
+    if let Some(Mode(mode)) = dir_mode {
+        set_mode(root, mode)?;
+    }
+
+    let mut queue = vec![root.to_owned()];
+    while let Some(dir) = queue.pop() {
+        for entry in fs::read_dir(&dir)? {
+            cancellation.check()?;
+            let entry = entry?;
+            let path = entry.path();
+            // Symlinks point to files kept outside of the release, like the license or shared config.
+            let metadata = fs::symlink_metadata(&path)?;
+
+            if metadata.is_dir() {
+                if let Some(Mode(mode)) = dir_mode {
+                    set_mode(&path, mode)?;
+                }
+                queue.push(path);
+            } else if let (true, Some(Mode(mode))) = (metadata.is_file(), file_mode) {
+                // Executables stay executable for everyone allowed to read them.
+                let executable = metadata.permissions().mode() & 0o111 != 0;
+                let mode = if executable {
+                    mode | (mode & 0o444) >> 2
+                } else {
+                    mode
+                };
+                set_mode(&path, mode)?;
+            }
+        }
+    }
+
+    Ok(())
+}
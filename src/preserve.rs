@@ -0,0 +1,141 @@
+use crate::{
+    cli::Config,
+    events::{self, Cancellation},
+    local, verify,
+};
+use anyhow::Result;
+use semver::Version;
+use std::path::{Path, PathBuf};
+
+/// Whether the release-relative path is one of the preserved paths or inside of one.
+pub fn is_preserved(config: &Config, path: &str) -> bool {
+    config.preserve.iter().any(|preserved| {
+        let preserved = preserved.to_string_lossy();
+        let preserved = preserved.trim_matches('/');
+
+        path == preserved
+            || path
+                .strip_prefix(preserved)
+                .is_some_and(|rest| rest.starts_with('/'))
+    })
+}
+
+/// Files of the release which differ from its manifest or were added to it, and are not carried over by `--preserve`.
+pub async fn unpreserved_modifications(config: &Config, version: &Version) -> Result<Vec<String>> {
+    let release_path = local::release_path(config, version)?;
+    let differences = match verify::differences(config, &release_path).await? {
+        Some(differences) => differences,
+        None => return Ok(vec![]),
+    };
+
+    Ok(differences
+        .modified
+        .into_iter()
+        .chain(differences.extra)
+        .filter(|path| !is_preserved(config, path))
+        .collect())
+}
+
+/// Warns about hand-made changes of the installed release which the update is about to leave behind.
+pub async fn warn_unpreserved(config: &Config, installed_version: Option<&Version>) -> Result<()> {
+    let installed_version = match installed_version {
+        Some(installed_version) => installed_version,
+        None => return Ok(()),
+    };
+
+    let modifications = unpreserved_modifications(config, installed_version).await?;
+    if modifications.is_empty() {
+        return Ok(());
+    }
+
+    println!(
+        "⚠️ Release {} was modified locally and these files won't be carried into the new release:",
+        installed_version
+    );
+    for path in &modifications {
+        println!("   {}", path);
+    }
+    println!("   ↳ list them with --preserve, or move configuration into --shared-config-dir");
+
+    Ok(())
+}
+
+/// Copies preserved files and directories of the installed release into the extracted, not yet activated one.
+pub async fn carry_over(
+    config: &Config,
+    installed_version: Option<&Version>,
+    published_version: &Version,
+) -> Result<()> {
+    let installed_version = match installed_version {
+        Some(installed_version) if !config.preserve.is_empty() => installed_version,
+        _ => return Ok(()),
+    };
+
+    let installed_path = local::release_path(config, installed_version)?;
+    let staged_path = local::staged_release_path(config, published_version)?;
+    let preserve = config.preserve.clone();
+
+    let copied = events::spawn_blocking(move |cancellation| -> Result<Vec<PathBuf>> {
+        let mut copied = vec![];
+        for relative in preserve {
+            let source = installed_path.join(&relative);
+            if std::fs::symlink_metadata(&source).is_ok() {
+                copy_tree(&source, &staged_path.join(&relative), &cancellation)?;
+                copied.push(relative);
+            }
+        }
+        Ok(copied)
+    })
+    .await??;
+
+    for relative in copied {
+        println!(
+            "📎 Preserved {} from release {}",
+            relative.to_string_lossy(),
+            installed_version
+        );
+    }
+
+    Ok(())
+}
+
+fn copy_tree(source: &Path, destination: &Path, cancellation: &Cancellation) -> Result<()> {
+    use std::fs;
+
+    cancellation.check()?;
+    let metadata = fs::symlink_metadata(source)?;
+    if let Some(parent) = destination.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    if metadata.is_dir() {
+        fs::create_dir_all(destination)?;
+        for entry in fs::read_dir(source)? {
+            let entry = entry?;
+            copy_tree(
+                &entry.path(),
+                &destination.join(entry.file_name()),
+                cancellation,
+            )?;
+        }
+        return Ok(());
+    }
+
+    match fs::symlink_metadata(destination) {
+        Ok(existing) if existing.is_dir() => fs::remove_dir_all(destination)?,
+        Ok(_) => fs::remove_file(destination)?,
+        Err(_) => {}
+    }
+
+    if metadata.is_symlink() {
+        let target = fs::read_link(source)?;
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(target, destination)?;
+        #[cfg(windows)]
+        std::os::windows::fs::symlink_file(target, destination)?;
+    } else {
+        fs::copy(source, destination)?;
+    }
+
+    Ok(())
+}
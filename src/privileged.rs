@@ -0,0 +1,163 @@
+use crate::cli::Config;
+use anyhow::{anyhow, Result};
+use std::{ffi::OsStr, path::Path};
+
+pub async fn rename(config: &Config, from: &Path, to: &Path) -> Result<()> {
+    match &config.escalate_with {
+        Some(escalate_with) => {
+            run_escalated(
+                escalate_with,
+                "mv",
+                &[OsStr::new("--"), from.as_os_str(), to.as_os_str()],
+            )
+            .await
+        }
+        None => Ok(tokio::fs::rename(from, to).await?),
+    }
+}
+
+pub async fn symlink_dir(config: &Config, src: &Path, dst: &Path) -> Result<()> {
+    match &config.escalate_with {
+        Some(escalate_with) => {
+            run_escalated(
+                escalate_with,
+                "ln",
+                &[
+                    OsStr::new("-s"),
+                    OsStr::new("--"),
+                    src.as_os_str(),
+                    dst.as_os_str(),
+                ],
+            )
+            .await
+        }
+        None => {
+            #[cfg(unix)]
+            tokio::fs::symlink(src, dst).await?;
+            #[cfg(windows)]
+            tokio::fs::symlink_dir(src, dst).await?;
+            Ok(())
+        }
+    }
+}
+
+pub async fn remove_symlink(config: &Config, path: &Path) -> Result<()> {
+    match &config.escalate_with {
+        Some(escalate_with) => {
+            run_escalated(
+                escalate_with,
+                "rm",
+                &[OsStr::new("-f"), OsStr::new("--"), path.as_os_str()],
+            )
+            .await
+        }
+        None => {
+            #[cfg(unix)]
+            tokio::fs::remove_file(path).await?;
+            #[cfg(windows)]
+            tokio::fs::remove_dir(path).await?;
+            Ok(())
+        }
+    }
+}
+
+/// Removes a directory with everything inside, which may belong to the release owner.
+pub async fn remove_tree(config: &Config, path: &Path) -> Result<()> {
+    match &config.escalate_with {
+        Some(escalate_with) => {
+            run_escalated(
+                escalate_with,
+                "rm",
+                &[OsStr::new("-rf"), OsStr::new("--"), path.as_os_str()],
+            )
+            .await
+        }
+        None => Ok(tokio::fs::remove_dir_all(path).await?),
+    }
+}
+
+/// Changes owner of the whole tree via escalation command. Returns `false` if no escalation is configured.
+pub async fn chown_tree(config: &Config, owner: &str, path: &Path) -> Result<bool> {
+    match &config.escalate_with {
+        Some(escalate_with) => {
+            run_escalated(
+                escalate_with,
+                "chown",
+                &[
+                    OsStr::new("-R"),
+                    OsStr::new("-h"),
+                    OsStr::new("--"),
+                    OsStr::new(owner),
+                    path.as_os_str(),
+                ],
+            )
+            .await?;
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
+/// Sets SELinux context of the whole tree, or restores it from the policy when no context is given.
+pub async fn relabel_tree(config: &Config, context: Option<&str>, path: &Path) -> Result<()> {
+    let (program, args) = match context {
+        Some(context) => (
+            "chcon",
+            vec![
+                OsStr::new("-R"),
+                OsStr::new("-h"),
+                OsStr::new("--"),
+                OsStr::new(context),
+                path.as_os_str(),
+            ],
+        ),
+        None => (
+            "restorecon",
+            vec![OsStr::new("-R"), OsStr::new("-F"), path.as_os_str()],
+        ),
+    };
+
+    match &config.escalate_with {
+        Some(escalate_with) => run_escalated(escalate_with, program, &args).await,
+        None => {
+            let status = tokio::process::Command::new(program)
+                .args(&args)
+                .status()
+                .await
+                .map_err(|e| anyhow!("failed to run {}: {}", program, e))?;
+
+            if status.success() {
+                Ok(())
+            } else {
+                Err(anyhow!("{} failed with {}", program, status))
+            }
+        }
+    }
+}
+
+async fn run_escalated(escalate_with: &str, program: &str, args: &[&OsStr]) -> Result<()> {
+    use tokio::process::Command;
+
+    let mut escalation = escalate_with.split_whitespace();
+    let escalation_program = escalation
+        .next()
+        .ok_or_else(|| anyhow!("escalation command is empty"))?;
+
+    let status = Command::new(escalation_program)
+        .args(escalation)
+        .arg(program)
+        .args(args)
+        .status()
+        .await?;
+
+    if status.success() {
+        Ok(())
+    } else {
+        Err(anyhow!(
+            "escalated command `{} {}` failed with {}",
+            escalate_with,
+            program,
+            status
+        ))
+    }
+}
@@ -1,8 +1,474 @@
-use crate::cli::Config;
+use crate::{
+    checksum, cli::Config, events::DownloadProgress, extractor, local, state, target,
+    throttle::Throttle,
+};
 use anyhow::{anyhow, Result};
-use reqwest::Client;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use reqwest::{
+    header::{HeaderMap, HeaderName, HeaderValue},
+    Client, RequestBuilder, Response,
+};
 use scraper::{Html, Selector};
 use semver::Version;
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    str::FromStr,
+    sync::Mutex,
+};
+use thiserror::Error;
+
+mod cache;
+mod ts6;
+
+const DEFAULT_USER_AGENT: &str = concat!("teamspeak-updater/", env!("CARGO_PKG_VERSION"));
+/// Archive bytes buffered between the download and the extraction under `--stream-extract`.
+const PIPELINE_BUFFER_BYTES: usize = 1 << 20;
+/// Smaller than any archive with a server in it, catching empty and cut off responses.
+const MIN_ARCHIVE_BYTES: u64 = 100;
+/// How long to wait on a 429 or 503 answer that doesn't tell with `Retry-After`.
+const DEFAULT_RETRY_AFTER: std::time::Duration = std::time::Duration::from_secs(60);
+pub const DEFAULT_TS3_MIRROR_URL: &str = "https://files.teamspeak-services.com/releases/server/";
+
+/// Listing validators of the last check, so repeated checks in daemon mode can be conditional.
+static LISTING_CACHE: Mutex<Option<CachedListing>> = Mutex::new(None);
+
+#[derive(Clone)]
+struct CachedListing {
+    mirror_url: String,
+    etag: Option<HeaderValue>,
+    last_modified: Option<HeaderValue>,
+    /// Oldest first.
+    versions: Vec<Version>,
+}
+
+/// Server generation managed by the updater. Each one is published in a different way.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Product {
+    Ts3,
+    Ts6,
+}
+
+#[derive(Debug, Error)]
+pub enum ProductError {
+    #[error("product not recognized: {0} (expected `ts3` or `ts6`)")]
+    NotRecognized(String),
+}
+
+impl FromStr for Product {
+    type Err = ProductError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "ts3" => Ok(Self::Ts3),
+            "ts6" => Ok(Self::Ts6),
+            _ => Err(ProductError::NotRecognized(s.to_owned())),
+        }
+    }
+}
+
+impl Display for Product {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Ts3 => "ts3",
+            Self::Ts6 => "ts6",
+        })
+    }
+}
+
+impl Product {
+    pub fn default_mirror_url(&self) -> &'static str {
+        match self {
+            Self::Ts3 => DEFAULT_TS3_MIRROR_URL,
+            Self::Ts6 => ts6::DEFAULT_MIRROR_URL,
+        }
+    }
+
+    pub fn default_filename_template(&self) -> &'static str {
+        match self {
+            Self::Ts3 => target::DEFAULT_FILENAME_TEMPLATE,
+            Self::Ts6 => ts6::DEFAULT_FILENAME_TEMPLATE,
+        }
+    }
+
+    /// Server executable inside the release directory, without the `.exe` suffix of Windows releases.
+    pub fn binary_name(&self) -> &'static str {
+        match self {
+            Self::Ts3 => "ts3server",
+            Self::Ts6 => "tsserver",
+        }
+    }
+
+    /// Whether published versions carry pre-release tags, like the `6.0.0-beta2` betas of TeamSpeak 6.
+    pub fn has_prereleases(&self) -> bool {
+        matches!(self, Self::Ts6)
+    }
+}
+
+/// Archive of a version isn't published on the mirror, even though the listing could be read.
+#[derive(Debug, Error)]
+#[error("archive {0} is not published on the mirror")]
+pub struct ArchiveMissing(String);
+
+/// Mirror answered with something else than the archive, e.g. an error page with status 200.
+#[derive(Debug, Error)]
+pub enum NotAnArchive {
+    #[error(
+        "mirror returned HTML instead of an archive for {0} - check the mirror URL and version"
+    )]
+    Html(reqwest::Url),
+    #[error("mirror returned only {1} bytes for {0}, too few for a server archive - check the mirror URL and version")]
+    TooSmall(reqwest::Url, u64),
+    #[error("{0} doesn't look like a server archive (expected {1}) - check --target-tuple and --filename-template")]
+    WrongFormat(reqwest::Url, target::ArchiveType),
+}
+
+/// Mirror answered 429 or 503 and asked to come back later than `--max-retry-wait` allows to wait, at the Unix timestamp.
+#[derive(Debug, Error)]
+#[error("mirror in maintenance, retrying at {}", state::format_timestamp(*.0))]
+pub struct MirrorMaintenance(pub u64);
+
+/// When the mirror asked to come back, if `error` is caused by [`MirrorMaintenance`].
+pub fn maintenance_retry_at(error: &anyhow::Error) -> Option<u64> {
+    error
+        .chain()
+        .find_map(|cause| cause.downcast_ref::<MirrorMaintenance>())
+        .map(|MirrorMaintenance(retry_at)| *retry_at)
+}
+
+/// Operation needs the network, but `--offline` forbids using it.
+#[derive(Debug, Error)]
+#[error("{0} needs network access, which is disabled in offline mode")]
+pub struct Offline(pub &'static str);
+
+/// Fails with [`Offline`] instead of letting `operation` run into a network timeout.
+pub fn ensure_online(config: &Config, operation: &'static str) -> Result<()> {
+    if config.offline {
+        Err(Offline(operation).into())
+    } else {
+        Ok(())
+    }
+}
+
+/// Host downloads may be fetched from, given as `example.com` or `*.example.com` for all its subdomains.
+#[derive(Clone)]
+pub struct AllowedHost {
+    host: String,
+    subdomains: bool,
+}
+
+#[derive(Debug, Error)]
+pub enum AllowedHostError {
+    #[error("allowed host not recognized: {0} (expected a host name like `example.com` or `*.example.com`)")]
+    NotRecognized(String),
+}
+
+impl FromStr for AllowedHost {
+    type Err = AllowedHostError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (host, subdomains) = match s.strip_prefix("*.") {
+            Some(host) => (host, true),
+            None => (s, false),
+        };
+        if host.is_empty() || host.contains(['/', ':', '*', '@']) {
+            return Err(AllowedHostError::NotRecognized(s.to_owned()));
+        }
+
+        Ok(Self {
+            host: host.to_ascii_lowercase(),
+            subdomains,
+        })
+    }
+}
+
+impl Display for AllowedHost {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.subdomains {
+            f.write_str("*.")?;
+        }
+        f.write_str(&self.host)
+    }
+}
+
+impl AllowedHost {
+    fn matches(&self, host: &str) -> bool {
+        let host = host.to_ascii_lowercase();
+        host == self.host
+            || (self.subdomains
+                && host
+                    .strip_suffix(&self.host)
+                    .is_some_and(|prefix| prefix.ends_with('.')))
+    }
+}
+
+/// Request was sent, or redirected, to a host outside of `--allowed-hosts`, or redirected too often.
+#[derive(Debug, Error)]
+pub enum RedirectRefused {
+    #[error("{0} is not on an allowed host - add its host with --allowed-hosts if it is trusted")]
+    HostNotAllowed(reqwest::Url),
+    #[error("gave up after {0} redirects - raise --max-redirects if the mirror needs more")]
+    TooMany(usize),
+}
+
+/// Whether `url` may be fetched from. All hosts are allowed when no `--allowed-hosts` are given.
+pub fn is_allowed(allowed_hosts: &[AllowedHost], url: &reqwest::Url) -> bool {
+    allowed_hosts.is_empty()
+        || url
+            .host_str()
+            .is_some_and(|host| allowed_hosts.iter().any(|allowed| allowed.matches(host)))
+}
+
+/// Fails with [`RedirectRefused`] when `url` is not on one of the `--allowed-hosts`.
+pub fn ensure_allowed(config: &Config, url: &reqwest::Url) -> Result<()> {
+    if is_allowed(&config.allowed_hosts, url) {
+        Ok(())
+    } else {
+        Err(RedirectRefused::HostNotAllowed(url.clone()).into())
+    }
+}
+
+/// Static address of a host given curl-style as `host:port:address`, e.g. for split-horizon DNS.
+#[derive(Clone)]
+pub struct Resolve {
+    host: String,
+    port: u16,
+    addresses: Vec<IpAddr>,
+}
+
+#[derive(Debug, Error)]
+pub enum ResolveError {
+    #[error("host mapping not recognized: {0} (expected `host:port:address`, e.g. `files.teamspeak-services.com:443:10.0.0.5`)")]
+    NotRecognized(String),
+}
+
+impl FromStr for Resolve {
+    type Err = ResolveError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let not_recognized = || ResolveError::NotRecognized(s.to_owned());
+        let mut parts = s.splitn(3, ':');
+        let (Some(host), Some(port), Some(addresses)) = (parts.next(), parts.next(), parts.next())
+        else {
+            return Err(not_recognized());
+        };
+        if host.is_empty() {
+            return Err(not_recognized());
+        }
+
+        // Like curl, several addresses are separated by commas and IPv6 ones may be in brackets.
+        let addresses = addresses
+            .split(',')
+            .map(|address| {
+                address
+                    .trim_start_matches('[')
+                    .trim_end_matches(']')
+                    .parse()
+            })
+            .collect::<Result<_, _>>()
+            .map_err(|_| not_recognized())?;
+
+        Ok(Self {
+            host: host.to_ascii_lowercase(),
+            port: port.parse().map_err(|_| not_recognized())?,
+            addresses,
+        })
+    }
+}
+
+impl Display for Resolve {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let addresses = self
+            .addresses
+            .iter()
+            .map(|address| match address {
+                IpAddr::V4(address) => address.to_string(),
+                IpAddr::V6(address) => format!("[{}]", address),
+            })
+            .collect::<Vec<_>>();
+        write!(f, "{}:{}:{}", self.host, self.port, addresses.join(","))
+    }
+}
+
+/// Extra request header given as `Name: value`.
+#[derive(Clone)]
+pub struct Header(HeaderName, HeaderValue);
+
+#[derive(Debug, Error)]
+pub enum HeaderError {
+    #[error("header not recognized: {0} (expected `Name: value`)")]
+    NotRecognized(String),
+}
+
+impl FromStr for Header {
+    type Err = HeaderError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let not_recognized = || HeaderError::NotRecognized(s.to_owned());
+        let (name, value) = s.split_once(':').ok_or_else(not_recognized)?;
+
+        Ok(Self(
+            HeaderName::from_str(name.trim()).map_err(|_| not_recognized())?,
+            HeaderValue::from_str(value.trim()).map_err(|_| not_recognized())?,
+        ))
+    }
+}
+
+/// HTTP client shared by all requests of a run, carrying the configured user agent and headers.
+/// Redirects are followed only up to `--max-redirects` and only to `--allowed-hosts`.
+pub fn client(config: &Config) -> Result<Client> {
+    let Config {
+        user_agent,
+        allowed_hosts,
+        max_redirects,
+        ipv4_only,
+        ipv6_only,
+        bind_address,
+        resolve,
+        ..
+    } = config;
+
+    let allowed_hosts = allowed_hosts.clone();
+    let max_redirects = *max_redirects;
+    let redirect_policy = reqwest::redirect::Policy::custom(move |attempt| {
+        if attempt.previous().len() > max_redirects {
+            attempt.error(RedirectRefused::TooMany(max_redirects))
+        } else if !is_allowed(&allowed_hosts, attempt.url()) {
+            let url = attempt.url().clone();
+            attempt.error(RedirectRefused::HostNotAllowed(url))
+        } else {
+            attempt.follow()
+        }
+    });
+
+    // Binding to the unspecified address of one IP version makes the connector skip addresses of the other.
+    let local_address = match bind_address {
+        Some(bind_address) => Some(*bind_address),
+        None if *ipv4_only => Some(Ipv4Addr::UNSPECIFIED.into()),
+        None if *ipv6_only => Some(Ipv6Addr::UNSPECIFIED.into()),
+        None => None,
+    };
+
+    let mut builder = Client::builder()
+        .user_agent(user_agent.as_deref().unwrap_or(DEFAULT_USER_AGENT))
+        .redirect(redirect_policy)
+        .local_address(local_address);
+    // The client maps hosts regardless of the port, as DNS does.
+    for Resolve {
+        host,
+        port,
+        addresses,
+    } in resolve
+    {
+        let addresses = addresses
+            .iter()
+            .map(|address| SocketAddr::new(*address, *port))
+            .collect::<Vec<_>>();
+        builder = builder.resolve_to_addrs(host, &addresses);
+    }
+
+    Ok(builder.build()?)
+}
+
+/// Moves user info embedded in the mirror URL into the basic authentication options, so the URL can be printed and recorded.
+/// Options given explicitly take precedence over the embedded credentials.
+pub fn take_url_credentials(config: &mut Config) {
+    use percent_encoding::percent_decode_str;
+
+    let Ok(mut url) = reqwest::Url::parse(&config.mirror_url) else {
+        return;
+    };
+    if url.username().is_empty() && url.password().is_none() {
+        return;
+    }
+
+    let decode = |value: &str| percent_decode_str(value).decode_utf8_lossy().into_owned();
+    if config.mirror_user.is_none() {
+        config.mirror_user = Some(decode(url.username()));
+    }
+    if config.mirror_password.is_none() {
+        config.mirror_password = url.password().map(decode);
+    }
+
+    let _ = url.set_username("");
+    let _ = url.set_password(None);
+    config.mirror_url = url.into();
+}
+
+/// Sends a request to the mirror. Answers 429 and 503 are waited out as long as their `Retry-After` asks,
+/// up to `--max-retry-wait` in total, and fail with [`MirrorMaintenance`] beyond that.
+pub async fn send_to_mirror(config: &Config, request: RequestBuilder) -> Result<Response> {
+    use reqwest::StatusCode;
+
+    let mut waited = std::time::Duration::ZERO;
+    loop {
+        let response = request
+            .try_clone()
+            .ok_or_else(|| anyhow!("request to the mirror can't be repeated"))?
+            .send()
+            .await?;
+        let status = response.status();
+        if ![
+            StatusCode::TOO_MANY_REQUESTS,
+            StatusCode::SERVICE_UNAVAILABLE,
+        ]
+        .contains(&status)
+        {
+            return Ok(response);
+        }
+
+        let retry_after = retry_after(response.headers()).unwrap_or(DEFAULT_RETRY_AFTER);
+        let retry_at = state::unix_timestamp() + retry_after.as_secs();
+        waited += retry_after;
+        if waited > *config.max_retry_wait {
+            return Err(MirrorMaintenance(retry_at).into());
+        }
+        println!(
+            "🚧 Mirror in maintenance ({}), retrying at {}",
+            status,
+            state::format_timestamp(retry_at)
+        );
+        tokio::time::sleep(retry_after).await;
+    }
+}
+
+/// `Retry-After` given in seconds or as an HTTP date.
+fn retry_after(headers: &HeaderMap) -> Option<std::time::Duration> {
+    use reqwest::header::RETRY_AFTER;
+
+    let retry_after = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(seconds) = retry_after.parse() {
+        return Some(std::time::Duration::from_secs(seconds));
+    }
+    let retry_at = DateTime::parse_from_rfc2822(retry_after).ok()?;
+    Some(
+        (retry_at.with_timezone(&Utc) - Utc::now())
+            .to_std()
+            .unwrap_or_default(),
+    )
+}
+
+/// Adds configured mirror credentials and `--header` headers to a request. Used only for requests going to the mirror.
+pub fn authorize(config: &Config, request: RequestBuilder) -> RequestBuilder {
+    let Config {
+        mirror_user,
+        mirror_password,
+        mirror_token,
+        header,
+        ..
+    } = config;
+
+    // `--header` is meant for the mirror, so webhooks and telemetry endpoints sharing the client don't get it.
+    let request = header.iter().fold(request, |request, Header(name, value)| {
+        request.header(name.clone(), value.clone())
+    });
+    match (mirror_token, mirror_user) {
+        (Some(token), _) => request.bearer_auth(token),
+        (None, Some(user)) => request.basic_auth(user, mirror_password.as_ref()),
+        (None, None) => request,
+    }
+}
 
 fn versions(listing_body: String) -> Vec<Version> {
     let fragment = Html::parse_fragment(&listing_body);
@@ -11,13 +477,10 @@ fn versions(listing_body: String) -> Vec<Version> {
     let mut versions = vec![];
 
     for version_link in fragment.select(&selector) {
-        let version_text = version_link
-            .text()
-            .into_iter()
-            .fold(String::new(), |mut m, piece| {
-                m.push_str(piece);
-                m
-            });
+        let version_text = version_link.text().fold(String::new(), |mut m, piece| {
+            m.push_str(piece);
+            m
+        });
 
         if let Ok(version) = Version::parse(&version_text) {
             versions.push(version);
@@ -27,65 +490,699 @@ fn versions(listing_body: String) -> Vec<Version> {
     versions
 }
 
+/// Dates of the version directories in an nginx or Apache directory listing, which follow the links.
+fn listing_dates(listing_body: &str) -> HashMap<Version, DateTime<Utc>> {
+    const DATE_FORMATS: &[&str] = &["%d-%b-%Y %H:%M", "%Y-%m-%d %H:%M"];
+
+    let fragment = Html::parse_fragment(listing_body);
+    let selector = Selector::parse("pre > a").expect("selector is invalid");
+
+    fragment
+        .select(&selector)
+        .filter_map(|version_link| {
+            let version = Version::parse(&version_link.text().collect::<String>()).ok()?;
+            let details = version_link.next_sibling()?.value().as_text()?.to_string();
+            let date = details
+                .split_whitespace()
+                .take(2)
+                .collect::<Vec<_>>()
+                .join(" ");
+            let date = DATE_FORMATS
+                .iter()
+                .find_map(|format| NaiveDateTime::parse_from_str(&date, format).ok())?;
+
+            Some((version, date.and_utc()))
+        })
+        .collect()
+}
+
+/// Body of the mirror listing with its `Last-Modified` date, from the cache within `--max-listing-age` or the mirror.
+async fn listing(config: &Config, http: &Client) -> Result<(String, Option<DateTime<Utc>>)> {
+    use reqwest::header::LAST_MODIFIED;
+
+    let Config {
+        mirror_url,
+        product,
+        ..
+    } = config;
+    let listing_url = match product {
+        Product::Ts3 => mirror_url.as_str(),
+        Product::Ts6 => ts6::listing_url(mirror_url),
+    };
+
+    if let Some(listing) = fresh_listing(config, listing_url).await {
+        return Ok((listing.body, None));
+    }
+
+    ensure_online(config, "reading the mirror listing")?;
+    let response = send_to_mirror(config, authorize(config, http.get(listing_url)))
+        .await?
+        .error_for_status()?;
+    let last_modified = response
+        .headers()
+        .get(LAST_MODIFIED)
+        .and_then(|last_modified| last_modified.to_str().ok())
+        .and_then(|last_modified| DateTime::parse_from_rfc2822(last_modified).ok())
+        .map(|last_modified| last_modified.with_timezone(&Utc));
+
+    Ok((response.text().await?, last_modified))
+}
+
+/// All versions in the mirror listing with their publish dates where the mirror tells them, oldest first.
+pub async fn published_releases(
+    config: &Config,
+    http: &Client,
+) -> Result<Vec<(Version, Option<DateTime<Utc>>)>> {
+    let (body, _) = listing(config, http).await?;
+
+    let (versions, dates) = match config.product {
+        Product::Ts3 => (versions(body.clone()), listing_dates(&body)),
+        Product::Ts6 => (ts6::versions(&body)?, ts6::release_dates(&body)?),
+    };
+
+    Ok(sorted(versions)
+        .into_iter()
+        .map(|version| {
+            let published_at = dates.get(&version).copied();
+            (version, published_at)
+        })
+        .collect())
+}
+
+/// All versions in the mirror listing, oldest first.
+pub async fn published_versions(config: &Config, http: &Client) -> Result<Vec<Version>> {
+    Ok(published_releases(config, http)
+        .await?
+        .into_iter()
+        .map(|(version, _)| version)
+        .collect())
+}
+
+/// When `version` was published, from its entry in the mirror listing or, failing that, the listing's
+/// `Last-Modified` header. `None` if the mirror doesn't tell.
+pub async fn published_at(
+    config: &Config,
+    http: &Client,
+    version: &Version,
+) -> Result<Option<DateTime<Utc>>> {
+    let (body, last_modified) = listing(config, http).await?;
+
+    let dates = match config.product {
+        Product::Ts3 => listing_dates(&body),
+        Product::Ts6 => ts6::release_dates(&body)?,
+    };
+
+    Ok(dates.get(version).copied().or(last_modified))
+}
+
+/// Whether `version` is flagged as a security fix, with `--security-release` or by release notes in the listing.
+/// The TeamSpeak 3 mirror publishes no notes, so only the former applies there.
+pub async fn is_security_release(
+    config: &Config,
+    http: &Client,
+    version: &Version,
+) -> Result<bool> {
+    if config.security_release.contains(version) {
+        return Ok(true);
+    }
+
+    match config.product {
+        Product::Ts3 => Ok(false),
+        Product::Ts6 => {
+            let (body, _) = listing(config, http).await?;
+            Ok(ts6::security_releases(&body)?.contains(version))
+        }
+    }
+}
+
+/// Versions sorted oldest first, without duplicates.
+fn sorted(mut versions: Vec<Version>) -> Vec<Version> {
+    versions.sort();
+    versions.dedup();
+    versions
+}
+
+/// File the parsed mirror listing is cached in.
+pub fn listing_cache_path(config: &Config) -> std::path::PathBuf {
+    cache::cache_path(config)
+}
+
+/// Newest version of the sorted `versions`, or the one `--lag` releases behind it.
+fn select_version(config: &Config, versions: &[Version]) -> Result<Version> {
+    let Config { lag, .. } = config;
+
+    versions
+        .iter()
+        .rev()
+        .nth(*lag)
+        .cloned()
+        .ok_or_else(|| match versions.len() {
+            0 => anyhow!("no versions are collected from remote endpoint"),
+            count => anyhow!(
+                "mirror lists only {} version(s), too few to stay {} release(s) behind the latest",
+                count,
+                lag
+            ),
+        })
+}
+
+/// Version updates go to: the newest one on the mirror, or the one `--lag` releases behind it.
 pub async fn latest_version(config: &Config, http: &Client) -> Result<Version> {
-    let Config { mirror_url, .. } = config;
+    use reqwest::{
+        header::{ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED},
+        StatusCode,
+    };
+
+    let Config {
+        mirror_url,
+        product,
+        ..
+    } = config;
+    let listing_url = match product {
+        Product::Ts3 => mirror_url.as_str(),
+        Product::Ts6 => ts6::listing_url(mirror_url),
+    };
+
+    if config.offline {
+        let listing = cache::load(config, listing_url).await?.ok_or(Offline(
+            "checking the mirror without a listing cached by an earlier run",
+        ))?;
+        return select_version(config, &listing.sorted_versions());
+    }
+    if let Some(listing) = fresh_listing(config, listing_url).await {
+        return select_version(config, &listing.sorted_versions());
+    }
+
+    let cached = listing_cache()
+        .clone()
+        .filter(|cached| cached.mirror_url == *mirror_url);
+
+    let mut request = authorize(config, http.get(listing_url));
+    if let Some(cached) = &cached {
+        if let Some(etag) = &cached.etag {
+            request = request.header(IF_NONE_MATCH, etag.clone());
+        }
+        if let Some(last_modified) = &cached.last_modified {
+            request = request.header(IF_MODIFIED_SINCE, last_modified.clone());
+        }
+    }
 
-    let response = http.get(mirror_url).send().await?.error_for_status()?;
+    let response = send_to_mirror(config, request).await?.error_for_status()?;
+    if let (StatusCode::NOT_MODIFIED, Some(cached)) = (response.status(), cached) {
+        return select_version(config, &cached.versions);
+    }
+
+    let etag = response.headers().get(ETAG).cloned();
+    let last_modified = response.headers().get(LAST_MODIFIED).cloned();
     let body = response.text().await?;
 
-    let result = versions(body)
-        .into_iter()
-        .max()
-        .ok_or_else(|| anyhow!("no versions are collected from remote endpoint"));
+    let versions = match product {
+        Product::Ts3 => versions(body.clone()),
+        Product::Ts6 => ts6::versions(&body)?,
+    };
+    // Without the cache, later runs just fetch the listing again. `check` must keep its output a single line.
+    let _ = cache::store(config, listing_url, &versions, body).await;
+    let versions = sorted(versions);
+    let latest_version = select_version(config, &versions)?;
 
-    if let Ok(ref version) = result {
-        println!("🌐 Determined latest remote TeamSpeak version: {}", version);
+    if etag.is_some() || last_modified.is_some() {
+        *listing_cache() = Some(CachedListing {
+            mirror_url: mirror_url.clone(),
+            etag,
+            last_modified,
+            versions,
+        });
     }
 
-    result
+    Ok(latest_version)
+}
+
+fn listing_cache() -> std::sync::MutexGuard<'static, Option<CachedListing>> {
+    LISTING_CACHE
+        .lock()
+        .expect("listing cache lock is poisoned")
+}
+
+/// Listing cached on disk within `--max-listing-age`, unless `--refresh` is given.
+async fn fresh_listing(config: &Config, listing_url: &str) -> Option<cache::Listing> {
+    let max_listing_age = config.max_listing_age.filter(|_| !config.refresh)?;
+
+    cache::load(config, listing_url)
+        .await
+        .ok()
+        .flatten()
+        .filter(|listing| listing.age() < *max_listing_age)
+}
+
+pub struct Download {
+    pub archive: tokio::fs::File,
+    pub url: reqwest::Url,
+    pub sha256: String,
+    pub size: u64,
+    /// Algorithm of the checksum file the archive matched, `None` when it wasn't verified.
+    pub verified: Option<checksum::Algorithm>,
 }
 
 pub async fn download_release(
     config: &Config,
     http: &Client,
     target: &Version,
-) -> Result<tokio::fs::File> {
+) -> Result<Download> {
+    download_archive(config, http, config.target_tuple, target).await
+}
+
+/// Whether `--stream-extract` applies: tarballs downloaded over a single connection arrive front to back.
+pub fn streams_extraction(config: &Config) -> bool {
+    config.stream_extract
+        && config.download_connections <= 1
+        && config.target_tuple.archive_type() != target::ArchiveType::Zip
+}
+
+/// Downloads the archive of `target` and unpacks it into `tempdir` as it arrives, under `--stream-extract`.
+///
+/// The archive is still written to a temporary file, for its checksum and for the artifact record.
+pub async fn download_release_extracting(
+    config: &Config,
+    http: &Client,
+    target: &Version,
+    tempdir: std::sync::Arc<tempfile::TempDir>,
+) -> Result<Download> {
+    ensure_online(config, "downloading the archive")?;
+
+    let archive_url = match config.product {
+        Product::Ts3 => tuple_archive_url(config, config.target_tuple, target)?,
+        Product::Ts6 => ts6::archive_url(config, http, config.target_tuple, target).await?,
+    };
+    print!("🌐 Downloading and extracting {}... ", archive_url);
+
+    // Bounded, so a slow disk holds back the download instead of buffering the archive in memory.
+    let (tee, reader) = tokio::io::duplex(PIPELINE_BUFFER_BYTES);
+    let extraction = tokio::spawn(crate::extractor::extract_stream(
+        config.target_tuple.archive_type(),
+        tempdir,
+        reader,
+    ));
+
+    let throttle = Throttle::new(config.limit_rate);
+    let progress = DownloadProgress::new(config, None);
+    let downloaded =
+        download_stream(config, http, &archive_url, &throttle, &progress, Some(tee)).await;
+    // A failed download cuts the archive short, so its error explains more than the extraction's.
+    let extracted = extraction.await?;
+    // The same goes for a response which isn't an archive at all.
+    let downloaded = match downloaded {
+        Ok((archive, sha256, size)) => check_archive(
+            &archive,
+            size,
+            config.target_tuple.archive_type(),
+            &archive_url,
+        )
+        .await
+        .map(|()| (archive, sha256, size)),
+        Err(e) => Err(e),
+    };
+    let (archive, sha256, size) = match (downloaded, extracted) {
+        (Err(e), _) | (_, Err(e)) => return Err(e),
+        (Ok(downloaded), Ok(())) => downloaded,
+    };
+    progress.finish();
+    println!("✅");
+
+    let mut download = Download {
+        archive,
+        url: archive_url,
+        sha256,
+        size,
+        verified: None,
+    };
+    download.verified = checksum::verify(config, http, &download, target).await?;
+
+    Ok(download)
+}
+
+/// Downloads the archive of `target` for any tuple, not only the configured one.
+pub async fn download_archive(
+    config: &Config,
+    http: &Client,
+    tuple: target::Tuple,
+    target: &Version,
+) -> Result<Download> {
+    ensure_online(config, "downloading the archive")?;
+
+    let archive_url = match config.product {
+        Product::Ts3 => tuple_archive_url(config, tuple, target)?,
+        Product::Ts6 => ts6::archive_url(config, http, tuple, target).await?,
+    };
+    print!("🌐 Downloading {}... ", archive_url);
+
+    let ranged_length = if config.download_connections > 1 {
+        ranged_content_length(config, http, &archive_url).await?
+    } else {
+        None
+    };
+
+    let throttle = Throttle::new(config.limit_rate);
+    let progress = DownloadProgress::new(config, ranged_length);
+    let (archive, sha256, size) = match ranged_length {
+        Some(length) => {
+            download_chunked(
+                config,
+                http,
+                &archive_url,
+                &throttle,
+                &progress,
+                length,
+                config.download_connections,
+            )
+            .await?
+        }
+        None => download_stream(config, http, &archive_url, &throttle, &progress, None).await?,
+    };
+    check_archive(&archive, size, tuple.archive_type(), &archive_url).await?;
+    progress.finish();
+    println!("✅");
+
+    let mut download = Download {
+        archive,
+        url: archive_url,
+        sha256,
+        size,
+        verified: None,
+    };
+    download.verified = checksum::verify(config, http, &download, target).await?;
+
+    Ok(download)
+}
+
+async fn download_stream(
+    config: &Config,
+    http: &Client,
+    archive_url: &reqwest::Url,
+    throttle: &Throttle,
+    progress: &DownloadProgress<'_>,
+    mut tee: Option<tokio::io::DuplexStream>,
+) -> Result<(tokio::fs::File, String, u64)> {
     use futures::stream::TryStreamExt;
+    use sha2::{Digest, Sha256};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
     use tokio_util::compat::FuturesAsyncReadCompatExt;
 
-    let archive_url = remote_archive_path(config, target);
-    print!("🌐 Downloading {}... ", archive_url);
-    let archive_response = http.get(archive_url).send().await?.error_for_status()?;
-    let tempfile = tempfile::tempfile()?;
+    ensure_allowed(config, archive_url)?;
+    let archive_response = send_to_mirror(config, authorize(config, http.get(archive_url.clone())))
+        .await?
+        .error_for_status()?;
+    print_redirect(archive_url, archive_response.url());
+    check_content_type(archive_response.headers(), archive_url)?;
+    let tempfile = tempfile::tempfile_in(local::work_dir(config)?)?;
     let mut tempfile = tokio::io::BufWriter::new(tokio::fs::File::from_std(tempfile));
+    let mut hasher = Sha256::new();
 
-    let mut stream = tokio::io::BufReader::new(
-        archive_response
-            .bytes_stream()
-            .map_err(|e| futures::io::Error::new(futures::io::ErrorKind::Other, e))
-            .into_async_read()
-            .compat(),
-    );
+    let stream = archive_response
+        .bytes_stream()
+        .inspect_ok(|chunk| {
+            hasher.update(chunk);
+            progress.advance(chunk.len());
+        })
+        .and_then(|chunk| async move {
+            throttle.consume(chunk.len()).await;
+            Ok(chunk)
+        })
+        .map_err(futures::io::Error::other);
+    let mut stream = tokio::io::BufReader::new(Box::pin(stream).into_async_read().compat());
 
-    tokio::io::copy(&mut stream, &mut tempfile).await?;
-    println!("✅");
-    Ok(tempfile.into_inner())
+    let size = if tee.is_none() {
+        tokio::io::copy(&mut stream, &mut tempfile).await?
+    } else {
+        let mut size = 0;
+        let mut buf = vec![0; 64 * 1024];
+        loop {
+            let read = stream.read(&mut buf).await?;
+            if read == 0 {
+                break;
+            }
+            tempfile.write_all(&buf[..read]).await?;
+            // The extractor stops reading at the end of the tarball, or when it failed, which the caller reports.
+            if let Some(pipe) = &mut tee {
+                if pipe.write_all(&buf[..read]).await.is_err() {
+                    tee = None;
+                }
+            }
+            size += read as u64;
+        }
+        tempfile.flush().await?;
+        size
+    };
+    drop(stream);
+    // Closing the pipe tells the extractor the archive is complete.
+    drop(tee);
+
+    Ok((tempfile.into_inner(), hex::encode(hasher.finalize()), size))
 }
 
-fn remote_archive_path(config: &Config, target: &Version) -> reqwest::Url {
-    use reqwest::Url;
+/// Fails right away on HTML, which mirrors and proxies answer with on errors instead of an error status.
+fn check_content_type(
+    headers: &reqwest::header::HeaderMap,
+    archive_url: &reqwest::Url,
+) -> Result<()> {
+    let is_html = headers
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.trim_start().to_lowercase().starts_with("text/html"));
+
+    if is_html {
+        Err(NotAnArchive::Html(archive_url.clone()).into())
+    } else {
+        Ok(())
+    }
+}
+
+/// Makes sure the downloaded file is an archive before it gets extracted, as extractors fail on anything else with
+/// errors which don't point to the mirror. Any type extraction recognizes passes, as the content decides it.
+async fn check_archive(
+    archive: &tokio::fs::File,
+    size: u64,
+    archive_type: target::ArchiveType,
+    archive_url: &reqwest::Url,
+) -> Result<()> {
+    use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+    let mut head = vec![];
+    let mut archive = archive.try_clone().await?;
+    archive.seek(std::io::SeekFrom::Start(0)).await?;
+    (&mut archive).take(512).read_to_end(&mut head).await?;
+
+    let text = String::from_utf8_lossy(&head).trim_start().to_lowercase();
+    if text.starts_with("<!doctype html") || text.starts_with("<html") || text.starts_with("<?xml")
+    {
+        return Err(NotAnArchive::Html(archive_url.clone()).into());
+    }
+    if size < MIN_ARCHIVE_BYTES {
+        return Err(NotAnArchive::TooSmall(archive_url.clone(), size).into());
+    }
+    if extractor::sniff(&head).is_none() {
+        return Err(NotAnArchive::WrongFormat(archive_url.clone(), archive_type).into());
+    }
+
+    Ok(())
+}
+
+/// Shows where the archive is actually fetched from when the mirror redirected, e.g. to a CDN.
+fn print_redirect(archive_url: &reqwest::Url, final_url: &reqwest::Url) {
+    if final_url != archive_url {
+        print!("↪️ {} ", final_url);
+    }
+}
+
+/// Returns archive size if the mirror supports range requests for it.
+async fn ranged_content_length(
+    config: &Config,
+    http: &Client,
+    archive_url: &reqwest::Url,
+) -> Result<Option<u64>> {
+    use reqwest::header::{ACCEPT_RANGES, CONTENT_LENGTH};
+
+    ensure_allowed(config, archive_url)?;
+    let response = send_to_mirror(config, authorize(config, http.head(archive_url.clone())))
+        .await?
+        .error_for_status()?;
+    print_redirect(archive_url, response.url());
+    check_content_type(response.headers(), archive_url)?;
+
+    let accepts_bytes = response
+        .headers()
+        .get(ACCEPT_RANGES)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.contains("bytes"))
+        .unwrap_or(false);
+
+    // `Response::content_length` reports the (empty) body of a HEAD response, so the header is read instead.
+    Ok(response
+        .headers()
+        .get(CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse().ok())
+        .filter(|length| accepts_bytes && *length > 0))
+}
+
+async fn download_chunked(
+    config: &Config,
+    http: &Client,
+    archive_url: &reqwest::Url,
+    throttle: &Throttle,
+    progress: &DownloadProgress<'_>,
+    length: u64,
+    connections: u64,
+) -> Result<(tokio::fs::File, String, u64)> {
+    use futures::stream::TryStreamExt;
+    use tokio::io::{AsyncSeekExt, AsyncWriteExt};
+
+    let tempfile = tempfile::Builder::new()
+        .prefix(local::WORK_ENTRY_PREFIX)
+        .tempfile_in(local::work_dir(config)?)?;
+    tempfile.as_file().set_len(length)?;
+
+    let chunk_size = length.div_ceil(connections);
+    let ranges = (0..length)
+        .step_by(chunk_size as usize)
+        .map(|start| (start, (start + chunk_size).min(length) - 1));
+
+    let chunks = ranges.map(|(start, end)| {
+        let path = tempfile.path().to_owned();
+        async move {
+            use reqwest::{
+                header::{CONTENT_RANGE, RANGE},
+                StatusCode,
+            };
+
+            let request = authorize(config, http.get(archive_url.clone()))
+                .header(RANGE, format!("bytes={}-{}", start, end));
+            let response = send_to_mirror(config, request).await?.error_for_status()?;
+
+            if response.status() != StatusCode::PARTIAL_CONTENT {
+                return Err(anyhow!(
+                    "mirror ignored range request for bytes {}-{}",
+                    start,
+                    end
+                ));
+            }
+            let content_range = response
+                .headers()
+                .get(CONTENT_RANGE)
+                .and_then(|value| value.to_str().ok())
+                .unwrap_or_default();
+            if !is_content_range(content_range, start, end, length) {
+                return Err(anyhow!(
+                    "mirror answered range request for bytes {}-{} of {} with range `{}`",
+                    start,
+                    end,
+                    length,
+                    content_range
+                ));
+            }
+
+            let mut file = tokio::fs::OpenOptions::new().write(true).open(path).await?;
+            file.seek(std::io::SeekFrom::Start(start)).await?;
+
+            // Anything but the exact range would overwrite a neighbour or leave a zero-filled gap in the archive.
+            let expected = end - start + 1;
+            let mut written = 0;
+            let mut stream = response.bytes_stream().map_err(anyhow::Error::from);
+            while let Some(bytes) = stream.try_next().await? {
+                written += bytes.len() as u64;
+                if written > expected {
+                    return Err(anyhow!(
+                        "mirror sent more than the {} byte(s) of range {}-{}",
+                        expected,
+                        start,
+                        end
+                    ));
+                }
+                throttle.consume(bytes.len()).await;
+                file.write_all(&bytes).await?;
+                progress.advance(bytes.len());
+            }
+            file.flush().await?;
+            if written != expected {
+                return Err(anyhow!(
+                    "mirror sent {} of the {} byte(s) of range {}-{}",
+                    written,
+                    expected,
+                    start,
+                    end
+                ));
+            }
+
+            Ok(())
+        }
+    });
+
+    futures::future::try_join_all(chunks).await?;
+
+    let mut file = tempfile.into_file();
+    let (file, sha256) = tokio::task::spawn_blocking(move || -> Result<_> {
+        use sha2::{Digest, Sha256};
+        use std::io::{Seek, SeekFrom};
+
+        let mut hasher = Sha256::new();
+        file.seek(SeekFrom::Start(0))?;
+        std::io::copy(&mut file, &mut hasher)?;
+
+        Ok((file, hex::encode(hasher.finalize())))
+    })
+    .await??;
+
+    Ok((tokio::fs::File::from_std(file), sha256, length))
+}
+
+/// Whether a `Content-Range` header value covers exactly bytes `start` to `end` of an archive of `length` bytes.
+fn is_content_range(value: &str, start: u64, end: u64, length: u64) -> bool {
+    let Some((range, total)) = value
+        .strip_prefix("bytes ")
+        .and_then(|value| value.split_once('/'))
+    else {
+        return false;
+    };
+
+    range.trim() == format!("{}-{}", start, end)
+        && (total.trim() == "*" || total.trim().parse() == Ok(length))
+}
+
+pub fn is_not_found(error: &anyhow::Error) -> bool {
+    error.is::<ArchiveMissing>()
+        || error
+            .downcast_ref::<reqwest::Error>()
+            .and_then(|e| e.status())
+            == Some(reqwest::StatusCode::NOT_FOUND)
+}
+
+pub fn remote_archive_path(config: &Config, target: &Version) -> Result<reqwest::Url> {
+    tuple_archive_url(config, config.target_tuple, target)
+}
+
+fn tuple_archive_url(
+    config: &Config,
+    tuple: target::Tuple,
+    target: &Version,
+) -> Result<reqwest::Url> {
+    let root_url = reqwest::Url::parse(&config.mirror_url)?;
+    let archive_path = archive_path(config, tuple, target);
+
+    root_url
+        .join(&archive_path)
+        .map_err(|e| anyhow!("archive URL {} is invalid: {}", archive_path, e))
+}
+
+/// Path of the archive relative to the mirror URL, following `--url-template`.
+pub fn archive_path(config: &Config, tuple: target::Tuple, target: &Version) -> String {
     let Config {
-        mirror_url,
-        target_tuple,
+        filename_template,
+        url_template,
         ..
     } = config;
-    let root_url = Url::parse(mirror_url).expect("mirror url is valid URL");
+    let file_name = tuple.archive_filename(filename_template, target);
 
-    root_url
-        .join(&format!("{}/", target))
-        .and_then(|version_url| {
-            let file_name = target_tuple.archive_filename(target);
-            version_url.join(&file_name)
-        })
-        .expect("wrong target URL format")
+    url_template
+        .replace("{version}", &target.to_string())
+        .replace("{target}", &tuple.to_string())
+        .replace("{file}", &file_name)
 }
@@ -0,0 +1,84 @@
+//! Parsed mirror listing kept on disk, so frequent invocations don't have to fetch it every time.
+
+use crate::{
+    cli::Config,
+    local,
+    state::{self, unix_timestamp},
+};
+use anyhow::Result;
+use semver::Version;
+use serde::{Deserialize, Serialize};
+use std::{path::PathBuf, time::Duration};
+
+const CACHE_FILE_NAME: &str = ".ts-updater-listing.json";
+
+#[derive(Serialize, Deserialize)]
+pub struct Listing {
+    pub listing_url: String,
+    pub fetched_at: u64,
+    pub versions: Vec<String>,
+    pub body: String,
+}
+
+impl Listing {
+    pub fn age(&self) -> Duration {
+        Duration::from_secs(unix_timestamp().saturating_sub(self.fetched_at))
+    }
+
+    /// Versions of the listing, oldest first.
+    pub fn sorted_versions(&self) -> Vec<Version> {
+        super::sorted(
+            self.versions
+                .iter()
+                .filter_map(|version| version.parse().ok())
+                .collect(),
+        )
+    }
+}
+
+pub fn cache_path(config: &Config) -> PathBuf {
+    config
+        .cache_dir
+        .as_deref()
+        .or_else(|| local::in_place_dir(config))
+        .unwrap_or(&config.releases_path)
+        .join(CACHE_FILE_NAME)
+}
+
+/// Cached listing of `listing_url`, `None` when there is none or it belongs to another mirror.
+pub async fn load(config: &Config, listing_url: &str) -> Result<Option<Listing>> {
+    use std::io::ErrorKind;
+
+    let listing: Listing = match tokio::fs::read(cache_path(config)).await {
+        Ok(contents) => serde_json::from_slice(&contents)?,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+
+    Ok(Some(listing).filter(|listing| listing.listing_url == listing_url))
+}
+
+pub async fn store(
+    config: &Config,
+    listing_url: &str,
+    versions: &[Version],
+    body: String,
+) -> Result<()> {
+    use tokio::fs;
+
+    let listing = Listing {
+        listing_url: listing_url.to_owned(),
+        fetched_at: state::unix_timestamp(),
+        versions: versions.iter().map(ToString::to_string).collect(),
+        body,
+    };
+
+    let path = cache_path(config);
+    let mut temp_path = path.clone().into_os_string();
+    temp_path.push(".tmp");
+
+    fs::write(&temp_path, serde_json::to_vec(&listing)?).await?;
+    fs::rename(&temp_path, &path).await?;
+
+    Ok(())
+}
@@ -0,0 +1,187 @@
+//! TeamSpeak 6 servers are published as GitHub releases, tagged like `v6.0.0/beta2`.
+
+use super::ArchiveMissing;
+use crate::{cli::Config, target::Tuple};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use reqwest::{Client, Url};
+use semver::Version;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+pub const DEFAULT_MIRROR_URL: &str =
+    "https://api.github.com/repos/teamspeak/teamspeak6-server/releases";
+pub const DEFAULT_FILENAME_TEMPLATE: &str = "teamspeak-server_{target}-v{version}.{ext}";
+
+#[derive(Deserialize)]
+struct Release {
+    tag_name: String,
+    #[serde(default)]
+    draft: bool,
+    #[serde(default)]
+    published_at: Option<String>,
+    /// Release notes.
+    #[serde(default)]
+    body: Option<String>,
+    assets: Vec<Asset>,
+}
+
+#[derive(Deserialize)]
+struct Asset {
+    name: String,
+    browser_download_url: String,
+}
+
+/// The releases API doesn't answer paths with a trailing slash, which URL validation adds.
+pub fn listing_url(mirror_url: &str) -> &str {
+    mirror_url.trim_end_matches('/')
+}
+
+pub fn versions(listing_body: &str) -> Result<Vec<Version>> {
+    Ok(releases(listing_body)?
+        .iter()
+        .filter_map(|release| tag_version(&release.tag_name))
+        .collect())
+}
+
+pub fn release_dates(listing_body: &str) -> Result<HashMap<Version, DateTime<Utc>>> {
+    Ok(releases(listing_body)?
+        .iter()
+        .filter_map(|release| {
+            let published_at =
+                DateTime::parse_from_rfc3339(release.published_at.as_deref()?).ok()?;
+            Some((
+                tag_version(&release.tag_name)?,
+                published_at.with_timezone(&Utc),
+            ))
+        })
+        .collect())
+}
+
+/// Versions whose release notes flag them as security fixes, see [`flags_security`].
+pub fn security_releases(listing_body: &str) -> Result<Vec<Version>> {
+    Ok(releases(listing_body)?
+        .iter()
+        .filter(|release| release.body.as_deref().is_some_and(flags_security))
+        .filter_map(|release| tag_version(&release.tag_name))
+        .collect())
+}
+
+/// Release notes flag a security fix with a `[security]` label, a heading about security or a CVE id.
+/// Notes merely mentioning security, like "security of the query interface was improved", don't.
+fn flags_security(notes: &str) -> bool {
+    notes.lines().any(|line| {
+        let line = line.trim().to_lowercase();
+        line.contains("[security]")
+            || (line.starts_with('#') && line.contains("security"))
+            || line
+                .match_indices("cve-")
+                .any(|(at, _)| is_cve_id(&line[at + 4..]))
+    })
+}
+
+/// Whether `rest` continues a `CVE-` prefix with a year and a sequence number, like `2024-12345`.
+fn is_cve_id(rest: &str) -> bool {
+    let Some((year, number)) = rest.split_once('-') else {
+        return false;
+    };
+    let digits = number.chars().take_while(char::is_ascii_digit).count();
+    year.len() == 4 && year.chars().all(|c| c.is_ascii_digit()) && digits >= 4
+}
+
+/// Looks up the download URL of the release archive among the assets of its release.
+pub async fn archive_url(
+    config: &Config,
+    http: &Client,
+    tuple: Tuple,
+    target: &Version,
+) -> Result<Url> {
+    let Config {
+        mirror_url,
+        filename_template,
+        ..
+    } = config;
+    let file_name = tuple.archive_filename(filename_template, target);
+
+    let body = match super::fresh_listing(config, listing_url(mirror_url)).await {
+        Some(listing) => listing.body,
+        None => {
+            let request = super::authorize(config, http.get(listing_url(mirror_url)));
+            super::send_to_mirror(config, request)
+                .await?
+                .error_for_status()?
+                .text()
+                .await?
+        }
+    };
+
+    let asset = releases(&body)?
+        .into_iter()
+        .filter(|release| tag_version(&release.tag_name).as_ref() == Some(target))
+        .flat_map(|release| release.assets)
+        .find(|asset| asset.name == file_name)
+        .ok_or(ArchiveMissing(file_name))?;
+
+    Ok(Url::parse(&asset.browser_download_url)?)
+}
+
+fn releases(listing_body: &str) -> Result<Vec<Release>> {
+    let releases: Vec<Release> = serde_json::from_str(listing_body)?;
+    Ok(releases
+        .into_iter()
+        .filter(|release| !release.draft)
+        .collect())
+}
+
+/// `v6.0.0/beta2` becomes `6.0.0-beta2`.
+fn tag_version(tag_name: &str) -> Option<Version> {
+    let tag_name = tag_name.strip_prefix('v').unwrap_or(tag_name);
+    Version::parse(&tag_name.replacen('/', "-", 1)).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn listing(notes: &[&str]) -> String {
+        let releases = notes
+            .iter()
+            .enumerate()
+            .map(|(beta, notes)| {
+                serde_json::json!({
+                    "tag_name": format!("v6.0.0/beta{}", beta + 1),
+                    "body": notes,
+                    "assets": [],
+                })
+            })
+            .collect::<Vec<_>>();
+        serde_json::to_string(&releases).unwrap()
+    }
+
+    #[test]
+    fn explicit_markers_flag_security_releases() {
+        let body = listing(&[
+            "[Security] Fixes a crash on malformed packets",
+            "## Security fixes\n- Query login rate limit",
+            "Fixes CVE-2025-12345 in the file transfer",
+        ]);
+
+        let versions = security_releases(&body).unwrap();
+
+        assert_eq!(
+            versions,
+            ["6.0.0-beta1", "6.0.0-beta2", "6.0.0-beta3"].map(|v| Version::parse(v).unwrap())
+        );
+    }
+
+    #[test]
+    fn mentions_of_security_are_not_flagged() {
+        let body = listing(&[
+            "Improved security of the query interface",
+            "- Updated the security documentation\n- See cve-list.txt",
+            "Fixes the CVE-20 typo in the changelog",
+        ]);
+
+        assert!(security_releases(&body).unwrap().is_empty());
+    }
+}
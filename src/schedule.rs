@@ -0,0 +1,139 @@
+use chrono::{DateTime, Datelike, Days, Local, NaiveDate, NaiveTime, TimeZone, Weekday};
+use std::{fmt::Display, str::FromStr};
+use thiserror::Error;
+
+const TIME_FORMAT: &str = "%H:%M";
+
+/// Time range in which installing updates is allowed, e.g. `Sun 03:00-05:00`. Without a day it repeats daily.
+#[derive(Clone)]
+pub struct Window {
+    day: Option<Weekday>,
+    start: NaiveTime,
+    end: NaiveTime,
+}
+
+#[derive(Debug, Error)]
+pub enum WindowError {
+    #[error("maintenance window not recognized: {0} (expected e.g. `Sun 03:00-05:00`)")]
+    NotRecognized(String),
+}
+
+impl FromStr for Window {
+    type Err = WindowError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let not_recognized = || WindowError::NotRecognized(s.to_owned());
+        let parts = s.split_whitespace().collect::<Vec<_>>();
+
+        let (day, range) = match parts.as_slice() {
+            [range] => (None, *range),
+            [day, range] => (Some(day.parse().map_err(|_| not_recognized())?), *range),
+            _ => return Err(not_recognized()),
+        };
+
+        let (start, end) = range.split_once('-').ok_or_else(not_recognized)?;
+        let start = NaiveTime::parse_from_str(start, TIME_FORMAT).map_err(|_| not_recognized())?;
+        let end = NaiveTime::parse_from_str(end, TIME_FORMAT).map_err(|_| not_recognized())?;
+
+        if start == end {
+            return Err(not_recognized());
+        }
+
+        Ok(Self { day, start, end })
+    }
+}
+
+impl Display for Window {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(day) = self.day {
+            write!(f, "{} ", day)?;
+        }
+
+        write!(
+            f,
+            "{}-{}",
+            self.start.format(TIME_FORMAT),
+            self.end.format(TIME_FORMAT)
+        )
+    }
+}
+
+impl Window {
+    /// Whether `now` falls into the window. Windows ending before they start span midnight.
+    pub fn contains(&self, now: DateTime<Local>) -> bool {
+        let time = now.time();
+
+        if self.start < self.end {
+            self.runs_on(now.weekday()) && self.start <= time && time < self.end
+        } else {
+            (self.runs_on(now.weekday()) && self.start <= time)
+                || (self.runs_on(now.weekday().pred()) && time < self.end)
+        }
+    }
+
+    /// Start of the next occurrence of the window after `now`.
+    pub fn next_start(&self, now: DateTime<Local>) -> Option<DateTime<Local>> {
+        (0..=7)
+            .filter_map(|days| now.date_naive().checked_add_days(Days::new(days)))
+            .filter(|date| self.runs_on(date.weekday()))
+            .filter_map(|date| {
+                Local
+                    .from_local_datetime(&date.and_time(self.start))
+                    .earliest()
+            })
+            .find(|start| *start > now)
+    }
+
+    fn runs_on(&self, weekday: Weekday) -> bool {
+        self.day.is_none_or(|day| day == weekday)
+    }
+}
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// Days on which no updates are installed, e.g. `2026-12-24` or `2026-12-24..2026-12-26`, in local time.
+#[derive(Clone)]
+pub struct Blackout {
+    first: NaiveDate,
+    last: NaiveDate,
+}
+
+#[derive(Debug, Error)]
+pub enum BlackoutError {
+    #[error("blackout dates not recognized: {0} (expected e.g. `2026-12-24` or `2026-12-24..2026-12-26`)")]
+    NotRecognized(String),
+}
+
+impl FromStr for Blackout {
+    type Err = BlackoutError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let not_recognized = || BlackoutError::NotRecognized(s.to_owned());
+        let (first, last) = s.split_once("..").unwrap_or((s, s));
+
+        let first = NaiveDate::parse_from_str(first, DATE_FORMAT).map_err(|_| not_recognized())?;
+        let last = NaiveDate::parse_from_str(last, DATE_FORMAT).map_err(|_| not_recognized())?;
+        if last < first {
+            return Err(not_recognized());
+        }
+
+        Ok(Self { first, last })
+    }
+}
+
+impl Display for Blackout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.first.format(DATE_FORMAT))?;
+        if self.last != self.first {
+            write!(f, "..{}", self.last.format(DATE_FORMAT))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Blackout {
+    pub fn contains(&self, now: DateTime<Local>) -> bool {
+        (self.first..=self.last).contains(&now.date_naive())
+    }
+}
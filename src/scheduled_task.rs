@@ -0,0 +1,70 @@
+use crate::{
+    cli::{self, RegisterTaskCommand},
+    event_log::EVENT_SOURCE,
+};
+use anyhow::{anyhow, Result};
+
+/// Creates a daily Windows Scheduled Task running the updater with the global options of this invocation.
+pub async fn register(command: &RegisterTaskCommand) -> Result<()> {
+    let RegisterTaskCommand {
+        task_name,
+        time,
+        user,
+    } = command;
+
+    cli::print_header();
+
+    if !cfg!(windows) {
+        return Err(anyhow!(
+            "scheduled tasks are only available on Windows - use a systemd timer or cron elsewhere"
+        ));
+    }
+
+    let executable = std::env::current_exe()?;
+    // Options given before the subcommand are the ones the task should run with.
+    let mut task_args = std::env::args()
+        .skip(1)
+        .take_while(|arg| arg != "register-task")
+        .collect::<Vec<_>>();
+    if !task_args.iter().any(|arg| arg == "--event-log") {
+        task_args.push(String::from("--event-log"));
+    }
+
+    let task_run = std::iter::once(executable.to_string_lossy().into_owned())
+        .chain(task_args)
+        .map(|arg| quote(&arg))
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    let status = tokio::process::Command::new("schtasks")
+        .args(["/Create", "/F", "/TN", task_name, "/TR", &task_run])
+        .args(["/SC", "DAILY", "/ST", time, "/RU", user, "/RL", "HIGHEST"])
+        .status()
+        .await
+        .map_err(|e| anyhow!("failed to run schtasks: {}", e))?;
+
+    if !status.success() {
+        return Err(anyhow!("schtasks failed with {}", status));
+    }
+
+    println!(
+        "✅ Scheduled task {} runs daily at {} as {}",
+        task_name, time, user
+    );
+    println!("   ↳ {}", task_run);
+    println!(
+        "   ↳ results are written to the Application event log with source {}",
+        EVENT_SOURCE
+    );
+
+    Ok(())
+}
+
+/// Quotes an argument for the command line of the task, unless it doesn't need it.
+fn quote(arg: &str) -> String {
+    if !arg.is_empty() && !arg.contains([' ', '\t', '"']) {
+        return arg.to_owned();
+    }
+
+    format!("\"{}\"", arg.replace('"', "\\\""))
+}
@@ -0,0 +1,49 @@
+use crate::{cli::Config, local, privileged};
+use anyhow::Result;
+
+/// Labels a new release so a confined server can run from it. Extracted files would otherwise keep the labels of the work directory.
+pub async fn relabel(config: &Config, published_version: &semver::Version) -> Result<()> {
+    let Config {
+        selinux_relabel,
+        selinux_context,
+        ..
+    } = config;
+
+    if !selinux_relabel && selinux_context.is_none() {
+        return Ok(());
+    }
+
+    let release_path = local::release_path(config, published_version)?;
+
+    match selinux_context {
+        Some(context) => {
+            print!(
+                "🛡️ Setting SELinux context of {} to {}... ",
+                release_path.to_string_lossy(),
+                context
+            );
+            privileged::relabel_tree(config, Some(context), &release_path).await?;
+        }
+        None => {
+            print!(
+                "🛡️ Restoring SELinux contexts of {}... ",
+                release_path.to_string_lossy()
+            );
+            privileged::relabel_tree(config, None, &release_path).await?;
+        }
+    }
+    println!("✅");
+
+    Ok(())
+}
+
+/// Whether SELinux is enabled and enforcing on this host.
+#[cfg(target_os = "linux")]
+pub fn enforcing() -> bool {
+    std::fs::read_to_string("/sys/fs/selinux/enforce").is_ok_and(|mode| mode.trim() == "1")
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn enforcing() -> bool {
+    false
+}
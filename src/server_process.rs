@@ -0,0 +1,100 @@
+use crate::{cli::Config, local};
+use anyhow::{anyhow, Result};
+use semver::Version;
+use std::path::Path;
+
+/// Written into the server directory by `ts3server_startscript.sh`.
+const PID_FILE_NAME: &str = "ts3server.pid";
+
+/// Refuses to swap releases while a server runs from the installed one, which would corrupt its database.
+///
+/// It runs after `pre-swap` hooks and `--startscript`, so a server they stop doesn't count. `--force` swaps anyway.
+pub async fn ensure_stopped(config: &Config, installed_version: Option<&Version>) -> Result<()> {
+    let installed_version = match installed_version {
+        Some(installed_version) => installed_version,
+        None => return Ok(()),
+    };
+
+    let release_path = local::release_path(config, installed_version)?;
+    let pids = tokio::task::spawn_blocking(move || running(&release_path)).await?;
+    if pids.is_empty() {
+        return Ok(());
+    }
+
+    let pids = pids
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(", ");
+    if config.force {
+        println!(
+            "⚠️ TeamSpeak {} is still running (pid {}) - swapping anyway because of --force",
+            installed_version, pids
+        );
+        return Ok(());
+    }
+
+    Err(anyhow!(
+        "TeamSpeak {} is still running (pid {}) - stop it before updating, e.g. in a pre-swap hook, or pass --force to update under the running server",
+        installed_version,
+        pids
+    ))
+}
+
+/// Processes of servers running from the release, found through its pidfile and, on Linux, their executables.
+fn running(release_path: &Path) -> Vec<u32> {
+    let release_path = release_path
+        .canonicalize()
+        .unwrap_or_else(|_| release_path.to_owned());
+    let mut pids = vec![];
+
+    if let Some(pid) = pid_file_pid(&release_path) {
+        pids.push(pid);
+    }
+
+    #[cfg(target_os = "linux")]
+    if let Ok(read_dir) = std::fs::read_dir("/proc") {
+        for entry in read_dir.flatten() {
+            let pid = match entry.file_name().to_str().and_then(|pid| pid.parse().ok()) {
+                Some(pid) => pid,
+                None => continue,
+            };
+            // Executables of other users can't be read without privileges, their servers go unnoticed.
+            if let Ok(exe) = std::fs::read_link(entry.path().join("exe")) {
+                if exe.starts_with(&release_path) {
+                    pids.push(pid);
+                }
+            }
+        }
+    }
+
+    pids.sort_unstable();
+    pids.dedup();
+    pids
+}
+
+/// Process named in the pidfile of the release, if it is alive. Servers which crashed leave stale pidfiles behind.
+pub fn pid_file_pid(release_path: &Path) -> Option<u32> {
+    std::fs::read_to_string(release_path.join(PID_FILE_NAME))
+        .ok()
+        .and_then(|pid| pid.trim().parse().ok())
+        .filter(|pid| is_alive(*pid) == Some(true))
+}
+
+/// Whether a process exists, `None` when this can't be checked on this platform.
+#[cfg(unix)]
+pub fn is_alive(pid: u32) -> Option<bool> {
+    use nix::{errno::Errno, sys::signal::kill, unistd::Pid};
+
+    let pid = i32::try_from(pid).ok()?;
+    // Signal 0 only checks whether the process exists; EPERM means it does, under another user.
+    Some(matches!(
+        kill(Pid::from_raw(pid), None),
+        Ok(()) | Err(Errno::EPERM)
+    ))
+}
+
+#[cfg(not(unix))]
+pub fn is_alive(_pid: u32) -> Option<bool> {
+    None
+}
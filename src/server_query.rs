@@ -0,0 +1,194 @@
+use crate::cli::Config;
+use anyhow::{anyhow, Result};
+use std::collections::HashMap;
+use thiserror::Error;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::TcpStream,
+};
+
+/// Answers to single commands are expected well within this time, including snapshots of big servers.
+const TIMEOUT: std::time::Duration = std::time::Duration::from_secs(60);
+
+#[derive(Debug, Error)]
+#[error("ServerQuery error {id}: {message}")]
+pub struct QueryError {
+    id: u32,
+    message: String,
+}
+
+/// Connection to the raw ServerQuery interface of a TeamSpeak 3 server.
+pub struct Client {
+    stream: BufReader<TcpStream>,
+}
+
+impl Client {
+    /// Connects to `--query-address` and logs in with the configured credentials.
+    pub async fn login(config: &Config) -> Result<Self> {
+        let Config {
+            query_address,
+            query_user,
+            query_password,
+            ..
+        } = config;
+
+        let password = query_password
+            .as_deref()
+            .ok_or_else(|| anyhow!("ServerQuery requires --query-password"))?;
+        let stream = tokio::time::timeout(TIMEOUT, TcpStream::connect(query_address))
+            .await
+            .map_err(|_| anyhow!("connecting to ServerQuery at {} timed out", query_address))?
+            .map_err(|e| {
+                anyhow!(
+                    "failed to connect to ServerQuery at {}: {}",
+                    query_address,
+                    e
+                )
+            })?;
+
+        let mut client = Self {
+            stream: BufReader::new(stream),
+        };
+        let greeting = client.read_line().await?;
+        if greeting != "TS3" {
+            return Err(anyhow!(
+                "{} is not a TeamSpeak 3 ServerQuery interface",
+                query_address
+            ));
+        }
+        // Skip the welcome message.
+        client.read_line().await?;
+
+        client
+            .command(&format!(
+                "login client_login_name={} client_login_password={}",
+                escape(query_user),
+                escape(password)
+            ))
+            .await?;
+
+        Ok(client)
+    }
+
+    /// Sends a command, returning the lines of its answer with the final status line stripped.
+    pub async fn command(&mut self, command: &str) -> Result<Vec<String>> {
+        self.stream
+            .get_mut()
+            .write_all(format!("{}\n", command).as_bytes())
+            .await?;
+
+        let mut lines = vec![];
+        loop {
+            let line = self.read_line().await?;
+            if !line.starts_with("error ") {
+                lines.push(line);
+                continue;
+            }
+
+            let status = parse(&line).into_iter().next().unwrap_or_default();
+            let id = status
+                .get("id")
+                .and_then(|id| id.parse().ok())
+                .unwrap_or(u32::MAX);
+            if id != 0 {
+                let message = status.get("msg").cloned().unwrap_or_default();
+                return Err(QueryError { id, message }.into());
+            }
+
+            return Ok(lines);
+        }
+    }
+
+    /// IDs of all virtual servers.
+    pub async fn server_ids(&mut self) -> Result<Vec<u64>> {
+        let lines = self.command("serverlist").await?;
+
+        lines
+            .iter()
+            .flat_map(|line| parse(line))
+            .map(|server| {
+                server
+                    .get("virtualserver_id")
+                    .and_then(|id| id.parse().ok())
+                    .ok_or_else(|| anyhow!("unexpected serverlist answer"))
+            })
+            .collect()
+    }
+
+    pub async fn quit(mut self) {
+        let _ = self.stream.get_mut().write_all(b"quit\n").await;
+    }
+
+    async fn read_line(&mut self) -> Result<String> {
+        let mut line = vec![];
+        let read = tokio::time::timeout(TIMEOUT, self.stream.read_until(b'\n', &mut line))
+            .await
+            .map_err(|_| anyhow!("ServerQuery did not answer in time"))??;
+        if read == 0 {
+            return Err(anyhow!("ServerQuery closed the connection"));
+        }
+
+        // Lines end with `\n\r`, so the `\r` of the previous line starts the next one.
+        Ok(String::from_utf8_lossy(&line)
+            .trim_matches(['\r', '\n'])
+            .to_owned())
+    }
+}
+
+/// Escapes a parameter value.
+pub fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '/' => escaped.push_str("\\/"),
+            ' ' => escaped.push_str("\\s"),
+            '|' => escaped.push_str("\\p"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+fn unescape(value: &str) -> String {
+    let mut unescaped = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('s') => unescaped.push(' '),
+            Some('p') => unescaped.push('|'),
+            Some('n') => unescaped.push('\n'),
+            Some('r') => unescaped.push('\r'),
+            Some('t') => unescaped.push('\t'),
+            Some(c) => unescaped.push(c),
+            None => {}
+        }
+    }
+
+    unescaped
+}
+
+/// Splits an answer line into its `|` separated entries of `key=value` pairs.
+fn parse(line: &str) -> Vec<HashMap<String, String>> {
+    line.split('|')
+        .map(|entry| {
+            entry
+                .split(' ')
+                .filter(|pair| !pair.is_empty())
+                .map(|pair| match pair.split_once('=') {
+                    Some((key, value)) => (key.to_owned(), unescape(value)),
+                    None => (pair.to_owned(), String::new()),
+                })
+                .collect()
+        })
+        .collect()
+}
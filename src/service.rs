@@ -0,0 +1,243 @@
+use crate::{cli::Config, local, server_process};
+use anyhow::{anyhow, Result};
+use semver::Version;
+use std::{path::Path, time::Duration};
+
+const STARTSCRIPT_FILE_NAME: &str = "ts3server_startscript.sh";
+const LAUNCH_DAEMONS_DIR: &str = "/Library/LaunchDaemons";
+/// Servers flush their database on shutdown, which takes a while for big ones.
+const STOP_TIMEOUT: Duration = Duration::from_secs(60);
+const START_TIMEOUT: Duration = Duration::from_secs(30);
+/// Servers with a broken configuration or license exit shortly after writing their pidfile.
+const START_SETTLE_TIME: Duration = Duration::from_secs(3);
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// How the server gets stopped and started around the swap.
+enum Integration<'a> {
+    StartScript,
+    Launchd {
+        label: &'a str,
+    },
+    RcService {
+        name: &'a str,
+        jail: Option<&'a str>,
+    },
+}
+
+impl<'a> Integration<'a> {
+    fn of(config: &'a Config) -> Option<Self> {
+        if config.startscript {
+            return Some(Self::StartScript);
+        }
+
+        if let Some(label) = &config.launchd_label {
+            return Some(Self::Launchd { label });
+        }
+
+        config.rc_service.as_deref().map(|name| Self::RcService {
+            name,
+            jail: config.jail.as_deref(),
+        })
+    }
+
+    /// Whether the server of the release is running, as far as the integration can tell.
+    async fn running(&self, release_path: &Path) -> Result<bool> {
+        match self {
+            Self::StartScript => Ok(server_process::pid_file_pid(release_path).is_some()),
+            Self::Launchd { label } => Ok(launchd_pid(label).await?.is_some()),
+            // rc.d scripts exit with a non-zero status when the service isn't running.
+            Self::RcService { name, jail } => Ok(rc_service(name, *jail, "status").await.is_ok()),
+        }
+    }
+}
+
+/// Stops the server of the installed release through the configured integration before the swap.
+/// Returns whether it was running and got stopped, so it's started again afterwards.
+pub async fn stop(config: &Config, installed_version: Option<&Version>) -> Result<bool> {
+    let (integration, installed_version) = match (Integration::of(config), installed_version) {
+        (Some(integration), Some(installed_version)) => (integration, installed_version),
+        _ => return Ok(false),
+    };
+
+    let release_path = local::release_path(config, installed_version)?;
+    if !integration.running(&release_path).await? {
+        println!("⏹️ TeamSpeak {} is not running", installed_version);
+        return Ok(false);
+    }
+
+    println!("⏹️ Stopping TeamSpeak {}", installed_version);
+    match &integration {
+        Integration::StartScript => run_startscript(&release_path, "stop").await?,
+        Integration::Launchd { label } => launchctl(&["unload", &plist_path(label)]).await?,
+        Integration::RcService { name, jail } => rc_service(name, *jail, "stop").await?,
+    }
+
+    let started_at = std::time::Instant::now();
+    while integration.running(&release_path).await? {
+        if started_at.elapsed() >= STOP_TIMEOUT {
+            return Err(anyhow!(
+                "TeamSpeak {} is still running {} after stopping it",
+                installed_version,
+                humantime::format_duration(STOP_TIMEOUT)
+            ));
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+
+    Ok(true)
+}
+
+/// Starts the server of `version` through the configured integration and makes sure it keeps running.
+pub async fn start(config: &Config, version: &Version) -> Result<()> {
+    let integration = match Integration::of(config) {
+        Some(integration) => integration,
+        None => return Ok(()),
+    };
+    let release_path = local::release_path(config, version)?;
+
+    println!("▶️ Starting TeamSpeak {}", version);
+    match &integration {
+        Integration::StartScript => run_startscript(&release_path, "start").await?,
+        Integration::Launchd { label } => launchctl(&["load", &plist_path(label)]).await?,
+        Integration::RcService { name, jail } => rc_service(name, *jail, "start").await?,
+    }
+
+    let started_at = std::time::Instant::now();
+    while !integration.running(&release_path).await? {
+        if started_at.elapsed() >= START_TIMEOUT {
+            return Err(anyhow!(
+                "TeamSpeak {} did not start within {}",
+                version,
+                humantime::format_duration(START_TIMEOUT)
+            ));
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+
+    tokio::time::sleep(START_SETTLE_TIME).await;
+    if !integration.running(&release_path).await? {
+        return Err(anyhow!(
+            "TeamSpeak {} exited right after starting - check the logs in {}",
+            version,
+            release_path.join("logs").to_string_lossy()
+        ));
+    }
+
+    Ok(())
+}
+
+/// Starts the server of the installed release again when the update failed after stopping it.
+pub async fn restart_after_failure(config: &Config, installed_version: Option<&Version>) {
+    let installed_version = match installed_version {
+        Some(installed_version) => installed_version,
+        None => return,
+    };
+
+    println!(
+        "↩️ Update failed after stopping the server - starting TeamSpeak {} again",
+        installed_version
+    );
+    if let Err(e) = start(config, installed_version).await {
+        println!(
+            "⚠️ Failed to start TeamSpeak {} again: {:#}",
+            installed_version, e
+        );
+    }
+}
+
+/// Runs the stock start script of a release, from inside of it as the script expects.
+async fn run_startscript(release_path: &Path, action: &str) -> Result<()> {
+    let script = release_path.join(STARTSCRIPT_FILE_NAME);
+    let status = tokio::process::Command::new(&script)
+        .arg(action)
+        .current_dir(release_path)
+        .stdin(std::process::Stdio::null())
+        .status()
+        .await
+        .map_err(|e| anyhow!("failed to run {}: {}", script.to_string_lossy(), e))?;
+
+    if !status.success() {
+        return Err(anyhow!(
+            "{} {} failed with {}",
+            script.to_string_lossy(),
+            action,
+            status
+        ));
+    }
+
+    Ok(())
+}
+
+/// Where the LaunchDaemon with the label is installed.
+pub fn plist_path(label: &str) -> String {
+    format!("{}/{}.plist", LAUNCH_DAEMONS_DIR, label)
+}
+
+async fn launchctl(args: &[&str]) -> Result<()> {
+    let output = tokio::process::Command::new("launchctl")
+        .args(args)
+        .output()
+        .await
+        .map_err(|e| anyhow!("failed to run launchctl: {}", e))?;
+
+    // `launchctl load` and `unload` report some failures on stderr only, with a zero exit code.
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if !output.status.success() || !stderr.trim().is_empty() {
+        return Err(anyhow!(
+            "launchctl {} failed with {}: {}",
+            args.join(" "),
+            output.status,
+            stderr.trim()
+        ));
+    }
+
+    Ok(())
+}
+
+/// Process of the loaded job, `None` if the job isn't loaded or has no running process.
+async fn launchd_pid(label: &str) -> Result<Option<u32>> {
+    let output = tokio::process::Command::new("launchctl")
+        .args(["list", label])
+        .output()
+        .await
+        .map_err(|e| anyhow!("failed to run launchctl: {}", e))?;
+    if !output.status.success() {
+        return Ok(None);
+    }
+
+    // The job is printed as a property list with a `"PID" = 123;` line while it runs.
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix("\"PID\" = "))
+        .find_map(|pid| pid.trim_end_matches(';').parse().ok()))
+}
+
+/// Runs `service <name> <action>`, inside the jail with `jexec` if given.
+async fn rc_service(name: &str, jail: Option<&str>, action: &str) -> Result<()> {
+    let mut command = match jail {
+        Some(jail) => {
+            let mut command = tokio::process::Command::new("jexec");
+            command.args([jail, "service"]);
+            command
+        }
+        None => tokio::process::Command::new("service"),
+    };
+    let output = command
+        .args([name, action])
+        .stdin(std::process::Stdio::null())
+        .output()
+        .await
+        .map_err(|e| anyhow!("failed to run service: {}", e))?;
+
+    if !output.status.success() {
+        return Err(anyhow!(
+            "service {} {} failed with {}: {}",
+            name,
+            action,
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,381 @@
+use crate::{cli::Config, remote};
+use anyhow::{anyhow, Context, Result};
+use argh::{ArgsInfo, FlagInfo, FlagInfoKind, FromArgs, Optionality};
+use std::path::{Path, PathBuf};
+use toml::{Table, Value};
+
+const ENV_PREFIX: &str = "TS_UPDATER_";
+const CONFIG_FLAG: &str = "--config";
+const PROFILE_FLAG: &str = "--profile";
+/// Section of the config file holding the profiles, `[profile.<name>]`.
+const PROFILES_KEY: &str = "profile";
+
+/// Command line arguments completed with options from the config file and `TS_UPDATER_*` environment variables.
+///
+/// Options given on the command line win over the config file, which wins over the environment.
+pub fn args() -> Result<Vec<String>> {
+    // Options are parsed as UTF-8, so other arguments are reported instead of panicking like `std::env::args` does.
+    let args = std::env::args_os()
+        .map(|arg| {
+            arg.into_string()
+                .map_err(|arg| anyhow!("argument {} is not valid UTF-8", arg.to_string_lossy()))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    complete(args)
+}
+
+fn complete(mut args: Vec<String>) -> Result<Vec<String>> {
+    let info = Config::get_args_info();
+
+    let config_path = flag_value(&args, CONFIG_FLAG).map(PathBuf::from);
+    let profile = flag_value(&args, PROFILE_FLAG);
+    let file = match (&config_path, &profile) {
+        (Some(config_path), profile) => {
+            let file = load(config_path, info.flags, &info.commands)?;
+            apply_profile(config_path, file, profile.as_deref())?
+        }
+        (None, Some(profile)) => {
+            return Err(anyhow!(
+                "profile {} was selected, but no config file was given",
+                profile
+            ))
+        }
+        (None, None) => Table::new(),
+    };
+
+    // Global options have to precede the subcommand, while its own options follow its name.
+    let subcommand = args.iter().enumerate().skip(1).find_map(|(index, arg)| {
+        info.commands
+            .iter()
+            .find(|subcommand| subcommand.name == arg)
+            .map(|subcommand| (index, subcommand))
+    });
+
+    if let Some((index, subcommand)) = subcommand {
+        let section = file.get(subcommand.name).and_then(Value::as_table);
+        let missing = missing_options(
+            &args,
+            subcommand.command.flags,
+            section,
+            Some(subcommand.name),
+        )?;
+        args.splice(index + 1..index + 1, missing);
+    }
+
+    let missing = missing_options(&args, info.flags, Some(&file), None)?;
+    args.splice(1..1, missing);
+
+    Ok(args)
+}
+
+/// Parses arguments the same way `argh::from_env` does, moving credentials out of the mirror URL
+/// and applying defaults of the selected product.
+pub fn parse(args: &[String]) -> Config {
+    let command = Path::new(&args[0])
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or(&args[0]);
+    let args = args.iter().map(String::as_str).collect::<Vec<_>>();
+
+    let mut config = Config::from_args(&[command], &args[1..]).unwrap_or_else(|early_exit| {
+        std::process::exit(match early_exit.status {
+            Ok(()) => {
+                println!("{}", early_exit.output);
+                0
+            }
+            Err(()) => {
+                eprintln!(
+                    "{}\nRun {} --help for more information.",
+                    early_exit.output, command
+                );
+                1
+            }
+        })
+    });
+
+    // argh defaults can't depend on other options, so product specific ones replace them here.
+    let given = |flag: &str| args.contains(&flag);
+    if !given("--mirror-url") {
+        config.mirror_url = config.product.default_mirror_url().to_owned();
+    }
+    if !given("--filename-template") {
+        config.filename_template = config.product.default_filename_template().to_owned();
+    }
+
+    remote::take_url_credentials(&mut config);
+    config
+}
+
+/// Value of a global option from the command line or, failing that, its environment variable.
+fn flag_value(args: &[String], long: &str) -> Option<String> {
+    args.iter()
+        .position(|arg| arg == long)
+        .and_then(|index| args.get(index + 1))
+        .cloned()
+        .or_else(|| std::env::var(env_name(None, long)).ok())
+}
+
+fn load(
+    config_path: &Path,
+    flags: &[FlagInfo],
+    commands: &[argh::SubCommandInfo],
+) -> Result<Table> {
+    let contents = std::fs::read_to_string(config_path)
+        .with_context(|| format!("failed to read config file {}", config_path.display()))?;
+    let file = contents
+        .parse::<Table>()
+        .with_context(|| format!("config file {} is invalid", config_path.display()))?;
+
+    check_options(config_path, &file, flags, commands, true)?;
+
+    Ok(file)
+}
+
+/// Merges the options of the selected profile into the top-level ones, dropping all profiles.
+///
+/// Subcommand sections are merged option by option, so a profile only needs to name what differs.
+fn apply_profile(config_path: &Path, mut file: Table, profile: Option<&str>) -> Result<Table> {
+    let profiles = file.remove(PROFILES_KEY);
+    let profile = match profile {
+        Some(profile) => profile,
+        None => return Ok(file),
+    };
+
+    let options = profiles
+        .as_ref()
+        .and_then(Value::as_table)
+        .and_then(|profiles| profiles.get(profile))
+        .and_then(Value::as_table)
+        .ok_or_else(|| {
+            anyhow!(
+                "config file {} has no profile {}",
+                config_path.display(),
+                profile
+            )
+        })?;
+
+    for (key, value) in options {
+        match (file.get_mut(key), value) {
+            (Some(Value::Table(section)), Value::Table(options)) => {
+                section.extend(options.clone());
+            }
+            _ => {
+                file.insert(key.clone(), value.clone());
+            }
+        }
+    }
+
+    Ok(file)
+}
+
+/// Makes sure every key of the config file, or of one of its profiles, names an option.
+fn check_options(
+    config_path: &Path,
+    file: &Table,
+    flags: &[FlagInfo],
+    commands: &[argh::SubCommandInfo],
+    profiles: bool,
+) -> Result<()> {
+    for (key, value) in file {
+        if profiles && key == PROFILES_KEY {
+            let profiles = value.as_table().ok_or_else(|| {
+                anyhow!(
+                    "config file {} has to define profiles as [{}.<name>] sections",
+                    config_path.display(),
+                    PROFILES_KEY
+                )
+            })?;
+            for (name, profile) in profiles {
+                let profile = profile.as_table().ok_or_else(|| {
+                    anyhow!(
+                        "config file {} has to define profile {} as a section",
+                        config_path.display(),
+                        name
+                    )
+                })?;
+                check_options(config_path, profile, flags, commands, false)
+                    .with_context(|| format!("profile {} is invalid", name))?;
+            }
+            continue;
+        }
+
+        let known = match commands.iter().find(|subcommand| subcommand.name == key) {
+            Some(subcommand) => value.as_table().is_some_and(|section| {
+                section
+                    .keys()
+                    .all(|key| find_flag(subcommand.command.flags, key).is_some())
+            }),
+            None => find_flag(flags, key).is_some(),
+        };
+
+        if !known {
+            return Err(anyhow!(
+                "config file {} contains unknown option {}",
+                config_path.display(),
+                key
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+fn find_flag<'a>(flags: &'a [FlagInfo<'a>], key: &str) -> Option<&'a FlagInfo<'a>> {
+    let long = format!("--{}", key.replace('_', "-"));
+    flags
+        .iter()
+        .find(|flag| flag.long == long && flag.long != CONFIG_FLAG && flag.long != PROFILE_FLAG)
+}
+
+fn env_name(subcommand: Option<&str>, long: &str) -> String {
+    let option = long.trim_start_matches('-');
+    let name = match subcommand {
+        Some(subcommand) => format!("{}{}_{}", ENV_PREFIX, subcommand, option),
+        None => format!("{}{}", ENV_PREFIX, option),
+    };
+
+    name.replace('-', "_").to_uppercase()
+}
+
+fn missing_options(
+    args: &[String],
+    flags: &[FlagInfo],
+    table: Option<&Table>,
+    subcommand: Option<&str>,
+) -> Result<Vec<String>> {
+    let mut missing = vec![];
+
+    for flag in flags {
+        if flag.long == "--help"
+            || flag.long == CONFIG_FLAG
+            || args.iter().any(|arg| arg == flag.long)
+        {
+            continue;
+        }
+
+        let from_file = table.and_then(|table| {
+            table
+                .iter()
+                .find(|(key, _)| find_flag(std::slice::from_ref(flag), key).is_some())
+        });
+        let values = match from_file {
+            Some((key, value)) => file_values(key, value)?,
+            None => match std::env::var(env_name(subcommand, flag.long)) {
+                Ok(value) if matches!(flag.optionality, Optionality::Repeating) => value
+                    .split(',')
+                    .map(|value| value.trim().to_owned())
+                    .filter(|value| !value.is_empty())
+                    .collect(),
+                Ok(value) => vec![value],
+                Err(_) => continue,
+            },
+        };
+
+        match flag.kind {
+            FlagInfoKind::Switch => {
+                if values
+                    .iter()
+                    .any(|value| ["1", "true", "yes"].contains(&value.to_lowercase().as_str()))
+                {
+                    missing.push(flag.long.to_owned());
+                }
+            }
+            FlagInfoKind::Option { .. } => {
+                for value in values {
+                    missing.push(flag.long.to_owned());
+                    missing.push(value);
+                }
+            }
+        }
+    }
+
+    Ok(missing)
+}
+
+fn file_values(key: &str, value: &Value) -> Result<Vec<String>> {
+    match value {
+        Value::String(value) => Ok(vec![value.clone()]),
+        Value::Integer(value) => Ok(vec![value.to_string()]),
+        Value::Float(value) => Ok(vec![value.to_string()]),
+        Value::Boolean(value) => Ok(vec![value.to_string()]),
+        Value::Array(values) => values
+            .iter()
+            .map(|value| file_values(key, value))
+            .collect::<Result<Vec<_>>>()
+            .map(|values| values.concat()),
+        _ => Err(anyhow!("config file option {} has unsupported value", key)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn config_file(contents: &str) -> tempfile::NamedTempFile {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(contents.as_bytes()).unwrap();
+        file
+    }
+
+    fn completed(args: &[&str]) -> Config {
+        let args = args.iter().map(|arg| arg.to_string()).collect();
+        parse(&complete(args).unwrap())
+    }
+
+    #[test]
+    fn command_line_wins_over_file_which_wins_over_environment() {
+        let file = config_file("user_agent = \"file\"\nmirror_token = \"file\"\n");
+        let path = file.path().to_str().unwrap();
+        // The only test reading these variables, so setting them doesn't race with the others.
+        std::env::set_var("TS_UPDATER_USER_AGENT", "env");
+        std::env::set_var("TS_UPDATER_MIRROR_TOKEN", "env");
+        std::env::set_var("TS_UPDATER_LAG", "2");
+
+        let config = completed(&["teamspeak-updater", "--config", path, "--user-agent", "cli"]);
+
+        assert_eq!(config.user_agent.as_deref(), Some("cli"));
+        assert_eq!(config.mirror_token.as_deref(), Some("file"));
+        assert_eq!(config.lag, 2);
+    }
+
+    #[test]
+    fn profile_overrides_options_and_merges_sections() {
+        let file = config_file(
+            "user_agent = \"base\"\nlag = 1\n\n[clean]\nkeep_backups = 2\ndry_run = true\n\n\
+             [profile.staging]\nuser_agent = \"staging\"\n\n[profile.staging.clean]\nkeep_backups = 5\n",
+        );
+        let info = Config::get_args_info();
+        let table = load(file.path(), info.flags, &info.commands).unwrap();
+
+        let merged = apply_profile(file.path(), table.clone(), Some("staging")).unwrap();
+
+        assert_eq!(merged["user_agent"].as_str(), Some("staging"));
+        assert_eq!(merged["lag"].as_integer(), Some(1));
+        assert_eq!(merged["clean"]["keep_backups"].as_integer(), Some(5));
+        assert_eq!(merged["clean"]["dry_run"].as_bool(), Some(true));
+        assert!(!merged.contains_key(PROFILES_KEY));
+
+        let unselected = apply_profile(file.path(), table.clone(), None).unwrap();
+        assert_eq!(unselected["user_agent"].as_str(), Some("base"));
+        assert!(apply_profile(file.path(), table, Some("production")).is_err());
+    }
+
+    #[test]
+    fn unknown_options_are_refused() {
+        let info = Config::get_args_info();
+        for contents in [
+            "user_agnet = \"typo\"\n",
+            "[clean]\nkeep_backup = 2\n",
+            "[profile.staging]\nmirror = \"typo\"\n",
+            "config = \"other.toml\"\n",
+        ] {
+            let file = config_file(contents);
+            assert!(
+                load(file.path(), info.flags, &info.commands).is_err(),
+                "{} was accepted",
+                contents
+            );
+        }
+    }
+}
@@ -0,0 +1,87 @@
+use crate::{cli::Config, local};
+use anyhow::Result;
+use std::path::Path;
+
+/// Server configuration files which are moved into the shared directory when it doesn't have them yet.
+pub const CONFIG_FILE_NAMES: &[&str] = &[
+    "ts3server.ini",
+    "query_ip_allowlist.txt",
+    "query_ip_denylist.txt",
+    "query_ip_whitelist.txt",
+    "query_ip_blacklist.txt",
+];
+
+/// Symlinks every file of the shared config directory into the extracted, not yet activated release.
+pub async fn link(config: &Config, published_version: &semver::Version) -> Result<()> {
+    use std::io::ErrorKind;
+    use tokio::fs;
+
+    let Config {
+        shared_config_dir,
+        symlink_path,
+        ..
+    } = config;
+    let shared_config_dir = match shared_config_dir {
+        Some(shared_config_dir) => fs::canonicalize(shared_config_dir).await?,
+        None => return Ok(()),
+    };
+
+    let installed_dir = local::in_place_dir(config).unwrap_or(symlink_path);
+    adopt_config_files(installed_dir, &shared_config_dir).await?;
+
+    let staged_path = local::staged_release_path(config, published_version)?;
+    let mut linked = 0;
+    let mut read_dir = fs::read_dir(&shared_config_dir).await?;
+
+    while let Some(entry) = read_dir.next_entry().await? {
+        if !entry.file_type().await?.is_file() {
+            continue;
+        }
+
+        let release_file = staged_path.join(entry.file_name());
+        match fs::remove_file(&release_file).await {
+            Err(e) if e.kind() != ErrorKind::NotFound => return Err(e.into()),
+            _ => {}
+        }
+
+        #[cfg(unix)]
+        fs::symlink(entry.path(), &release_file).await?;
+        #[cfg(windows)]
+        fs::symlink_file(entry.path(), &release_file).await?;
+        linked += 1;
+    }
+
+    println!(
+        "🗂️ Linked {} shared config file(s) from {}",
+        linked,
+        shared_config_dir.to_string_lossy()
+    );
+
+    Ok(())
+}
+
+/// Copies configuration files of the running installation which the shared directory lacks, so they survive the update.
+async fn adopt_config_files(installed_dir: &Path, shared_config_dir: &Path) -> Result<()> {
+    use tokio::fs;
+
+    for file_name in CONFIG_FILE_NAMES {
+        let shared_file = shared_config_dir.join(file_name);
+        if fs::symlink_metadata(&shared_file).await.is_ok() {
+            continue;
+        }
+
+        let installed_file = installed_dir.join(file_name);
+        match fs::symlink_metadata(&installed_file).await {
+            Ok(metadata) if metadata.is_file() => {
+                fs::copy(&installed_file, &shared_file).await?;
+                println!(
+                    "📋 Copied {} into the shared config directory",
+                    installed_file.to_string_lossy()
+                );
+            }
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,90 @@
+use crate::{
+    cli::{self, Config, SidecarCommand},
+    lint, local, lock, remote,
+    update::{self, Outcome},
+};
+use anyhow::{anyhow, Result};
+use std::path::Path;
+
+const READY_FILE_NAME: &str = ".ts-updater-ready";
+
+/// Keeps the server files in `--target-dir` current, as a Kubernetes initContainer (`--once`) or a sidecar.
+///
+/// Replicas sharing the volume take turns through the lock file, and the readiness file is present only
+/// while the files are complete.
+pub async fn run(config: &mut Config, command: &SidecarCommand) -> Result<()> {
+    let SidecarCommand {
+        once,
+        interval,
+        ready_file,
+    } = command;
+
+    cli::print_header();
+    lint::validate(config)?;
+
+    let config = &*config;
+    let target_dir = local::in_place_dir(config).ok_or_else(|| {
+        anyhow!("sidecar mode updates a shared volume in place - pass --install-mode in-place --target-dir <volume>")
+    })?;
+    let ready_file = ready_file
+        .clone()
+        .unwrap_or_else(|| target_dir.join(READY_FILE_NAME));
+    let http = remote::client(config)?;
+
+    config.print_summary();
+    if !once {
+        println!("Check interval: {}", interval);
+    }
+    println!("Readiness file: {}", ready_file.to_string_lossy());
+    println!();
+    lint::check(config)?;
+
+    loop {
+        let result = check(config, &http, &ready_file).await;
+        if *once {
+            return result;
+        }
+        if let Err(e) = result {
+            println!("❌ {:#}", e);
+        }
+
+        println!(
+            "💤 Next check in {}",
+            humantime::format_duration(std::time::Duration::from_secs(interval.as_secs()))
+        );
+        println!();
+        tokio::time::sleep(**interval).await;
+    }
+}
+
+async fn check(config: &Config, http: &reqwest::Client, ready_file: &Path) -> Result<()> {
+    let _lock = lock::acquire(config).await?;
+
+    // Another replica may have updated the volume while this one waited for the lock.
+    let (installed_version, published_version) = update::fetch_versions(config, http).await?;
+    if installed_version.as_ref() < Some(&published_version) {
+        remove_ready_file(ready_file).await?;
+    }
+
+    let result = update::run_once_locked(config, http, true).await;
+
+    // Interrupted in-place updates leave the old files in place, so the volume is usable whenever a version is installed.
+    match local::installed_version(config).await? {
+        Some(version) => {
+            tokio::fs::write(ready_file, format!("{}\n", version)).await?;
+            if let Ok(Outcome::Updated) = &result {
+                println!("🟢 Server files of {} are ready", version);
+            }
+        }
+        None => remove_ready_file(ready_file).await?,
+    }
+
+    result.map(|_| ())
+}
+
+async fn remove_ready_file(ready_file: &Path) -> Result<()> {
+    match tokio::fs::remove_file(ready_file).await {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
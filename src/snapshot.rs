@@ -0,0 +1,445 @@
+use crate::{
+    cli::{self, Config, RestoreCommand, RestoreSnapshotCommand, SnapshotCommand},
+    database::{self, Backend, SQLITE_FILE_NAMES},
+    license::LICENSE_FILE_NAME,
+    lint, local, server_query,
+    shared_config::CONFIG_FILE_NAMES,
+    state,
+};
+use anyhow::{anyhow, Result};
+use semver::Version;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+const SNAPSHOTS_DIR_NAME: &str = ".snapshots";
+const METADATA_FILE_NAME: &str = "snapshot.json";
+const ARCHIVE_FILE_NAME: &str = "state.tar.gz";
+const DATABASE_DUMP_FILE_NAME: &str = "database.sql";
+
+/// Files and directories the server writes to besides the database, relative to the release directory.
+const STATE_PATHS: &[&str] = &["files", LICENSE_FILE_NAME];
+
+#[derive(Serialize, Deserialize)]
+struct Metadata {
+    id: String,
+    version: String,
+    created_at: u64,
+    source: PathBuf,
+    /// Paths in the archive, relative to the release directory.
+    paths: Vec<PathBuf>,
+    /// Virtual servers with a ServerQuery snapshot, taken under `--query-snapshot`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    virtual_servers: Vec<u64>,
+    /// Whether the snapshot holds a `mysqldump` of the MariaDB database, taken under `--mysqldump`.
+    #[serde(default)]
+    database_dump: bool,
+}
+
+fn server_snapshot_file_name(server_id: u64) -> String {
+    format!("virtualserver_{}.snapshot", server_id)
+}
+
+pub fn snapshots_dir(config: &Config) -> PathBuf {
+    config.snapshots_dir.clone().unwrap_or_else(|| {
+        local::in_place_dir(config)
+            .unwrap_or(&config.releases_path)
+            .join(SNAPSHOTS_DIR_NAME)
+    })
+}
+
+pub async fn run(config: &mut Config, command: &SnapshotCommand) -> Result<()> {
+    cli::print_header();
+    lint::validate(config)?;
+    let config = &*config;
+
+    if command.list {
+        return list(config).await;
+    }
+
+    let version = local::installed_version(config)
+        .await?
+        .ok_or_else(|| anyhow!("no release is installed"))?;
+    println!("📸 Taking snapshot of release {}...", version);
+    let id = take(config, &version).await?;
+    println!("📸 Snapshot {} taken", id);
+
+    Ok(())
+}
+
+/// Snapshots the state of the active release before it gets replaced, under `--snapshot-before-update`.
+/// Returns the snapshot ID, if one was taken. Nothing is printed, as the download runs alongside.
+pub async fn before_update(
+    config: &Config,
+    installed_version: Option<&Version>,
+) -> Result<Option<String>> {
+    match installed_version {
+        Some(installed_version) if config.snapshot_before_update => {
+            // A server that never ran has nothing worth keeping.
+            let release_path = local::release_path(config, installed_version)?;
+            let backend = Backend::detect(&release_path)?;
+            if !has_state(config, &release_path, &backend) {
+                return Ok(None);
+            }
+            take(config, installed_version).await.map(Some)
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Prints the outcome of [`before_update`].
+pub fn print_before_update(config: &Config, id: Option<&str>) {
+    match id {
+        Some(id) => println!("📸 Snapshot {} taken", id),
+        None if config.snapshot_before_update => println!("📸 No server state to snapshot yet."),
+        None => {}
+    }
+}
+
+/// Archives the state of the release of `version`, returning the snapshot ID.
+async fn take(config: &Config, version: &Version) -> Result<String> {
+    let release_path = local::release_path(config, version)?;
+    let created_at = state::unix_timestamp();
+    let taken_at = chrono::Utc::now().format("%Y%m%dT%H%M%SZ");
+
+    let backend = Backend::detect(&release_path)?;
+    let paths = state_paths(config, &release_path, &backend);
+    if !has_state(config, &release_path, &backend) {
+        return Err(anyhow!(
+            "release {} has no server state to snapshot",
+            version
+        ));
+    }
+
+    tokio::fs::create_dir_all(snapshots_dir(config)).await?;
+    // Snapshots taken within the same second get a counter appended.
+    let mut id = format!("{}-{}", taken_at, version);
+    let mut counter = 1;
+    let snapshot_dir = loop {
+        let snapshot_dir = snapshots_dir(config).join(&id);
+        match tokio::fs::create_dir(&snapshot_dir).await {
+            Ok(()) => break snapshot_dir,
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                counter += 1;
+                id = format!("{}-{}.{}", taken_at, version, counter);
+            }
+            Err(e) => return Err(e.into()),
+        }
+    };
+
+    let taken = async {
+        if !paths.is_empty() {
+            let archive_path = snapshot_dir.join(ARCHIVE_FILE_NAME);
+            let (release_path, paths) = (release_path.clone(), paths.clone());
+            tokio::task::spawn_blocking(move || archive(&release_path, &paths, &archive_path))
+                .await??;
+        }
+
+        let database_dump = match &backend {
+            Backend::MariaDb { settings, .. } if config.mysqldump => {
+                database::dump(settings, &snapshot_dir.join(DATABASE_DUMP_FILE_NAME)).await?;
+                true
+            }
+            _ => false,
+        };
+
+        let virtual_servers = if config.query_snapshot {
+            snapshot_servers(config, &snapshot_dir).await?
+        } else {
+            vec![]
+        };
+
+        let metadata = Metadata {
+            id: id.clone(),
+            version: version.to_string(),
+            created_at,
+            source: release_path,
+            paths,
+            virtual_servers,
+            database_dump,
+        };
+        tokio::fs::write(
+            snapshot_dir.join(METADATA_FILE_NAME),
+            serde_json::to_vec_pretty(&metadata)?,
+        )
+        .await?;
+
+        Ok::<_, anyhow::Error>(())
+    }
+    .await;
+    // A partial snapshot must not be mistaken for a usable one.
+    if let Err(e) = taken {
+        let _ = tokio::fs::remove_dir_all(&snapshot_dir).await;
+        return Err(e);
+    }
+
+    Ok(id)
+}
+
+/// Stores the answer of `serversnapshotcreate` for every virtual server, returning their IDs.
+///
+/// Unlike copies of the files, these also cover servers keeping their database in MariaDB.
+async fn snapshot_servers(config: &Config, snapshot_dir: &Path) -> Result<Vec<u64>> {
+    let mut client = server_query::Client::login(config).await?;
+
+    let server_ids = client.server_ids().await?;
+    for server_id in &server_ids {
+        client.command(&format!("use sid={}", server_id)).await?;
+        let snapshot = client
+            .command("serversnapshotcreate")
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow!("virtual server {} returned an empty snapshot", server_id))?;
+        tokio::fs::write(
+            snapshot_dir.join(server_snapshot_file_name(*server_id)),
+            snapshot,
+        )
+        .await?;
+    }
+    client.quit().await;
+
+    Ok(server_ids)
+}
+
+/// Whether a snapshot of the release would hold anything.
+fn has_state(config: &Config, release_path: &Path, backend: &Backend) -> bool {
+    !state_paths(config, release_path, backend).is_empty()
+        || config.query_snapshot
+        || (config.mysqldump && !backend.is_sqlite())
+}
+
+/// State paths present in the release directory. SQLite files are left out when the server uses MariaDB.
+fn state_paths(config: &Config, release_path: &Path, backend: &Backend) -> Vec<PathBuf> {
+    let database_paths = match backend {
+        Backend::Sqlite => SQLITE_FILE_NAMES.iter().map(PathBuf::from).collect(),
+        Backend::MariaDb { ini_file, .. } => vec![PathBuf::from(ini_file)],
+    };
+
+    STATE_PATHS
+        .iter()
+        .chain(CONFIG_FILE_NAMES)
+        .map(PathBuf::from)
+        .chain(database_paths)
+        .chain(config.preserve.iter().cloned())
+        .filter(|path| std::fs::symlink_metadata(release_path.join(path)).is_ok())
+        .fold(vec![], |mut paths, path| {
+            if !paths.contains(&path) {
+                paths.push(path);
+            }
+            paths
+        })
+}
+
+fn archive(release_path: &Path, paths: &[PathBuf], archive_path: &Path) -> Result<()> {
+    use flate2::{write::GzEncoder, Compression};
+
+    let archive = std::fs::File::create(archive_path)?;
+    let mut builder = tar::Builder::new(GzEncoder::new(archive, Compression::default()));
+    // The license and shared config are often symlinks to files kept outside of the release.
+    builder.follow_symlinks(false);
+
+    for path in paths {
+        let source = release_path.join(path);
+        if std::fs::symlink_metadata(&source)?.is_dir() {
+            builder.append_dir_all(path, &source)?;
+        } else {
+            builder.append_path_with_name(&source, path)?;
+        }
+    }
+
+    builder.into_inner()?.finish()?.sync_all()?;
+    Ok(())
+}
+
+async fn list(config: &Config) -> Result<()> {
+    let snapshots = snapshots(config).await?;
+    if snapshots.is_empty() {
+        println!(
+            "📸 No snapshots in {}",
+            snapshots_dir(config).to_string_lossy()
+        );
+        return Ok(());
+    }
+
+    println!(
+        "📸 Snapshots in {}",
+        snapshots_dir(config).to_string_lossy()
+    );
+    for Metadata {
+        id,
+        version,
+        created_at,
+        virtual_servers,
+        ..
+    } in snapshots
+    {
+        println!(
+            "   {}  version {}, taken {}",
+            id,
+            version,
+            state::format_timestamp(created_at)
+        );
+        if !virtual_servers.is_empty() {
+            println!(
+                "   ↳ ServerQuery snapshots of virtual servers {}",
+                virtual_servers
+                    .iter()
+                    .map(u64::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Snapshots found in the snapshots directory, oldest first.
+async fn snapshots(config: &Config) -> Result<Vec<Metadata>> {
+    use std::io::ErrorKind;
+
+    let mut read_dir = match tokio::fs::read_dir(snapshots_dir(config)).await {
+        Ok(read_dir) => read_dir,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(vec![]),
+        Err(e) => return Err(e.into()),
+    };
+
+    let mut snapshots = vec![];
+    while let Some(entry) = read_dir.next_entry().await? {
+        if let Ok(contents) = tokio::fs::read(entry.path().join(METADATA_FILE_NAME)).await {
+            snapshots.push(serde_json::from_slice::<Metadata>(&contents)?);
+        }
+    }
+    snapshots.sort_by_key(|snapshot| snapshot.created_at);
+
+    Ok(snapshots)
+}
+
+pub async fn restore(config: &mut Config, command: &RestoreCommand) -> Result<()> {
+    let RestoreCommand { id, version } = command;
+
+    cli::print_header();
+    lint::validate(config)?;
+    let config = &*config;
+
+    let (snapshot_dir, metadata) = load(config, id).await?;
+    if metadata.paths.is_empty() && !metadata.database_dump {
+        return Err(anyhow!(
+            "snapshot {} holds ServerQuery snapshots only - use `restore-snapshot`",
+            id
+        ));
+    }
+
+    let version = match version {
+        Some(version) => version.clone(),
+        None => local::installed_version(config)
+            .await?
+            .ok_or_else(|| anyhow!("no release is installed - pass --version"))?,
+    };
+    let release_path = local::release_path(config, &version)?;
+    if !release_path.is_dir() {
+        return Err(anyhow!("release {} is not installed", version));
+    }
+
+    println!(
+        "⚠️ Make sure the server is stopped - restoring state of {} taken {} into release {}",
+        metadata.version,
+        state::format_timestamp(metadata.created_at),
+        version
+    );
+    if !metadata.paths.is_empty() {
+        print!("⏪ Restoring snapshot {}... ", id);
+        let archive_path = snapshot_dir.join(ARCHIVE_FILE_NAME);
+        let paths = metadata.paths.clone();
+        tokio::task::spawn_blocking(move || unarchive(&archive_path, &paths, &release_path))
+            .await??;
+        println!("✅");
+    }
+
+    // Loading the dump needs credentials with more privileges than the server's, so it's left to the administrator.
+    if metadata.database_dump {
+        println!(
+            "🗄️ Load the database dump into MariaDB with `mysql <database> < {}`",
+            snapshot_dir.join(DATABASE_DUMP_FILE_NAME).to_string_lossy()
+        );
+    }
+
+    Ok(())
+}
+
+/// Deploys ServerQuery snapshots back into the running server with `serversnapshotdeploy`.
+pub async fn restore_servers(config: &mut Config, command: &RestoreSnapshotCommand) -> Result<()> {
+    let RestoreSnapshotCommand { id, server_id } = command;
+
+    cli::print_header();
+    lint::validate(config)?;
+    let config = &*config;
+
+    let (snapshot_dir, metadata) = load(config, id).await?;
+    let server_ids = match server_id {
+        Some(server_id) if metadata.virtual_servers.contains(server_id) => vec![*server_id],
+        Some(server_id) => {
+            return Err(anyhow!(
+                "snapshot {} has no ServerQuery snapshot of virtual server {}",
+                id,
+                server_id
+            ))
+        }
+        None if metadata.virtual_servers.is_empty() => {
+            return Err(anyhow!(
+                "snapshot {} has no ServerQuery snapshots - it was taken without --query-snapshot",
+                id
+            ))
+        }
+        None => metadata.virtual_servers,
+    };
+
+    let mut client = server_query::Client::login(config).await?;
+    for server_id in server_ids {
+        let snapshot =
+            tokio::fs::read_to_string(snapshot_dir.join(server_snapshot_file_name(server_id)))
+                .await?;
+
+        print!("⏪ Deploying snapshot of virtual server {}... ", server_id);
+        client.command(&format!("use sid={}", server_id)).await?;
+        client
+            .command(&format!("serversnapshotdeploy {}", snapshot))
+            .await?;
+        println!("✅");
+    }
+    client.quit().await;
+
+    Ok(())
+}
+
+async fn load(config: &Config, id: &str) -> Result<(PathBuf, Metadata)> {
+    let snapshot_dir = snapshots_dir(config).join(id);
+    match tokio::fs::read(snapshot_dir.join(METADATA_FILE_NAME)).await {
+        Ok(contents) => Ok((snapshot_dir, serde_json::from_slice(&contents)?)),
+        Err(_) => Err(anyhow!("snapshot {} does not exist", id)),
+    }
+}
+
+fn unarchive(archive_path: &Path, paths: &[PathBuf], release_path: &Path) -> Result<()> {
+    use flate2::read::GzDecoder;
+    use std::{fs, io::ErrorKind};
+
+    // Files created since the snapshot, e.g. uploads, must not survive the restore.
+    for path in paths {
+        let target = release_path.join(path);
+        let removed = match fs::symlink_metadata(&target) {
+            Ok(metadata) if metadata.is_dir() => fs::remove_dir_all(&target),
+            Ok(_) => fs::remove_file(&target),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(e),
+        };
+        removed.map_err(|e| anyhow!("{}: {}", target.to_string_lossy(), e))?;
+    }
+
+    let mut archive = tar::Archive::new(GzDecoder::new(fs::File::open(archive_path)?));
+    archive.set_preserve_permissions(true);
+    archive.set_preserve_ownerships(true);
+    archive.unpack(release_path)?;
+
+    Ok(())
+}
@@ -0,0 +1,293 @@
+use crate::{cli::Config, local};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::{path::PathBuf, time::Duration};
+
+const STATE_FILE_NAME: &str = ".ts-updater-state.json";
+
+/// Persistent updater state shared between runs.
+#[derive(Default, Serialize, Deserialize)]
+pub struct State {
+    #[serde(default)]
+    pub unavailable: Vec<Unavailable>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pending: Option<Pending>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_check: Option<LastCheck>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub daemon: Option<Daemon>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hold: Option<Hold>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub incomplete: Option<Incomplete>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub staged: Option<Staged>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mirror_backoff: Option<MirrorBackoff>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub notified: Vec<Notified>,
+}
+
+/// Version which archive was missing on the mirror for a given target tuple.
+#[derive(Serialize, Deserialize)]
+pub struct Unavailable {
+    pub version: String,
+    pub target: String,
+    pub since: u64,
+}
+
+/// Update waiting for a maintenance window in daemon mode.
+#[derive(Serialize, Deserialize)]
+pub struct Pending {
+    pub version: String,
+    pub since: u64,
+}
+
+/// Updates frozen with `hold` until `unhold`. New versions are still detected, but not installed.
+#[derive(Serialize, Deserialize)]
+pub struct Hold {
+    pub since: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+/// Release which extraction started, but didn't finish. Its directory is removed before the next extraction,
+/// in case removing it right away failed or the updater got killed.
+#[derive(Serialize, Deserialize)]
+pub struct Incomplete {
+    pub version: String,
+    pub path: PathBuf,
+    pub since: u64,
+    /// Error the extraction failed with, `None` while it runs or when the updater didn't get to record it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Release installed by `--stage-only`, activated by `commit` given the same token.
+#[derive(Serialize, Deserialize)]
+pub struct Staged {
+    pub version: String,
+    pub token: String,
+    pub since: u64,
+}
+
+/// Mirror asked to come back later with `Retry-After`. Update runs don't contact it until then.
+#[derive(Serialize, Deserialize)]
+pub struct MirrorBackoff {
+    pub until: u64,
+}
+
+/// Notification last sent about a `kind` of event, e.g. a failure, so the daemon repeats an unchanged one only
+/// as a reminder.
+#[derive(Serialize, Deserialize)]
+pub struct Notified {
+    pub kind: String,
+    /// What the notification was about, like the error or the pending version. A different one is sent right away.
+    pub key: String,
+    pub at: u64,
+    /// Times the same event was held back since.
+    #[serde(default)]
+    pub held_back: u32,
+}
+
+/// Outcome of the most recent update run.
+#[derive(Serialize, Deserialize)]
+pub struct LastCheck {
+    pub at: u64,
+    pub result: CheckResult,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub published_version: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckResult {
+    UpToDate,
+    Updated,
+    Pending,
+    Failed,
+}
+
+impl std::fmt::Display for CheckResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::UpToDate => "up to date",
+            Self::Updated => "updated",
+            Self::Pending => "update pending",
+            Self::Failed => "failed",
+        })
+    }
+}
+
+/// Heartbeat of a running daemon, refreshed before every sleep.
+#[derive(Serialize, Deserialize)]
+pub struct Daemon {
+    pub pid: u32,
+    pub started_at: u64,
+    pub next_check_at: u64,
+}
+
+pub fn state_path(config: &Config) -> PathBuf {
+    config.state_path.clone().unwrap_or_else(|| {
+        local::in_place_dir(config)
+            .unwrap_or(&config.releases_path)
+            .join(STATE_FILE_NAME)
+    })
+}
+
+pub fn unix_timestamp() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+/// Hex-encoded secret of `bytes` length from the random source of the operating system, e.g. the token a staged
+/// release is committed with.
+pub fn random_token(bytes: usize) -> Result<String> {
+    let mut token = vec![0; bytes];
+    getrandom::getrandom(&mut token)
+        .map_err(|e| anyhow::anyhow!("failed to generate a random token: {}", e))?;
+
+    Ok(hex::encode(token))
+}
+
+pub fn format_timestamp(unix_timestamp: u64) -> humantime::Rfc3339Timestamp {
+    humantime::format_rfc3339_seconds(std::time::UNIX_EPOCH + Duration::from_secs(unix_timestamp))
+}
+
+impl State {
+    pub async fn load(config: &Config) -> Result<Self> {
+        use std::io::ErrorKind;
+
+        match tokio::fs::read(state_path(config)).await {
+            Ok(contents) => Ok(serde_json::from_slice(&contents)?),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub async fn save(&self, config: &Config) -> Result<()> {
+        use tokio::fs;
+
+        let path = state_path(config);
+        let mut temp_path = path.clone().into_os_string();
+        temp_path.push(".tmp");
+
+        fs::write(&temp_path, serde_json::to_vec_pretty(self)?).await?;
+        fs::rename(&temp_path, &path).await?;
+
+        Ok(())
+    }
+
+    /// Returns timestamp since when the version is known to be unavailable, unless the record is older than `ttl`.
+    pub fn unavailable_since(&self, version: &str, target: &str, ttl: Duration) -> Option<u64> {
+        let now = unix_timestamp();
+
+        self.unavailable
+            .iter()
+            .find(|entry| entry.version == version && entry.target == target)
+            .map(|entry| entry.since)
+            .filter(|since| now.saturating_sub(*since) < ttl.as_secs())
+    }
+
+    /// Forgets the unavailability record, returning whether there was any.
+    pub fn mark_available(&mut self, version: &str, target: &str) -> bool {
+        let count = self.unavailable.len();
+        self.unavailable
+            .retain(|entry| !(entry.version == version && entry.target == target));
+        count != self.unavailable.len()
+    }
+
+    pub fn mark_unavailable(&mut self, version: &str, target: &str) {
+        self.mark_available(version, target);
+        self.unavailable.push(Unavailable {
+            version: version.to_owned(),
+            target: target.to_owned(),
+            since: unix_timestamp(),
+        });
+    }
+
+    /// Drops all records about the version, returning whether there were any.
+    pub fn forget(&mut self, version: &str) -> bool {
+        let count = self.unavailable.len();
+        self.unavailable.retain(|entry| entry.version != version);
+        let pending = self
+            .pending
+            .take_if(|pending| pending.version == version)
+            .is_some();
+        let staged = self
+            .staged
+            .take_if(|staged| staged.version == version)
+            .is_some();
+
+        pending || staged || count != self.unavailable.len()
+    }
+
+    /// Whether to notify about an event of `kind`: right away when `key` differs from the last notification,
+    /// otherwise once `interval` passed since it. Returns how often the event was held back in between.
+    pub fn digest(&mut self, kind: &str, key: &str, interval: Duration) -> Option<u32> {
+        let now = unix_timestamp();
+        match self
+            .notified
+            .iter_mut()
+            .find(|notified| notified.kind == kind)
+        {
+            Some(notified)
+                if notified.key == key && now.saturating_sub(notified.at) < interval.as_secs() =>
+            {
+                notified.held_back += 1;
+                None
+            }
+            Some(notified) => {
+                let held_back = if notified.key == key {
+                    notified.held_back
+                } else {
+                    0
+                };
+                *notified = Notified {
+                    kind: kind.to_owned(),
+                    key: key.to_owned(),
+                    at: now,
+                    held_back: 0,
+                };
+                Some(held_back)
+            }
+            None => {
+                self.notified.push(Notified {
+                    kind: kind.to_owned(),
+                    key: key.to_owned(),
+                    at: now,
+                    held_back: 0,
+                });
+                Some(0)
+            }
+        }
+    }
+
+    /// Forgets the last notification of `kind`, so the next event is sent right away. Returns whether there was one.
+    pub fn forget_notified(&mut self, kind: &str) -> bool {
+        let count = self.notified.len();
+        self.notified.retain(|notified| notified.kind != kind);
+        count != self.notified.len()
+    }
+
+    /// Queues the version until the next maintenance window, returning whether the record changed.
+    pub fn mark_pending(&mut self, version: &str) -> bool {
+        if self
+            .pending
+            .as_ref()
+            .is_some_and(|pending| pending.version == version)
+        {
+            return false;
+        }
+
+        self.pending = Some(Pending {
+            version: version.to_owned(),
+            since: unix_timestamp(),
+        });
+        true
+    }
+}
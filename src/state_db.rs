@@ -0,0 +1,200 @@
+use crate::{
+    cli::{Config, ReportCommand},
+    failure,
+    metrics::Report,
+    state,
+};
+use anyhow::{anyhow, Context, Result};
+use rusqlite::{params, Connection};
+use std::path::Path;
+
+const SCHEMA: &str = "CREATE TABLE IF NOT EXISTS runs (
+    id INTEGER PRIMARY KEY,
+    started_at INTEGER NOT NULL,
+    host TEXT,
+    command TEXT NOT NULL,
+    outcome TEXT NOT NULL,
+    switched INTEGER NOT NULL,
+    installed_version TEXT,
+    published_version TEXT,
+    previous_version TEXT,
+    download_bytes INTEGER NOT NULL,
+    duration_ms INTEGER NOT NULL,
+    error TEXT
+);
+";
+
+/// Appends a run of `command` to the `--state-db` database, creating it when missing.
+/// `outcome` is the result name also exported as trace status.
+pub async fn record(
+    config: &Config,
+    command: &str,
+    report: &Report,
+    outcome: Result<&str, &anyhow::Error>,
+) -> Result<()> {
+    let Some(state_db) = &config.state_db else {
+        return Ok(());
+    };
+
+    let Report {
+        installed_version,
+        published_version,
+        previous_version,
+        download_bytes,
+        duration,
+        ..
+    } = report;
+    // A switch from an earlier release leaves that release as previous version.
+    let switched = matches!(outcome, Ok("updated" | "installed"))
+        || (outcome.is_ok() && previous_version.is_some());
+    let started_at = state::unix_timestamp().saturating_sub(duration.as_secs());
+    let version = |version: &Option<semver::Version>| version.as_ref().map(ToString::to_string);
+    let row = (
+        started_at,
+        failure::hostname(),
+        command.to_owned(),
+        outcome.unwrap_or("failed").to_owned(),
+        switched,
+        version(installed_version),
+        version(published_version),
+        version(previous_version),
+        *download_bytes,
+        duration.as_millis() as u64,
+        outcome.err().map(|e| format!("{:#}", e)),
+    );
+
+    let state_db = state_db.clone();
+    tokio::task::spawn_blocking(move || -> Result<()> {
+        open(&state_db)?.execute(
+            "INSERT INTO runs (started_at, host, command, outcome, switched, installed_version, published_version, \
+             previous_version, download_bytes, duration_ms, error) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+            params![
+                row.0, row.1, row.2, row.3, row.4, row.5, row.6, row.7, row.8, row.9, row.10
+            ],
+        )?;
+        Ok(())
+    })
+    .await??;
+
+    Ok(())
+}
+
+/// Per-host summary of the runs in the database.
+struct HostRuns {
+    host: Option<String>,
+    last_run: u64,
+    last_outcome: String,
+    last_update: Option<u64>,
+    last_version: Option<String>,
+    runs: u64,
+    failures: u64,
+    updates: u64,
+}
+
+/// Prints for every host in the `--state-db` database when it last ran and updated, and how its recent runs went.
+pub async fn report(config: &Config, command: &ReportCommand) -> Result<()> {
+    let ReportCommand { days } = command;
+
+    let state_db = config
+        .state_db
+        .as_ref()
+        .ok_or_else(|| anyhow!("no database to report on - give it with --state-db"))?;
+    if !state_db.exists() {
+        return Err(anyhow!(
+            "no runs recorded in {} yet",
+            state_db.to_string_lossy()
+        ));
+    }
+
+    let window_start = state::unix_timestamp().saturating_sub(days * 24 * 60 * 60);
+    let hosts = {
+        let state_db = state_db.clone();
+        tokio::task::spawn_blocking(move || -> Result<Vec<HostRuns>> {
+            let connection = open(&state_db)?;
+            let mut statement = connection.prepare(
+                "SELECT
+    host,
+    MAX(started_at),
+    (SELECT outcome FROM runs latest WHERE latest.host IS runs.host ORDER BY id DESC LIMIT 1),
+    (SELECT MAX(started_at) FROM runs updates WHERE updates.host IS runs.host AND switched),
+    (SELECT installed_version FROM runs updates WHERE updates.host IS runs.host AND switched ORDER BY id DESC LIMIT 1),
+    SUM(started_at >= ?1),
+    SUM(started_at >= ?1 AND outcome = 'failed'),
+    SUM(started_at >= ?1 AND switched)
+FROM runs GROUP BY host ORDER BY host",
+            )?;
+            let hosts = statement
+                .query_map(params![window_start], |row| {
+                    Ok(HostRuns {
+                        host: row.get(0)?,
+                        last_run: row.get(1)?,
+                        last_outcome: row.get(2)?,
+                        last_update: row.get(3)?,
+                        last_version: row.get(4)?,
+                        runs: row.get(5)?,
+                        failures: row.get(6)?,
+                        updates: row.get(7)?,
+                    })
+                })?
+                .collect::<rusqlite::Result<_>>()?;
+            Ok(hosts)
+        })
+        .await??
+    };
+
+    println!(
+        "📒 Runs recorded in {}, counted over the last {} day(s)",
+        state_db.to_string_lossy(),
+        days
+    );
+    let now = state::unix_timestamp();
+    let ago = |timestamp: u64| {
+        humantime::format_duration(std::time::Duration::from_secs(
+            now.saturating_sub(timestamp),
+        ))
+    };
+    for HostRuns {
+        host,
+        last_run,
+        last_outcome,
+        last_update,
+        last_version,
+        runs,
+        failures,
+        updates,
+    } in hosts
+    {
+        println!();
+        println!("🖥️ {}", host.as_deref().unwrap_or("unknown host"));
+        println!(
+            "   Last run: {} ({} ago), {}",
+            state::format_timestamp(last_run),
+            ago(last_run),
+            last_outcome
+        );
+        match last_update {
+            Some(last_update) => println!(
+                "   Last update: to {} at {} ({} ago)",
+                last_version.as_deref().unwrap_or("unknown version"),
+                state::format_timestamp(last_update),
+                ago(last_update)
+            ),
+            None => println!("   Last update: none recorded"),
+        }
+        println!(
+            "   Runs: {}, failed: {}, updates: {}",
+            runs, failures, updates
+        );
+    }
+
+    Ok(())
+}
+
+/// Opens the database, creating it and its table when missing.
+fn open(db: &Path) -> Result<Connection> {
+    let connection =
+        Connection::open(db).with_context(|| format!("failed to open {}", db.to_string_lossy()))?;
+    connection.execute_batch(SCHEMA)?;
+
+    Ok(connection)
+}
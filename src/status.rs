@@ -0,0 +1,284 @@
+use crate::{
+    cli::{self, Config, StatusCommand},
+    local, preserve, server_process,
+    state::{self, format_timestamp, Hold, Incomplete, LastCheck, MirrorBackoff, Pending, Staged},
+};
+use anyhow::Result;
+use semver::Version;
+use serde::Serialize;
+use std::path::PathBuf;
+
+#[derive(Serialize)]
+pub struct Status {
+    install_mode: String,
+    active_version: Option<String>,
+    /// Where the symlink points to, in the releases layout.
+    symlink_target: Option<PathBuf>,
+    releases: Vec<Release>,
+    /// Files of the active release which differ from its manifest and are not listed in `--preserve`.
+    local_modifications: Vec<String>,
+    last_check: Option<LastCheck>,
+    pending: Option<Pending>,
+    hold: Option<Hold>,
+    /// Release which extraction failed or got interrupted.
+    incomplete: Option<Incomplete>,
+    /// Release installed by `--stage-only`, waiting for `commit`.
+    staged: Option<Staged>,
+    /// Mirror asked to come back later, update runs leave it alone until then.
+    mirror_backoff: Option<MirrorBackoff>,
+    daemon: Option<Daemon>,
+}
+
+#[derive(Serialize)]
+struct Release {
+    version: String,
+    path: PathBuf,
+    size: u64,
+    active: bool,
+}
+
+#[derive(Serialize)]
+struct Daemon {
+    pid: u32,
+    /// `None` when liveness of processes can't be checked on this platform.
+    running: Option<bool>,
+    started_at: u64,
+    next_check_at: u64,
+}
+
+pub async fn run(config: &Config, command: &StatusCommand) -> Result<()> {
+    let status = collect(config).await?;
+
+    if command.json {
+        println!("{}", serde_json::to_string_pretty(&status)?);
+    } else {
+        print(&status);
+    }
+
+    Ok(())
+}
+
+pub async fn collect(config: &Config) -> Result<Status> {
+    let Config {
+        symlink_path,
+        install_mode,
+        ..
+    } = config;
+
+    let active_version = local::installed_version(config).await.ok().flatten();
+    let in_place = local::in_place_dir(config).is_some();
+    let symlink_target = if in_place {
+        None
+    } else {
+        tokio::fs::read_link(symlink_path).await.ok()
+    };
+
+    let releases = match local::in_place_dir(config) {
+        Some(target_dir) => match &active_version {
+            Some(version) => vec![release(target_dir.to_owned(), version, true).await?],
+            None => vec![],
+        },
+        None => releases(config, active_version.as_ref()).await?,
+    };
+
+    let local_modifications = match &active_version {
+        Some(version) => preserve::unpreserved_modifications(config, version).await?,
+        None => vec![],
+    };
+
+    let mut state = state::State::load(config).await?;
+    let daemon = state.daemon.take().map(|daemon| Daemon {
+        running: server_process::is_alive(daemon.pid),
+        pid: daemon.pid,
+        started_at: daemon.started_at,
+        next_check_at: daemon.next_check_at,
+    });
+
+    Ok(Status {
+        install_mode: install_mode.to_string(),
+        active_version: active_version.as_ref().map(ToString::to_string),
+        symlink_target,
+        releases,
+        local_modifications,
+        last_check: state.last_check.take(),
+        pending: state.pending.take(),
+        hold: state.hold.take(),
+        incomplete: state.incomplete.take(),
+        staged: state.staged.take(),
+        mirror_backoff: state
+            .mirror_backoff
+            .take()
+            .filter(|backoff| backoff.until > state::unix_timestamp()),
+        daemon,
+    })
+}
+
+/// Release directories, named after `--release-dir-name`, newest first.
+async fn releases(config: &Config, active_version: Option<&Version>) -> Result<Vec<Release>> {
+    let mut versions = vec![];
+    let mut read_dir = tokio::fs::read_dir(&config.releases_path).await?;
+    while let Some(entry) = read_dir.next_entry().await? {
+        let version = entry
+            .file_name()
+            .to_str()
+            .and_then(|name| local::release_version(config, name));
+
+        if let (Some(version), true) = (version, entry.file_type().await?.is_dir()) {
+            versions.push((version, entry.path()));
+        }
+    }
+    versions.sort_by(|(a, _), (b, _)| b.cmp(a));
+
+    let mut releases = vec![];
+    for (version, path) in versions {
+        let active = Some(&version) == active_version;
+        releases.push(release(path, &version, active).await?);
+    }
+
+    Ok(releases)
+}
+
+async fn release(path: PathBuf, version: &Version, active: bool) -> Result<Release> {
+    let size = {
+        let path = path.clone();
+        tokio::task::spawn_blocking(move || local::dir_size(&path)).await??
+    };
+
+    Ok(Release {
+        version: version.to_string(),
+        path,
+        size,
+        active,
+    })
+}
+
+fn print(status: &Status) {
+    cli::print_header();
+
+    match (&status.active_version, &status.symlink_target) {
+        (Some(version), Some(target)) => println!(
+            "🟢 Active version: {} ({})",
+            version,
+            target.to_string_lossy()
+        ),
+        (Some(version), None) => {
+            println!("🟢 Active version: {} ({})", version, status.install_mode)
+        }
+        (None, _) => println!("⚪ No active version"),
+    }
+
+    let total: u64 = status.releases.iter().map(|release| release.size).sum();
+    println!(
+        "📦 Releases: {} retained, {} MiB",
+        status.releases.len(),
+        total / 1024 / 1024
+    );
+    for release in &status.releases {
+        println!(
+            "   {:<12} {:>6} MiB{}",
+            release.version,
+            release.size / 1024 / 1024,
+            if release.active { "  (active)" } else { "" }
+        );
+    }
+
+    if !status.local_modifications.is_empty() {
+        println!("✏️ Local modifications not carried into new releases (see --preserve):");
+        for path in &status.local_modifications {
+            println!("   {}", path);
+        }
+    }
+
+    match &status.last_check {
+        Some(LastCheck {
+            at,
+            result,
+            published_version,
+            error,
+        }) => {
+            print!("🔎 Last check: {} - {}", format_timestamp(*at), result);
+            if let Some(published_version) = published_version {
+                print!(", latest published {}", published_version);
+            }
+            println!();
+            if let Some(error) = error {
+                println!("   ↳ {}", error);
+            }
+        }
+        None => println!("🔎 Last check: never"),
+    }
+
+    if let Some(Pending { version, since }) = &status.pending {
+        println!(
+            "⏸️ Pending update: {} since {}",
+            version,
+            format_timestamp(*since)
+        );
+    }
+
+    if let Some(Hold { since, reason }) = &status.hold {
+        print!("🔒 Updates on hold since {}", format_timestamp(*since));
+        if let Some(reason) = reason {
+            print!(": {}", reason);
+        }
+        println!();
+    }
+
+    if let Some(Incomplete {
+        version,
+        path,
+        since,
+        error,
+    }) = &status.incomplete
+    {
+        println!(
+            "🧩 Incomplete release: {} ({}), extraction started {}",
+            version,
+            path.to_string_lossy(),
+            format_timestamp(*since)
+        );
+        match error {
+            Some(error) => println!("   ↳ {}", error),
+            None => println!("   ↳ the updater was stopped before it finished"),
+        }
+    }
+
+    if let Some(Staged {
+        version,
+        token,
+        since,
+    }) = &status.staged
+    {
+        println!(
+            "🎫 Staged release: {} since {}, switch with `teamspeak-updater commit --token {}`",
+            version,
+            format_timestamp(*since),
+            token
+        );
+    }
+
+    if let Some(MirrorBackoff { until }) = &status.mirror_backoff {
+        println!(
+            "🚧 Mirror in maintenance, retrying at {}",
+            format_timestamp(*until)
+        );
+    }
+
+    match &status.daemon {
+        Some(daemon) => {
+            let state = match daemon.running {
+                Some(true) => "running",
+                Some(false) => "not running",
+                None => "unknown",
+            };
+            println!(
+                "🤖 Daemon: {} (pid {}, started {}, next check {})",
+                state,
+                daemon.pid,
+                format_timestamp(daemon.started_at),
+                format_timestamp(daemon.next_check_at)
+            );
+        }
+        None => println!("🤖 Daemon: never ran"),
+    }
+}
@@ -0,0 +1,230 @@
+use crate::{cli::Config, events::PhaseFailed, metrics::Report, update::Outcome};
+use anyhow::Result;
+use std::{fmt::Display, str::FromStr};
+use thiserror::Error;
+
+/// Identifier the entries are tagged with, so `journalctl -t teamspeak-updater` finds them.
+const IDENTIFIER: &str = "teamspeak-updater";
+
+/// Where `--system-log` sends the result of every run.
+#[derive(Clone, Copy)]
+pub enum SystemLog {
+    Syslog,
+    Journald,
+}
+
+#[derive(Debug, Error)]
+pub enum SystemLogError {
+    #[error("system log not recognized: {0} (expected `syslog` or `journald`)")]
+    NotRecognized(String),
+}
+
+impl FromStr for SystemLog {
+    type Err = SystemLogError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "syslog" => Ok(Self::Syslog),
+            "journald" => Ok(Self::Journald),
+            _ => Err(SystemLogError::NotRecognized(s.to_owned())),
+        }
+    }
+}
+
+impl Display for SystemLog {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Syslog => "syslog",
+            Self::Journald => "journald",
+        })
+    }
+}
+
+/// Severities of syslog(3), which journald's `PRIORITY` field shares.
+#[derive(Clone, Copy)]
+enum Priority {
+    Error = 3,
+    Notice = 5,
+    Info = 6,
+}
+
+/// Entry describing the result of a run, with the structured fields journald indexes.
+struct Entry {
+    priority: Priority,
+    message: String,
+    fields: Vec<(&'static str, String)>,
+}
+
+/// Writes the result of a run into syslog or the systemd journal, with a priority matching its outcome.
+pub async fn write(config: &Config, report: &Report, result: &Result<Outcome>) -> Result<()> {
+    let system_log = match config.system_log {
+        Some(system_log) => system_log,
+        None => return Ok(()),
+    };
+
+    let entry = entry(report, result);
+    match system_log {
+        SystemLog::Syslog => unix::syslog(&entry).await,
+        SystemLog::Journald => unix::journald(&entry).await,
+    }
+}
+
+fn entry(report: &Report, result: &Result<Outcome>) -> Entry {
+    let version = |version: &Option<semver::Version>| {
+        version
+            .as_ref()
+            .map_or_else(|| String::from("unknown"), ToString::to_string)
+    };
+
+    let mut fields = vec![];
+    if let Some(from_version) = &report.previous_version {
+        fields.push(("FROM_VERSION", from_version.to_string()));
+    }
+    if let Some(to_version) = &report.published_version {
+        fields.push(("TO_VERSION", to_version.to_string()));
+    }
+
+    let (priority, outcome, message) = match result {
+        Ok(Outcome::Updated) => (
+            Priority::Notice,
+            "updated",
+            match &report.changes {
+                Some(changes) => format!(
+                    "TeamSpeak updated from {} to {}. Files: {}.",
+                    version(&report.previous_version),
+                    version(&report.published_version),
+                    changes.summary()
+                ),
+                None => format!(
+                    "TeamSpeak updated from {} to {}.",
+                    version(&report.previous_version),
+                    version(&report.published_version)
+                ),
+            },
+        ),
+        Ok(Outcome::NoAction) => (
+            Priority::Info,
+            "no_action",
+            format!(
+                "TeamSpeak {} is up to date.",
+                version(&report.installed_version)
+            ),
+        ),
+        Ok(Outcome::Pending) => (
+            Priority::Info,
+            "pending",
+            format!(
+                "Update to TeamSpeak {} is pending.",
+                version(&report.published_version)
+            ),
+        ),
+        Err(e) => {
+            if let Some(PhaseFailed(phase)) = e.downcast_ref::<PhaseFailed>() {
+                fields.push(("PHASE", phase.clone()));
+            }
+            (
+                Priority::Error,
+                "failed",
+                format!("TeamSpeak update failed: {:#}", e),
+            )
+        }
+    };
+    fields.push(("OUTCOME", outcome.to_owned()));
+
+    Entry {
+        priority,
+        message,
+        fields,
+    }
+}
+
+#[cfg(unix)]
+mod unix {
+    use super::{Entry, IDENTIFIER};
+    use anyhow::{anyhow, Result};
+    use std::path::Path;
+    use tokio::net::UnixDatagram;
+
+    /// Native protocol socket of systemd-journald.
+    const JOURNALD_SOCKET: &str = "/run/systemd/journal/socket";
+    /// Sockets syslog daemons listen on, Linux and the BSDs first, then macOS.
+    const SYSLOG_SOCKETS: &[&str] = &["/dev/log", "/var/run/log", "/var/run/syslog"];
+    /// `LOG_DAEMON` facility of syslog(3).
+    const FACILITY_DAEMON: u8 = 3;
+
+    /// Sends the entry to journald, with the structured fields next to `MESSAGE` and `PRIORITY`.
+    pub async fn journald(entry: &Entry) -> Result<()> {
+        let mut datagram = vec![];
+        let mut field = |name: &str, value: &str| {
+            datagram.extend_from_slice(name.as_bytes());
+            // Values spanning lines are sent length-prefixed instead of after `=`.
+            if value.contains('\n') {
+                datagram.push(b'\n');
+                datagram.extend_from_slice(&(value.len() as u64).to_le_bytes());
+            } else {
+                datagram.push(b'=');
+            }
+            datagram.extend_from_slice(value.as_bytes());
+            datagram.push(b'\n');
+        };
+
+        field("MESSAGE", &entry.message);
+        field("PRIORITY", &(entry.priority as u8).to_string());
+        field("SYSLOG_IDENTIFIER", IDENTIFIER);
+        for (name, value) in &entry.fields {
+            field(name, value);
+        }
+
+        send(Path::new(JOURNALD_SOCKET), &datagram).await
+    }
+
+    /// Sends the entry to the local syslog daemon in the BSD format, with the fields appended as `NAME=value`.
+    pub async fn syslog(entry: &Entry) -> Result<()> {
+        let socket = SYSLOG_SOCKETS
+            .iter()
+            .map(Path::new)
+            .find(|socket| socket.exists())
+            .ok_or_else(|| anyhow!("no syslog socket found at {}", SYSLOG_SOCKETS.join(", ")))?;
+
+        let mut message = entry.message.replace('\n', " ");
+        for (name, value) in &entry.fields {
+            message.push_str(&format!(" {}={}", name, value));
+        }
+        let line = format!(
+            "<{}>{} {}[{}]: {}",
+            FACILITY_DAEMON * 8 + entry.priority as u8,
+            chrono::Local::now().format("%b %e %H:%M:%S"),
+            IDENTIFIER,
+            std::process::id(),
+            message
+        );
+
+        send(socket, line.as_bytes()).await
+    }
+
+    async fn send(socket: &Path, datagram: &[u8]) -> Result<()> {
+        let sender = UnixDatagram::unbound()?;
+        sender
+            .send_to(datagram, socket)
+            .await
+            .map_err(|e| anyhow!("{}: {}", socket.to_string_lossy(), e))?;
+
+        Ok(())
+    }
+}
+
+#[cfg(not(unix))]
+mod unix {
+    use super::Entry;
+    use anyhow::{anyhow, Result};
+
+    pub async fn journald(_: &Entry) -> Result<()> {
+        Err(anyhow!("journald is only available on Linux"))
+    }
+
+    pub async fn syslog(_: &Entry) -> Result<()> {
+        Err(anyhow!(
+            "syslog is only available on Unix, use --event-log on Windows"
+        ))
+    }
+}
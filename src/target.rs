@@ -1,6 +1,12 @@
-use std::{fmt::Display, str::FromStr};
+use std::{fmt::Display, str::FromStr, sync::OnceLock};
 use thiserror::Error;
 
+pub const DEFAULT_FILENAME_TEMPLATE: &str = "teamspeak3-server_{target}-{version}.{ext}";
+
+/// Why the tuple deduced for this host may be wrong, if the detection signals disagreed.
+static DEDUCTION_DOUBT: OnceLock<String> = OnceLock::new();
+
+#[derive(Clone, Copy, PartialEq, Eq)]
 pub enum Tuple {
     WindowsX86,
     WindowsX8664,
@@ -11,14 +17,23 @@ pub enum Tuple {
     LinuxX86,
 }
 
+/// Target tuple given on the command line, where `all` stands for every tuple.
+pub enum Selection {
+    All,
+    Tuple(Tuple),
+}
+
 #[derive(Debug, Error)]
 pub enum TupleError {
     #[error("target tuple not recognized: {0}")]
     NotRecognized(String),
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ArchiveType {
     Bzip2Tarball,
+    GzipTarball,
+    XzTarball,
     Zip,
 }
 
@@ -26,9 +41,21 @@ impl ArchiveType {
     fn extension(&self) -> &'static str {
         match &self {
             Self::Bzip2Tarball => "tar.bz2",
+            Self::GzipTarball => "tar.gz",
+            Self::XzTarball => "tar.xz",
             Self::Zip => "zip",
         }
     }
+
+    /// Bytes every archive of the type starts with.
+    pub fn magic(&self) -> &'static [u8] {
+        match &self {
+            Self::Bzip2Tarball => b"BZh",
+            Self::GzipTarball => &[0x1f, 0x8b],
+            Self::XzTarball => &[0xfd, b'7', b'z', b'X', b'Z', 0x00],
+            Self::Zip => b"PK\x03\x04",
+        }
+    }
 }
 
 impl Display for ArchiveType {
@@ -54,7 +81,28 @@ impl FromStr for Tuple {
     }
 }
 
+impl FromStr for Selection {
+    type Err = TupleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "all" => Ok(Self::All),
+            _ => s.parse().map(Self::Tuple),
+        }
+    }
+}
+
 impl Tuple {
+    pub const ALL: [Self; 7] = [
+        Self::WindowsX86,
+        Self::WindowsX8664,
+        Self::LinuxX8664,
+        Self::Mac,
+        Self::FreeBSDX8664,
+        Self::LinuxAlpine,
+        Self::LinuxX86,
+    ];
+
     fn target_string(&self) -> &'static str {
         match &self {
             Self::LinuxAlpine => "linux_alpine",
@@ -67,13 +115,25 @@ impl Tuple {
         }
     }
 
-    pub fn archive_filename(&self, version: &semver::Version) -> String {
-        format!(
-            "teamspeak3-server_{}-{}.{}",
-            self.target_string(),
-            version,
-            self.archive_type().extension()
-        )
+    pub fn archive_filename(&self, template: &str, version: &semver::Version) -> String {
+        template
+            .replace("{version}", &version.to_string())
+            .replace("{target}", self.target_string())
+            .replace("{ext}", self.archive_type().extension())
+    }
+
+    /// Reverse of [`Self::archive_filename`], `None` if the name doesn't belong to this tuple.
+    pub fn archive_version(&self, template: &str, file_name: &str) -> Option<semver::Version> {
+        let pattern = template
+            .replace("{target}", self.target_string())
+            .replace("{ext}", self.archive_type().extension());
+        let (prefix, suffix) = pattern.split_once("{version}")?;
+
+        file_name
+            .strip_prefix(prefix)?
+            .strip_suffix(suffix)?
+            .parse()
+            .ok()
     }
 
     pub fn archive_type(&self) -> ArchiveType {
@@ -83,7 +143,13 @@ impl Tuple {
         }
     }
 
-    pub fn deduce() -> Self {
+    /// Detects the tuple matching the host, `None` if the host is not supported by TeamSpeak.
+    pub fn detect() -> Option<Self> {
+        Self::detect_with_doubt().map(|(tuple, _)| tuple)
+    }
+
+    fn detect_with_doubt() -> Option<(Self, Option<String>)> {
+        let mut doubt = None;
         let tuple_str = if cfg!(all(target_os = "windows", target_arch = "x86_64")) {
             "win64"
         } else if cfg!(all(target_os = "windows", target_arch = "x86")) {
@@ -91,7 +157,14 @@ impl Tuple {
         } else if cfg!(target_os = "macos") {
             "mac"
         } else if cfg!(all(target_os = "linux", target_arch = "x86_64")) {
-            "linux_amd64"
+            // Glibc builds don't run on musl-based hosts like Alpine, which have their own package.
+            let (musl, musl_doubt) = musl_host();
+            doubt = musl_doubt;
+            if musl {
+                "linux_alpine"
+            } else {
+                "linux_amd64"
+            }
         } else if cfg!(all(target_os = "linux", target_arch = "x86")) {
             "linux_x86"
         } else if cfg!(target_os = "freebsd") {
@@ -100,7 +173,14 @@ impl Tuple {
             "not supported"
         };
 
-        if let Ok(tuple) = Self::from_str(tuple_str) {
+        Self::from_str(tuple_str).ok().map(|tuple| (tuple, doubt))
+    }
+
+    pub fn deduce() -> Self {
+        if let Some((tuple, doubt)) = Self::detect_with_doubt() {
+            if let Some(doubt) = doubt {
+                let _ = DEDUCTION_DOUBT.set(doubt);
+            }
             tuple
         } else {
             panic!("failed to deduce target tuple - you need to provide it by yourself.");
@@ -108,6 +188,53 @@ impl Tuple {
     }
 }
 
+/// Reason to doubt the tuple deduced for this host, reported as a configuration warning.
+pub fn deduction_doubt() -> Option<&'static str> {
+    DEDUCTION_DOUBT.get().map(String::as_str)
+}
+
+/// Whether the host uses musl libc, along with a doubt when the signals are inconclusive.
+fn musl_host() -> (bool, Option<String>) {
+    use std::path::Path;
+
+    const MUSL_LOADER: &str = "/lib/ld-musl-x86_64.so.1";
+    const GLIBC_LOADER: &str = "/lib64/ld-linux-x86-64.so.2";
+
+    let alpine = std::fs::read_to_string("/etc/os-release")
+        .map(|os_release| {
+            os_release.lines().any(|line| {
+                let line = line.replace('"', "");
+                line == "ID=alpine" || (line.starts_with("ID_LIKE=") && line.contains("alpine"))
+            })
+        })
+        .unwrap_or(false);
+
+    match (
+        Path::new(MUSL_LOADER).exists(),
+        Path::new(GLIBC_LOADER).exists(),
+    ) {
+        (true, false) => (true, None),
+        (false, true) => (false, None),
+        (true, true) => (
+            alpine,
+            Some(format!(
+                "both musl and glibc loaders are present, {} was picked based on /etc/os-release",
+                if alpine { "musl" } else { "glibc" }
+            )),
+        ),
+        (false, false) => {
+            let musl = alpine || cfg!(target_env = "musl");
+            (
+                musl,
+                Some(format!(
+                    "neither musl nor glibc loader was found, assumed {}",
+                    if musl { "musl" } else { "glibc" }
+                )),
+            )
+        }
+    }
+}
+
 impl Display for Tuple {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.write_str(self.target_string())
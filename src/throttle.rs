@@ -0,0 +1,88 @@
+use std::{
+    fmt::Display,
+    str::FromStr,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+use thiserror::Error;
+
+/// Transfer rate in bytes per second, written as e.g. `500K` or `2M`.
+#[derive(Clone, Copy)]
+pub struct Rate(u64);
+
+#[derive(Debug, Error)]
+pub enum RateError {
+    #[error("rate not recognized: {0} (expected e.g. `500K` or `2M`)")]
+    NotRecognized(String),
+}
+
+impl FromStr for Rate {
+    type Err = RateError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let not_recognized = || RateError::NotRecognized(s.to_owned());
+        let (digits, multiplier) = match s.char_indices().last() {
+            Some((index, unit)) if unit.is_ascii_alphabetic() => {
+                let multiplier = match unit.to_ascii_lowercase() {
+                    'k' => 1 << 10,
+                    'm' => 1 << 20,
+                    'g' => 1 << 30,
+                    _ => return Err(not_recognized()),
+                };
+                (&s[..index], multiplier)
+            }
+            _ => (s, 1),
+        };
+
+        digits
+            .parse::<u64>()
+            .ok()
+            .and_then(|value| value.checked_mul(multiplier))
+            .filter(|bytes| *bytes > 0)
+            .map(Self)
+            .ok_or_else(not_recognized)
+    }
+}
+
+impl Display for Rate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.0 {
+            bytes if bytes % (1 << 20) == 0 => write!(f, "{}M", bytes >> 20),
+            bytes if bytes % (1 << 10) == 0 => write!(f, "{}K", bytes >> 10),
+            bytes => write!(f, "{}", bytes),
+        }
+    }
+}
+
+/// Keeps the combined throughput of all download connections under the configured rate.
+pub struct Throttle {
+    rate: Option<Rate>,
+    started_at: Instant,
+    transferred: Mutex<u64>,
+}
+
+impl Throttle {
+    pub fn new(rate: Option<Rate>) -> Self {
+        Self {
+            rate,
+            started_at: Instant::now(),
+            transferred: Mutex::new(0),
+        }
+    }
+
+    /// Accounts for `bytes` just received and sleeps until they fit into the rate.
+    pub async fn consume(&self, bytes: usize) {
+        let Some(Rate(rate)) = self.rate else {
+            return;
+        };
+
+        let transferred = {
+            let mut transferred = self.transferred.lock().expect("throttle lock is poisoned");
+            *transferred += bytes as u64;
+            *transferred
+        };
+
+        let due_at = self.started_at + Duration::from_secs_f64(transferred as f64 / rate as f64);
+        tokio::time::sleep_until(due_at.into()).await;
+    }
+}
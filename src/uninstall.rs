@@ -0,0 +1,84 @@
+use crate::{
+    cli::{self, Config, UninstallCommand},
+    lint, local, privileged, state,
+};
+use anyhow::{anyhow, Result};
+use std::path::{Path, PathBuf};
+
+pub async fn run(config: &mut Config, command: &UninstallCommand) -> Result<()> {
+    use std::io::ErrorKind;
+    use tokio::fs;
+
+    cli::print_header();
+    lint::validate(config)?;
+    let config = &*config;
+
+    if local::in_place_dir(config).is_some() {
+        return Err(anyhow!(
+            "in-place installs keep a single release - there is nothing to uninstall"
+        ));
+    }
+
+    let version = &command.version;
+    let release_path = local::release_path(config, version)?;
+    match fs::symlink_metadata(&release_path).await {
+        Ok(metadata) if metadata.is_dir() => {}
+        Ok(_) => {
+            return Err(anyhow!(
+                "{} is not a release directory",
+                release_path.to_string_lossy()
+            ))
+        }
+        Err(e) if e.kind() == ErrorKind::NotFound => {
+            return Err(anyhow!("release {} is not installed", version))
+        }
+        Err(e) => return Err(e.into()),
+    }
+
+    let active_release = fs::canonicalize(&config.symlink_path).await.ok();
+    if active_release.as_deref() == Some(release_path.as_path()) {
+        return Err(anyhow!(
+            "release {} is active - switch {} to another release first",
+            version,
+            config.symlink_path.to_string_lossy()
+        ));
+    }
+
+    let size = {
+        let release_path = release_path.clone();
+        tokio::task::spawn_blocking(move || local::dir_size(&release_path)).await??
+    };
+    let backups = backup_links(config, &release_path).await?;
+    privileged::remove_tree(config, &release_path).await?;
+    println!(
+        "🗑️ Removed release {} from {} ({} MiB)",
+        version,
+        release_path.to_string_lossy(),
+        size / 1024 / 1024
+    );
+
+    for backup in backups {
+        privileged::remove_symlink(config, &backup).await?;
+        println!("🧹 Removed old symlink {}", backup.to_string_lossy());
+    }
+
+    let mut state = state::State::load(config).await?;
+    if state.forget(&version.to_string()) {
+        state.save(config).await?;
+        println!("🧹 Removed {} from the updater state", version);
+    }
+
+    Ok(())
+}
+
+/// Symlinks saved as `<symlink>.<timestamp>` by earlier swaps which point to the release.
+async fn backup_links(config: &Config, release_path: &Path) -> Result<Vec<PathBuf>> {
+    let mut backups = vec![];
+    for backup in local::symlink_backups(config).await? {
+        if tokio::fs::canonicalize(&backup).await.ok().as_deref() == Some(release_path) {
+            backups.push(backup);
+        }
+    }
+
+    Ok(backups)
+}
@@ -0,0 +1,905 @@
+use crate::{
+    adopt, artifact, changes,
+    cli::{self, Config},
+    database, delta, event_log,
+    events::{self, PhaseTimedOut},
+    failure, fleet, hardlink,
+    hooks::{self, Hook},
+    journal::Journal,
+    libraries, license, lint, local, lock, manifest,
+    metrics::{self, Report},
+    otlp, overlay, ownership, permissions, preserve, remote, selinux, server_process, service,
+    shared_config, snapshot, state, state_db, system_log,
+};
+use anyhow::Result;
+use std::process::exit;
+
+/// Returns the installed version (if any) and the latest version published on the mirror.
+pub async fn fetch_versions(
+    config: &Config,
+    http: &reqwest::Client,
+) -> Result<(Option<semver::Version>, semver::Version)> {
+    tokio::try_join!(
+        local::installed_version(config),
+        remote::latest_version(config, http)
+    )
+}
+
+async fn determine_teamspeak_versions(
+    config: &Config,
+    http: &reqwest::Client,
+) -> Result<(Option<semver::Version>, semver::Version)> {
+    println!("⏳ Checking for updates...");
+    if config.offline {
+        println!("📴 Offline mode - using the remote version cached by the last online run");
+    } else if let Some(state::MirrorBackoff { until }) =
+        state::State::load(config).await?.mirror_backoff
+    {
+        // Runs started by cron before the mirror is back would only add to its load.
+        if until > state::unix_timestamp() {
+            return Err(remote::MirrorMaintenance(until).into());
+        }
+    }
+    let (last_installed_version, last_published_version) = fetch_versions(config, http).await?;
+    if let Some(version) = &last_installed_version {
+        println!(
+            "🏠 Determined locally installed TeamSpeak version: {}",
+            version
+        );
+    }
+    match config.lag {
+        0 => println!(
+            "🌐 Determined latest remote TeamSpeak version: {}",
+            last_published_version
+        ),
+        lag => println!(
+            "🌐 Determined remote TeamSpeak version {} release(s) behind the latest: {}",
+            lag, last_published_version
+        ),
+    }
+    println!();
+
+    Ok((last_installed_version, last_published_version))
+}
+
+pub const INTERRUPTED_EXIT_CODE: i32 = 130;
+
+/// Resolves on Ctrl-C, or on SIGTERM as sent by `systemctl stop` or `docker stop`.
+pub async fn shutdown_requested() -> Result<()> {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        let mut terminate = signal(SignalKind::terminate())?;
+        tokio::select! {
+            result = tokio::signal::ctrl_c() => result?,
+            _ = terminate.recv() => {}
+        }
+    }
+    #[cfg(not(unix))]
+    tokio::signal::ctrl_c().await?;
+
+    Ok(())
+}
+
+pub async fn run(config: &mut Config) -> Result<()> {
+    cli::print_header();
+    lint::validate(config)?;
+
+    let config = &*config;
+    let http = remote::client(config)?;
+
+    config.print_summary();
+    if config.ssh.is_empty() {
+        lint::check(config)?;
+    }
+    let outcome = run_once(config, &http, true).await?;
+
+    match outcome {
+        Outcome::Updated => Ok(()),
+        Outcome::NoAction | Outcome::Pending => exit(1),
+    }
+}
+
+pub enum Outcome {
+    Updated,
+    NoAction,
+    /// Update is available, but installing it is not allowed right now.
+    Pending,
+}
+
+/// Checks for an update and installs it when `install` allows, writing metrics of the run.
+/// The installation is locked meanwhile, so `clean` or another updater don't remove what this run works on.
+/// Hosts updated over `--ssh` have no local installation to lock.
+pub async fn run_once(config: &Config, http: &reqwest::Client, install: bool) -> Result<Outcome> {
+    let _lock = if config.ssh.is_empty() {
+        Some(lock::acquire(config).await?)
+    } else {
+        None
+    };
+    run_once_locked(config, http, install).await
+}
+
+/// `run_once` for callers which hold the lock of the installation already.
+pub async fn run_once_locked(
+    config: &Config,
+    http: &reqwest::Client,
+    install: bool,
+) -> Result<Outcome> {
+    let journal = Journal::default();
+    let started_at = std::time::Instant::now();
+    let mut report = Report::default();
+
+    let result = rollback_on_abort(
+        config,
+        &journal,
+        update(config, http, &journal, &mut report, install),
+    )
+    .await;
+
+    match &result {
+        Err(e) => failure::notify(config, http, &report, e).await,
+        Ok(_) => failure::resolved(config).await,
+    }
+
+    report.success = result.is_ok();
+    report.duration = started_at.elapsed();
+    let phases = events::summarize(config, report.duration);
+    let outcome = result.as_ref().map(|outcome| match outcome {
+        Outcome::Updated => "updated",
+        Outcome::NoAction => "no_action",
+        Outcome::Pending => "pending",
+    });
+    otlp::export(config, http, "update", &report, &phases, outcome).await;
+    if let Err(e) = event_log::write(config, &report, &result).await {
+        println!("⚠️ Failed to write the event log: {}", e);
+    }
+    if let Err(e) = system_log::write(config, &report, &result).await {
+        println!("⚠️ Failed to write to the system log: {}", e);
+    }
+    if let Err(e) = record_check(config, &report, &result).await {
+        println!("⚠️ Failed to record the check in the state file: {}", e);
+    }
+    if let Err(e) = state_db::record(config, "update", &report, outcome).await {
+        println!("⚠️ Failed to record the run in the state database: {}", e);
+    }
+    if let Err(e) = metrics::write_textfile(config, &report).await {
+        println!("⚠️ Failed to write metrics: {}", e);
+    }
+
+    result
+}
+
+/// Runs `work`, undoing what the journal recorded when a phase times out, or when the user interrupts or stops it
+/// and exiting.
+pub async fn rollback_on_abort<T>(
+    config: &Config,
+    journal: &Journal,
+    work: impl std::future::Future<Output = Result<T>>,
+) -> Result<T> {
+    let result = tokio::select! {
+        result = work => result,
+        Ok(()) = shutdown_requested() => {
+            println!();
+            println!("🛑 Interrupted - cleaning up partial work...");
+            journal.rollback(config).await?;
+            exit(INTERRUPTED_EXIT_CODE);
+        }
+    };
+
+    if let Err(e) = &result {
+        if let Some(PhaseTimedOut(phase, timeout)) = e.downcast_ref::<PhaseTimedOut>() {
+            println!();
+            println!(
+                "⏰ {} phase exceeded its timeout of {} - cleaning up partial work...",
+                phase, timeout
+            );
+            if let Err(e) = journal.rollback(config).await {
+                println!("⚠️ Failed to clean up after the timeout: {:#}", e);
+            }
+        }
+    }
+
+    result
+}
+
+async fn record_check(config: &Config, report: &Report, result: &Result<Outcome>) -> Result<()> {
+    use state::CheckResult;
+
+    let mut state = state::State::load(config).await?;
+    state.last_check = Some(state::LastCheck {
+        at: state::unix_timestamp(),
+        result: match result {
+            Ok(Outcome::Updated) => CheckResult::Updated,
+            Ok(Outcome::NoAction) => CheckResult::UpToDate,
+            Ok(Outcome::Pending) => CheckResult::Pending,
+            Err(_) => CheckResult::Failed,
+        },
+        published_version: report.published_version.as_ref().map(ToString::to_string),
+        error: result.as_ref().err().map(|e| format!("{:#}", e)),
+    });
+    state.mirror_backoff = result
+        .as_ref()
+        .err()
+        .and_then(remote::maintenance_retry_at)
+        .map(|until| state::MirrorBackoff { until });
+    state.save(config).await
+}
+
+/// Builds the new release from the delta directory of the mirror when `--delta` allows, from the full archive otherwise.
+pub async fn download_release(
+    config: &Config,
+    http: &reqwest::Client,
+    journal: &Journal,
+    state: &mut state::State,
+    installed_version: Option<&semver::Version>,
+    published_version: &semver::Version,
+) -> Result<remote::Download> {
+    let delta = if config.delta {
+        events::phase(
+            config,
+            "delta",
+            delta::assemble(config, http, installed_version, published_version, journal),
+        )
+        .await?
+    } else {
+        None
+    };
+    match delta {
+        Some(download) => Ok(download),
+        None => download_and_extract(config, http, journal, state, published_version).await,
+    }
+}
+
+async fn download_and_extract(
+    config: &Config,
+    http: &reqwest::Client,
+    journal: &Journal,
+    state: &mut state::State,
+    published_version: &semver::Version,
+) -> Result<remote::Download> {
+    let version = published_version.to_string();
+    let target = config.target_tuple.to_string();
+
+    // Under `--stream-extract` the archive is unpacked during the download, leaving only the move to the extract phase.
+    let extraction_dir = if remote::streams_extraction(config) {
+        Some(local::extraction_dir(config, journal)?)
+    } else {
+        None
+    };
+    let download = match events::phase(config, "download", async {
+        match &extraction_dir {
+            Some(extraction_dir) => {
+                remote::download_release_extracting(
+                    config,
+                    http,
+                    published_version,
+                    extraction_dir.clone(),
+                )
+                .await
+            }
+            None => remote::download_release(config, http, published_version).await,
+        }
+    })
+    .await
+    {
+        Err(e) if remote::is_not_found(&e) => {
+            state.mark_unavailable(&version, &target);
+            state.save(config).await?;
+            println!();
+            println!(
+                "🚨 Archive of {} for {} is missing on the mirror. Skipping this version for {}.",
+                version, target, config.unavailable_ttl
+            );
+            return Err(e);
+        }
+        download => download?,
+    };
+
+    if state.mark_available(&version, &target) {
+        state.save(config).await?;
+    }
+    let archive = download.archive.try_clone().await?;
+    extract_phase(config, journal, state, published_version, async {
+        match extraction_dir {
+            Some(extraction_dir) => {
+                local::move_extracted(extraction_dir, config, published_version, journal).await
+            }
+            None => local::extract_archive(archive, config, published_version, journal).await,
+        }
+    })
+    .await?;
+
+    Ok(download)
+}
+
+/// Runs `extract` as the extract phase. When it fails, the incomplete release is removed right away, so later runs
+/// don't mistake it for an installed one, and the failure is recorded in the state file. The record also makes
+/// the next extraction retry a removal that failed or never happened, e.g. because the updater got killed.
+pub async fn extract_phase(
+    config: &Config,
+    journal: &Journal,
+    state: &mut state::State,
+    published_version: &semver::Version,
+    extract: impl std::future::Future<Output = Result<()>>,
+) -> Result<()> {
+    remove_incomplete(config, state).await?;
+    state.incomplete = Some(state::Incomplete {
+        version: published_version.to_string(),
+        path: local::staged_release_path(config, published_version)?,
+        since: state::unix_timestamp(),
+        error: None,
+    });
+    state.save(config).await?;
+
+    let result = events::phase(config, "extract", extract).await;
+    match (&result, &mut state.incomplete) {
+        (Err(e), Some(incomplete)) => {
+            println!();
+            incomplete.error = Some(format!("{:#}", e));
+            let removed = async {
+                journal.rollback(config).await?;
+                remove_release_dir(config, incomplete).await
+            };
+            if let Err(e) = removed.await {
+                println!(
+                    "⚠️ Failed to remove the incomplete release, retrying before the next extraction: {:#}",
+                    e
+                );
+            }
+        }
+        _ => state.incomplete = None,
+    }
+    if let Err(e) = state.save(config).await {
+        println!(
+            "⚠️ Failed to record the extraction in the state file: {:#}",
+            e
+        );
+    }
+
+    result
+}
+
+/// Removes the directory of a release which extraction didn't finish in an earlier run.
+async fn remove_incomplete(config: &Config, state: &mut state::State) -> Result<()> {
+    let incomplete = match &state.incomplete {
+        Some(incomplete) => incomplete,
+        None => return Ok(()),
+    };
+
+    remove_release_dir(config, incomplete).await?;
+    state.incomplete = None;
+    state.save(config).await
+}
+
+/// Removes the directory of an incomplete release, unless it is gone already. The active release is never removed.
+async fn remove_release_dir(config: &Config, incomplete: &state::Incomplete) -> Result<()> {
+    if tokio::fs::symlink_metadata(&incomplete.path).await.is_err() {
+        return Ok(());
+    }
+    let active = local::in_place_dir(config).is_none()
+        && local::canonical_link_target(config).await.ok() == incomplete.path.canonicalize().ok();
+    if active {
+        return Ok(());
+    }
+
+    println!(
+        "🧹 Removing incomplete release {}",
+        incomplete.path.to_string_lossy()
+    );
+    tokio::fs::remove_dir_all(&incomplete.path).await?;
+
+    Ok(())
+}
+
+async fn update(
+    config: &Config,
+    http: &reqwest::Client,
+    journal: &Journal,
+    report: &mut Report,
+    install: bool,
+) -> Result<Outcome> {
+    if !config.ssh.is_empty() {
+        return fleet::update(config, http, report, install).await;
+    }
+
+    adopt::ensure_layout(config).await?;
+    let (installed_version, published_version) =
+        events::phase(config, "check", determine_teamspeak_versions(config, http)).await?;
+    report.installed_version = installed_version.clone();
+    report.published_version = Some(published_version.clone());
+
+    if installed_version.as_ref() < Some(&published_version) {
+        match &installed_version {
+            Some(installed_version) => println!(
+                "⚠️ Update available - local {}, remote {}",
+                installed_version, published_version
+            ),
+            None => println!("⚠️ Installing remote {}", published_version),
+        }
+
+        let mut state = state::State::load(config).await?;
+        let version = published_version.to_string();
+
+        if let Some(outcome) = deferred(
+            config,
+            http,
+            &mut state,
+            report,
+            &published_version,
+            install,
+        )
+        .await?
+        {
+            return Ok(outcome);
+        }
+
+        if let Err(e) = preserve::warn_unpreserved(config, installed_version.as_ref()).await {
+            println!(
+                "⚠️ Failed to check the installed release for local modifications: {:#}",
+                e
+            );
+        }
+        if config.stage_only {
+            if !is_staged(config, &state, &published_version).await? {
+                hooks::run(config, Hook::PreDownload, report, None).await?;
+                let download = download_release(
+                    config,
+                    http,
+                    journal,
+                    &mut state,
+                    installed_version.as_ref(),
+                    &published_version,
+                )
+                .await?;
+                report.download_bytes = download.size;
+                stage_release(
+                    config,
+                    report,
+                    installed_version.as_ref(),
+                    &published_version,
+                    &download,
+                )
+                .await?;
+                artifact::record(config, http, &download, &published_version).await;
+            }
+            return record_staged(config, &mut state, &published_version).await;
+        }
+
+        if is_staged(config, &state, &published_version).await? {
+            println!(
+                "📦 Release {} was installed earlier - activating it",
+                version
+            );
+            let snapshot = events::phase(
+                config,
+                "snapshot",
+                snapshot::before_update(config, installed_version.as_ref()),
+            )
+            .await?;
+            snapshot::print_before_update(config, snapshot.as_deref());
+            activate_staged(
+                config,
+                journal,
+                report,
+                installed_version.as_ref(),
+                &published_version,
+            )
+            .await?;
+            return finish_update(config, &mut state, report, published_version).await;
+        }
+
+        hooks::run(config, Hook::PreDownload, report, None).await?;
+        let download = download_release(
+            config,
+            http,
+            journal,
+            &mut state,
+            installed_version.as_ref(),
+            &published_version,
+        );
+        // The snapshot only reads the installed release, so it is taken while the new one downloads.
+        // Neither gets cancelled when the other fails, and the install waits for both.
+        let (download, snapshot) = tokio::join!(
+            download,
+            events::phase(
+                config,
+                "snapshot",
+                snapshot::before_update(config, installed_version.as_ref()),
+            )
+        );
+        let download = download?;
+        snapshot::print_before_update(config, snapshot?.as_deref());
+        report.download_bytes = download.size;
+
+        install_release(
+            config,
+            http,
+            journal,
+            report,
+            installed_version.as_ref(),
+            &published_version,
+            &download,
+        )
+        .await?;
+
+        finish_update(config, &mut state, report, published_version).await
+    } else {
+        println!("✅ You are running the newest version of TeamSpeak.");
+
+        Ok(Outcome::NoAction)
+    }
+}
+
+/// Outcome of a run which may not install `published_version` now: while updates are on hold or blacked out,
+/// the release is younger than `--min-release-age`, `install` forbids it outside of the maintenance window,
+/// or its archive is known to be missing on the mirror. `None` when the update can go ahead.
+pub async fn deferred(
+    config: &Config,
+    http: &reqwest::Client,
+    state: &mut state::State,
+    report: &Report,
+    published_version: &semver::Version,
+    install: bool,
+) -> Result<Option<Outcome>> {
+    let version = published_version.to_string();
+    let target = config.target_tuple.to_string();
+
+    if let Some(hold) = &state.hold {
+        print!(
+            "🔒 Updates are on hold since {}",
+            state::format_timestamp(hold.since)
+        );
+        if let Some(reason) = &hold.reason {
+            print!(" ({})", reason);
+        }
+        println!(" - not installing {}.", version);
+        println!("   ↳ run `teamspeak-updater unhold` to allow updates again");
+        return Ok(Some(Outcome::Pending));
+    }
+
+    if let Some(blackout) = config
+        .blackout
+        .iter()
+        .find(|blackout| blackout.contains(chrono::Local::now()))
+    {
+        println!(
+            "🚫 Updates are blacked out on {} - not installing {}.",
+            blackout, version
+        );
+        return Ok(Some(Outcome::Pending));
+    }
+
+    // Security fixes shouldn't wait for the conservative schedule of routine updates.
+    let fast_path = config.security_fast_path
+        && (config.min_release_age.is_some() || !install)
+        && remote::is_security_release(config, http, published_version).await?;
+    if fast_path {
+        println!(
+            "🛡️ {} is flagged as a security fix - installing it right away (--security-fast-path).",
+            version
+        );
+    }
+
+    if let Some(min_release_age) = config.min_release_age.as_ref().filter(|_| !fast_path) {
+        match remote::published_at(config, http, published_version).await? {
+            Some(published_at) => {
+                let age = (chrono::Utc::now() - published_at).to_std().unwrap_or_default();
+                if age < **min_release_age {
+                    println!(
+                        "🐣 {} was published {} ago - waiting until it is {} old.",
+                        version,
+                        humantime::format_duration(std::time::Duration::from_secs(age.as_secs() / 60 * 60)),
+                        min_release_age
+                    );
+                    return Ok(Some(Outcome::Pending));
+                }
+            }
+            None => println!(
+                "⚠️ The mirror doesn't tell when {} was published - not waiting for --min-release-age.",
+                version
+            ),
+        }
+    }
+
+    if !install && !fast_path {
+        let newly_pending = state.mark_pending(&version);
+        // Hooks learn about a pending version once, and the daemon reminds of it while it keeps waiting.
+        let reminder = match config.reminder_interval() {
+            Some(interval) => state
+                .digest("pending", &version, interval)
+                .or(newly_pending.then_some(0)),
+            None => newly_pending.then_some(0),
+        };
+        if newly_pending || config.reminder_interval().is_some() {
+            state.save(config).await?;
+        }
+        println!(
+            "⏸️ Update to {} is pending since {} - waiting for the maintenance window.",
+            version,
+            state::format_timestamp(state.pending.as_ref().map_or(0, |pending| pending.since))
+        );
+        if let Some(held_back) = reminder {
+            if !newly_pending {
+                println!(
+                    "🔔 Reminding of the pending update, checked {} more time(s) since the last reminder",
+                    held_back
+                );
+            }
+            if let Err(e) = hooks::run(config, Hook::Pending, report, None).await {
+                println!("⚠️ {:#}", e);
+            }
+        }
+        return Ok(Some(Outcome::Pending));
+    }
+
+    if let Some(since) = state.unavailable_since(&version, &target, config.unavailable_ttl.into()) {
+        println!(
+            "⏭️ Archive of {} for {} is missing on the mirror since {} - skipping.",
+            version,
+            target,
+            state::format_timestamp(since)
+        );
+        return Ok(Some(Outcome::NoAction));
+    }
+
+    Ok(None)
+}
+
+/// Records the release installed by `--stage-only` with a token for `commit`, keeping the token of a release
+/// staged already, so repeated runs don't invalidate the one the orchestration tooling holds.
+async fn record_staged(
+    config: &Config,
+    state: &mut state::State,
+    version: &semver::Version,
+) -> Result<Outcome> {
+    let staged = match state.staged.take() {
+        Some(staged) if staged.version == version.to_string() => staged,
+        _ => state::Staged {
+            version: version.to_string(),
+            token: state::random_token(16)?,
+            since: state::unix_timestamp(),
+        },
+    };
+    println!();
+    println!(
+        "🎫 TeamSpeak {} is staged - switch to it with `teamspeak-updater commit --token {}`",
+        version, staged.token
+    );
+    state.staged = Some(staged);
+    state.save(config).await?;
+
+    Ok(Outcome::Pending)
+}
+
+async fn finish_update(
+    config: &Config,
+    state: &mut state::State,
+    report: &mut Report,
+    published_version: semver::Version,
+) -> Result<Outcome> {
+    let staged = state
+        .staged
+        .take_if(|staged| staged.version == published_version.to_string())
+        .is_some();
+    if state.pending.take().is_some() || staged {
+        state.save(config).await?;
+    }
+
+    println!();
+    println!("✅ TeamSpeak successfully updated! ✅");
+    report.previous_version = report.installed_version.replace(published_version);
+
+    Ok(Outcome::Updated)
+}
+
+/// Whether the release was installed completely by an earlier `install`, so it only has to be activated.
+/// In-place installs have no place to keep it.
+pub async fn is_staged(
+    config: &Config,
+    state: &state::State,
+    version: &semver::Version,
+) -> Result<bool> {
+    if local::in_place_dir(config).is_some() {
+        return Ok(false);
+    }
+
+    let release_path = local::release_path(config, version)?;
+    let incomplete = state
+        .incomplete
+        .as_ref()
+        .is_some_and(|incomplete| incomplete.path == release_path);
+    if incomplete || !release_path.is_dir() {
+        return Ok(false);
+    }
+
+    Ok(manifest::read(&release_path)
+        .await?
+        .is_some_and(|manifest| manifest.version == version.to_string()))
+}
+
+/// Turns the extracted release into the active one: everything between extraction and the swap, and the artifact record.
+/// The snapshot of the installed release is up to the caller, which takes it alongside the download or extraction.
+pub async fn install_release(
+    config: &Config,
+    http: &reqwest::Client,
+    journal: &Journal,
+    report: &mut Report,
+    installed_version: Option<&semver::Version>,
+    published_version: &semver::Version,
+    download: &remote::Download,
+) -> Result<()> {
+    let old_manifest = changes::installed_manifest(config, installed_version).await;
+
+    stage_release(
+        config,
+        report,
+        installed_version,
+        published_version,
+        download,
+    )
+    .await?;
+    switch_release(
+        config,
+        journal,
+        report,
+        installed_version,
+        published_version,
+        old_manifest.as_ref(),
+    )
+    .await?;
+    artifact::record(config, http, download, published_version).await;
+
+    Ok(())
+}
+
+/// Prepares the extracted release for activation, up to checking it can run on this host.
+pub async fn stage_release(
+    config: &Config,
+    report: &mut Report,
+    installed_version: Option<&semver::Version>,
+    published_version: &semver::Version,
+    download: &remote::Download,
+) -> Result<()> {
+    hooks::run(config, Hook::PostExtract, report, None).await?;
+    events::phase(
+        config,
+        "preserve",
+        preserve::carry_over(config, installed_version, published_version),
+    )
+    .await?;
+    events::phase(config, "overlay", overlay::apply(config, published_version)).await?;
+    events::phase(
+        config,
+        "config",
+        shared_config::link(config, published_version),
+    )
+    .await?;
+    events::phase(
+        config,
+        "database",
+        database::check_plugin(config, published_version),
+    )
+    .await?;
+    manifest::write(config, published_version, download).await?;
+    events::phase(
+        config,
+        "permissions",
+        permissions::apply(config, published_version),
+    )
+    .await?;
+    events::phase(
+        config,
+        "link",
+        hardlink::link_unchanged(config, installed_version, published_version),
+    )
+    .await?;
+    events::phase(
+        config,
+        "license",
+        license::link_license(config, published_version),
+    )
+    .await?;
+    events::phase(
+        config,
+        "ownership",
+        ownership::apply(config, published_version),
+    )
+    .await?;
+    events::phase(
+        config,
+        "selinux",
+        selinux::relabel(config, published_version),
+    )
+    .await?;
+    events::phase(
+        config,
+        "libraries",
+        libraries::check(config, published_version),
+    )
+    .await
+}
+
+/// Activates a release installed earlier, e.g. by `install`: preserved paths are carried over again, as they
+/// changed since, before the server is switched to it.
+pub async fn activate_staged(
+    config: &Config,
+    journal: &Journal,
+    report: &mut Report,
+    installed_version: Option<&semver::Version>,
+    version: &semver::Version,
+) -> Result<()> {
+    let old_manifest = changes::installed_manifest(config, installed_version).await;
+
+    events::phase(
+        config,
+        "preserve",
+        preserve::carry_over(config, installed_version, version),
+    )
+    .await?;
+    // Copies of preserved files are owned by the updater until then.
+    if !config.preserve.is_empty() {
+        events::phase(config, "ownership", ownership::apply(config, version)).await?;
+    }
+    switch_release(
+        config,
+        journal,
+        report,
+        installed_version,
+        version,
+        old_manifest.as_ref(),
+    )
+    .await
+}
+
+/// Stops the server, switches to the release and starts it again, restarting the old one when the switch fails.
+async fn switch_release(
+    config: &Config,
+    journal: &Journal,
+    report: &mut Report,
+    installed_version: Option<&semver::Version>,
+    published_version: &semver::Version,
+    old_manifest: Option<&manifest::Manifest>,
+) -> Result<()> {
+    hooks::run(config, Hook::PreSwap, report, None).await?;
+    let stopped = events::phase(config, "stop", service::stop(config, installed_version)).await?;
+    if let (true, Some(installed_version)) = (stopped, installed_version) {
+        journal.stopped_server(installed_version);
+    }
+    let swapped = async {
+        events::phase(
+            config,
+            "process",
+            server_process::ensure_stopped(config, installed_version),
+        )
+        .await?;
+        events::phase(
+            config,
+            "switch",
+            local::activate_release(config, published_version, journal),
+        )
+        .await
+    }
+    .await;
+    if let Err(e) = swapped {
+        if stopped {
+            journal.started_server();
+            service::restart_after_failure(config, installed_version).await;
+        }
+        return Err(e);
+    }
+    // Interruptions from here on leave the new release active.
+    journal.commit(published_version);
+    if stopped {
+        events::phase(config, "start", service::start(config, published_version)).await?;
+        journal.started_server();
+    }
+    match changes::report(config, old_manifest, published_version).await {
+        Ok(changes) => report.changes = changes,
+        Err(e) => println!(
+            "⚠️ Failed to compare files with the previous release: {:#}",
+            e
+        ),
+    }
+    hooks::run(config, Hook::PostSwap, report, None).await
+}
@@ -0,0 +1,121 @@
+use crate::{
+    cli::{self, Config, VerifyCommand},
+    license::LICENSE_FILE_NAME,
+    lint, local, manifest,
+};
+use anyhow::{anyhow, Result};
+use std::{collections::BTreeMap, path::Path};
+
+pub async fn run(config: &mut Config, command: &VerifyCommand) -> Result<()> {
+    cli::print_header();
+    lint::validate(config)?;
+    let config = &*config;
+
+    let version = match &command.version {
+        Some(version) => version.clone(),
+        None => local::installed_version(config)
+            .await?
+            .ok_or_else(|| anyhow!("no release is installed"))?,
+    };
+    let release_path = local::release_path(config, &version)?;
+    println!(
+        "🔍 Verifying release {} in {}",
+        version,
+        release_path.to_string_lossy()
+    );
+
+    let Differences {
+        recorded,
+        modified,
+        missing,
+        extra,
+    } = differences(config, &release_path).await?.ok_or_else(|| {
+        anyhow!(
+            "release {} has no recorded file hashes - it was installed by an older version of the updater",
+            version
+        )
+    })?;
+
+    for path in &modified {
+        println!("✏️ Modified: {}", path);
+    }
+    for path in &missing {
+        println!("❌ Missing: {}", path);
+    }
+    for path in &extra {
+        println!("➕ Extra: {}", path);
+    }
+    let (modified, missing) = (modified.len(), missing.len());
+
+    println!();
+    if modified + missing > 0 {
+        return Err(anyhow!(
+            "release {} differs from its manifest: {} modified, {} missing file(s)",
+            version,
+            modified,
+            missing
+        ));
+    }
+
+    println!(
+        "✅ All {} files of release {} match the manifest.",
+        recorded, version
+    );
+
+    Ok(())
+}
+
+/// Differences between a release directory and the file hashes recorded in its manifest.
+pub struct Differences {
+    /// Number of files recorded in the manifest.
+    pub recorded: usize,
+    pub modified: Vec<String>,
+    pub missing: Vec<String>,
+    /// Files not shipped with the release. Not reported for in-place installs, where server data lives next to them.
+    pub extra: Vec<String>,
+}
+
+/// Compares the release with its manifest, returning `None` for releases installed without recorded hashes.
+pub async fn differences(config: &Config, release_path: &Path) -> Result<Option<Differences>> {
+    let recorded = match manifest::read(release_path)
+        .await?
+        .and_then(|manifest| manifest.files)
+    {
+        Some(recorded) => recorded,
+        None => return Ok(None),
+    };
+    let actual = {
+        let release_path = release_path.to_owned();
+        tokio::task::spawn_blocking(move || manifest::file_digests(&release_path))
+            .await??
+            .into_iter()
+            .collect::<BTreeMap<_, _>>()
+    };
+
+    let mut modified = vec![];
+    let mut missing = vec![];
+    for (path, digest) in &recorded {
+        match actual.get(path) {
+            Some(actual_digest) if actual_digest == digest => {}
+            Some(_) => modified.push(path.clone()),
+            None => missing.push(path.clone()),
+        }
+    }
+
+    // The target directory of in-place installs holds server data too, so only releases report extra files.
+    let extra = if local::in_place_dir(config).is_none() {
+        actual
+            .into_keys()
+            .filter(|path| !recorded.contains_key(path) && path != LICENSE_FILE_NAME)
+            .collect()
+    } else {
+        vec![]
+    };
+
+    Ok(Some(Differences {
+        recorded: recorded.len(),
+        modified,
+        missing,
+        extra,
+    }))
+}